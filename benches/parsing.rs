@@ -5,7 +5,7 @@ extern crate esplugin;
 use std::path::Path;
 
 use criterion::Criterion;
-use esplugin::{GameId, Plugin};
+use esplugin::{GameId, ParseOptions, Plugin};
 
 // Hearthfires.esm is a 3.8 MB file, so it's got plenty of content without being
 // large enough to slow down benchmarking much.
@@ -28,6 +28,27 @@ fn criterion_benchmark(c: &mut Criterion) {
         });
     });
 
+    c.bench_function("Plugin.parse_file_with_options() full in parallel", |b| {
+        let mut plugin = Plugin::new(GameId::SkyrimSE, Path::new(PLUGIN_TO_PARSE));
+        let options = ParseOptions::whole_plugin().in_parallel();
+
+        b.iter(|| {
+            assert!(plugin.parse_file_with_options(&options).is_ok());
+        });
+    });
+
+    c.bench_function("Plugin::parse_files() full", |b| {
+        let files: Vec<_> = (0..8)
+            .map(|_| (Path::new(PLUGIN_TO_PARSE), ParseOptions::whole_plugin()))
+            .collect();
+
+        b.iter(|| {
+            assert!(Plugin::parse_files(GameId::SkyrimSE, &files)
+                .iter()
+                .all(|r| r.is_ok()));
+        });
+    });
+
     c.bench_function("Plugin.overlaps_with()", |b| {
         let mut plugin = Plugin::new(GameId::SkyrimSE, Path::new(PLUGIN_TO_PARSE));
 
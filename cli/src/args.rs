@@ -0,0 +1,163 @@
+/*
+ * This file is part of esplugin
+ *
+ * Copyright (C) 2017 Oliver Hamlet
+ *
+ * esplugin is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * esplugin is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with esplugin. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::path::PathBuf;
+
+use esplugin::GameId;
+
+pub const USAGE: &str = "Usage: esplugin --game <game> [--json] <command> <plugin>...
+
+Games:
+    morrowind, oblivion, skyrim, skyrimse, fallout3, falloutnv, fallout4
+
+Commands:
+    info <plugin>               Show the plugin's header information
+    records <plugin>            List the plugin's records
+    overlaps <plugin> <plugin>  Check if two plugins edit any of the same records
+    validate <plugin>...        Check plugins for problems, giving them in load order
+    light-check <plugin>        Check if a plugin can be converted to a light master";
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Command {
+    Info(PathBuf),
+    Records(PathBuf),
+    Overlaps(PathBuf, PathBuf),
+    Validate(Vec<PathBuf>),
+    LightCheck(PathBuf),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Args {
+    pub game_id: GameId,
+    pub json: bool,
+    pub command: Command,
+}
+
+pub fn parse_args(args: &[String]) -> Result<Args, String> {
+    let mut game_id = None;
+    let mut json = false;
+    let mut positional = Vec::new();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-g" | "--game" => {
+                let game = iter.next().ok_or("No game given")?;
+                game_id = Some(parse_game_id(game).ok_or(format!("Unknown game \"{}\"", game))?);
+            }
+            "--json" => json = true,
+            _ if arg.starts_with('-') => return Err(format!("Unknown option \"{}\"", arg)),
+            _ => positional.push(arg.as_str()),
+        }
+    }
+
+    let game_id = game_id.ok_or("No game given")?;
+
+    let (command, paths) = positional.split_first().ok_or("No command given")?;
+    let paths: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+
+    let command = match (*command, paths.len()) {
+        ("info", 1) => Command::Info(paths[0].clone()),
+        ("records", 1) => Command::Records(paths[0].clone()),
+        ("overlaps", 2) => Command::Overlaps(paths[0].clone(), paths[1].clone()),
+        ("validate", n) if n > 0 => Command::Validate(paths),
+        ("light-check", 1) => Command::LightCheck(paths[0].clone()),
+        ("info", _) | ("records", _) | ("overlaps", _) | ("validate", _) | ("light-check", _) => {
+            return Err(format!("Wrong number of plugins given for \"{}\"", command))
+        }
+        _ => return Err(format!("Unknown command \"{}\"", command)),
+    };
+
+    Ok(Args {
+        game_id,
+        json,
+        command,
+    })
+}
+
+fn parse_game_id(game: &str) -> Option<GameId> {
+    match game.to_lowercase().as_str() {
+        "morrowind" => Some(GameId::Morrowind),
+        "oblivion" => Some(GameId::Oblivion),
+        "skyrim" => Some(GameId::Skyrim),
+        "skyrimse" => Some(GameId::SkyrimSE),
+        "fallout3" => Some(GameId::Fallout3),
+        "falloutnv" => Some(GameId::FalloutNV),
+        "fallout4" => Some(GameId::Fallout4),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_args_should_accept_options_before_or_after_the_command() {
+        let expected = Args {
+            game_id: GameId::SkyrimSE,
+            json: true,
+            command: Command::Info(PathBuf::from("Blank.esp")),
+        };
+
+        let parsed = parse_args(&args(&[
+            "--game",
+            "SkyrimSE",
+            "--json",
+            "info",
+            "Blank.esp",
+        ]));
+        assert_eq!(Ok(expected.clone()), parsed);
+        assert_eq!(
+            Ok(expected),
+            parse_args(&args(&["info", "Blank.esp", "--json", "-g", "skyrimse"]))
+        );
+    }
+
+    #[test]
+    fn parse_args_should_keep_the_order_of_plugins_to_validate() {
+        let parsed = parse_args(&args(&["-g", "oblivion", "validate", "B.esm", "A.esp"])).unwrap();
+
+        assert!(!parsed.json);
+        assert_eq!(
+            Command::Validate(vec![PathBuf::from("B.esm"), PathBuf::from("A.esp")]),
+            parsed.command
+        );
+    }
+
+    #[test]
+    fn parse_args_should_error_if_the_game_is_missing_or_unknown() {
+        assert!(parse_args(&args(&["info", "Blank.esp"])).is_err());
+        assert!(parse_args(&args(&["-g", "daggerfall", "info", "Blank.esp"])).is_err());
+        assert!(parse_args(&args(&["info", "Blank.esp", "-g"])).is_err());
+    }
+
+    #[test]
+    fn parse_args_should_error_if_the_command_is_unknown_or_has_the_wrong_plugin_count() {
+        assert!(parse_args(&args(&["-g", "skyrim"])).is_err());
+        assert!(parse_args(&args(&["-g", "skyrim", "list", "Blank.esp"])).is_err());
+        assert!(parse_args(&args(&["-g", "skyrim", "overlaps", "Blank.esp"])).is_err());
+        assert!(parse_args(&args(&["-g", "skyrim", "validate"])).is_err());
+        assert!(parse_args(&args(&["-g", "skyrim", "--yaml", "info", "Blank.esp"])).is_err());
+    }
+}
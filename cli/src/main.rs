@@ -0,0 +1,253 @@
+/*
+ * This file is part of esplugin
+ *
+ * Copyright (C) 2017 Oliver Hamlet
+ *
+ * esplugin is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * esplugin is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with esplugin. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate esplugin;
+#[macro_use]
+extern crate serde_json;
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use esplugin::{Error, GameId, ParseOptions, Plugin, ResolvedFormId};
+use serde_json::Value;
+
+use args::{parse_args, Args, Command, USAGE};
+
+mod args;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let args = match parse_args(&args) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    match run(&args) {
+        Ok(ref output) if args.json => match serde_json::to_string_pretty(output) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        },
+        Ok(ref output) => print!("{}", to_text(output, 0)),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn run(args: &Args) -> Result<Value, Error> {
+    match args.command {
+        Command::Info(ref path) => info(args.game_id, path),
+        Command::Records(ref path) => records(args.game_id, path),
+        Command::Overlaps(ref path, ref other_path) => overlaps(args.game_id, path, other_path),
+        Command::Validate(ref paths) => validate(args.game_id, paths),
+        Command::LightCheck(ref path) => light_check(args.game_id, path),
+    }
+}
+
+fn parse(game_id: GameId, path: &Path, options: &ParseOptions) -> Result<Plugin, Error> {
+    let mut plugin = Plugin::new(game_id, path);
+    plugin.parse_file_with_options(options)?;
+
+    Ok(plugin)
+}
+
+fn info(game_id: GameId, path: &Path) -> Result<Value, Error> {
+    let plugin = parse(game_id, path, &ParseOptions::header_only())?;
+
+    // Going through a string avoids showing f32 rounding errors, e.g. 0.94
+    // as 0.9399999976158142.
+    let header_version = plugin
+        .header_version()
+        .and_then(|v| v.to_string().parse::<f64>().ok());
+
+    Ok(json!({
+        "plugin": plugin.filename(),
+        "game": format!("{:?}", game_id),
+        "is_master": plugin.is_master_file(),
+        "is_light_master": plugin.is_light_master_file(),
+        "masters": plugin.masters()?,
+        "author": plugin.author()?,
+        "description": plugin.description()?,
+        "header_version": header_version,
+        "record_and_group_count": plugin.record_and_group_count(),
+    }))
+}
+
+// Every record's type is listed, so all records must be kept.
+fn records(game_id: GameId, path: &Path) -> Result<Value, Error> {
+    let options = ParseOptions::whole_plugin().keeping_all_records();
+    let plugin = parse(game_id, path, &options)?;
+
+    let mut records = Vec::new();
+    for record in plugin.records() {
+        let form_id = record.header().form_id();
+        let editor_id = record
+            .editor_id()
+            .or_else(|| plugin.editor_id(form_id).map(|e| e.to_string()));
+
+        records.push(json!({
+            "form_id": format!("{:08X}", form_id),
+            "plugin": plugin.resolve_form_id(form_id)?.plugin_name(),
+            "type": record.header().record_type(),
+            "editor_id": editor_id,
+        }));
+    }
+
+    Ok(Value::Array(records))
+}
+
+fn overlaps(game_id: GameId, path: &Path, other_path: &Path) -> Result<Value, Error> {
+    let plugin = parse(game_id, path, &ParseOptions::whole_plugin())?;
+    let other_plugin = parse(game_id, other_path, &ParseOptions::whole_plugin())?;
+
+    Ok(json!({
+        "plugin": plugin.filename(),
+        "other_plugin": other_plugin.filename(),
+        "overlaps": plugin.overlaps_with(&other_plugin),
+    }))
+}
+
+// Finding unused masters needs all of a plugin's records, so each plugin is
+// parsed again keeping them, one at a time, to avoid holding the records of
+// every plugin at once. The other checks only need FormIDs.
+fn validate(game_id: GameId, paths: &[PathBuf]) -> Result<Value, Error> {
+    let plugins = paths
+        .iter()
+        .map(|p| parse(game_id, p, &ParseOptions::whole_plugin()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let records_options = ParseOptions::whole_plugin().keeping_all_records();
+
+    let master_diagnostics = Plugin::validate_masters(&plugins)?;
+    let injected_records = Plugin::find_injected_records(&plugins)?;
+
+    let mut results = Vec::new();
+    for ((plugin, master_diagnostics), injected_records) in
+        plugins.iter().zip(master_diagnostics).zip(injected_records)
+    {
+        let injected_records: Vec<_> = injected_records.iter().map(form_id_to_string).collect();
+        let unused_masters = parse(game_id, plugin.path(), &records_options)?.unused_masters()?;
+
+        results.push(json!({
+            "plugin": plugin.filename(),
+            "masters": to_strings(&master_diagnostics),
+            "unused_masters": unused_masters,
+            "record_and_group_count": plugin
+                .validate_record_and_group_count()
+                .map(|m| m.to_string()),
+            "overridden_forms": to_strings(&plugin.validate_overridden_forms()?),
+            "form_ids": to_strings(&plugin.validate_form_ids()?),
+            "injected_records": injected_records,
+        }));
+    }
+
+    Ok(Value::Array(results))
+}
+
+fn light_check(game_id: GameId, path: &Path) -> Result<Value, Error> {
+    let plugin = parse(game_id, path, &ParseOptions::whole_plugin())?;
+
+    let unlisted_temporary_overrides: Vec<_> = plugin
+        .unlisted_temporary_overrides()?
+        .iter()
+        .map(form_id_to_string)
+        .collect();
+
+    Ok(json!({
+        "plugin": plugin.filename(),
+        "is_light_master": plugin.is_light_master_file(),
+        "is_valid_as_light_master": plugin.is_valid_as_light_master(),
+        "unlisted_temporary_overrides": unlisted_temporary_overrides,
+    }))
+}
+
+fn to_strings<T: ToString>(values: &[T]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+fn form_id_to_string(form_id: &ResolvedFormId) -> String {
+    format!("{:06X} ({})", form_id.object_index(), form_id.plugin_name())
+}
+
+// Writes each object field on its own line, and each array element on its
+// own line prefixed with a dash, indenting nested values.
+fn to_text(value: &Value, indent: usize) -> String {
+    let padding = " ".repeat(indent);
+
+    match *value {
+        Value::Object(ref map) => map
+            .iter()
+            .map(|(key, value)| match *value {
+                Value::Object(_) => format!("{}{}:\n{}", padding, key, to_text(value, indent + 2)),
+                Value::Array(ref array) if !array.is_empty() => {
+                    format!("{}{}:\n{}", padding, key, to_text(value, indent + 2))
+                }
+                _ => format!("{}{}: {}\n", padding, key, scalar_to_text(value)),
+            })
+            .collect(),
+        Value::Array(ref array) => array
+            .iter()
+            .map(|value| match *value {
+                Value::Object(_) | Value::Array(_) => {
+                    format!("{}-\n{}", padding, to_text(value, indent + 2))
+                }
+                _ => format!("{}- {}\n", padding, scalar_to_text(value)),
+            })
+            .collect(),
+        _ => format!("{}{}\n", padding, scalar_to_text(value)),
+    }
+}
+
+fn scalar_to_text(value: &Value) -> String {
+    match *value {
+        Value::Null => "none".to_string(),
+        Value::String(ref s) => s.clone(),
+        Value::Array(_) => "none".to_string(),
+        _ => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_text_should_write_nested_values_on_indented_lines() {
+        let value = json!([{
+            "plugin": "Blank.esp",
+            "masters": ["Blank.esm"],
+            "unused_masters": [],
+            "description": null,
+            "is_master": false,
+        }]);
+
+        assert_eq!(
+            "-\n  description: none\n  is_master: false\n  masters:\n    - Blank.esm\n  plugin: Blank.esp\n  unused_masters: none\n",
+            to_text(&value, 0)
+        );
+    }
+}
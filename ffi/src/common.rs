@@ -1,6 +1,6 @@
 use std::ffi::CString;
 
-use libc::{c_char, size_t};
+use libc::{c_char, size_t, uint32_t};
 
 #[no_mangle]
 pub unsafe extern "C" fn esp_string_free(string: *mut c_char) {
@@ -20,3 +20,12 @@ pub unsafe extern "C" fn esp_string_array_free(array: *mut *mut c_char, size: si
         esp_string_free(string);
     }
 }
+
+#[no_mangle]
+pub unsafe extern "C" fn esp_uint32_array_free(array: *mut uint32_t, size: size_t) {
+    if array.is_null() || size == 0 {
+        return;
+    }
+
+    Vec::from_raw_parts(array, size, size);
+}
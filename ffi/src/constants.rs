@@ -23,6 +23,9 @@ pub static ESP_ERROR_PARSE_ERROR: uint32_t = 5;
 #[no_mangle]
 pub static ESP_ERROR_PANICKED: uint32_t = 6;
 
+#[no_mangle]
+pub static ESP_ERROR_INDEX_OUT_OF_RANGE: uint32_t = 7;
+
 #[no_mangle]
 pub static ESP_GAME_OBLIVION: uint32_t = GameId::Oblivion as uint32_t;
 
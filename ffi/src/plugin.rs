@@ -294,3 +294,174 @@ pub unsafe extern "C" fn esp_plugin_is_valid_as_light_master(
         }
     }).unwrap_or(ESP_ERROR_PANICKED)
 }
+
+#[no_mangle]
+pub unsafe extern "C" fn esp_plugin_crc(
+    plugin_ptr: *const Plugin,
+    crc: *mut uint32_t,
+    has_crc: *mut bool,
+) -> uint32_t {
+    panic::catch_unwind(|| {
+        if plugin_ptr.is_null() || crc.is_null() || has_crc.is_null() {
+            ESP_ERROR_NULL_POINTER
+        } else {
+            let plugin = &*plugin_ptr;
+
+            match plugin.crc() {
+                Some(c) => {
+                    *crc = c;
+                    *has_crc = true;
+                }
+                None => {
+                    *crc = 0;
+                    *has_crc = false;
+                }
+            }
+
+            ESP_OK
+        }
+    }).unwrap_or(ESP_ERROR_PANICKED)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn esp_plugin_author(
+    plugin_ptr: *const Plugin,
+    author: *mut *mut c_char,
+) -> uint32_t {
+    panic::catch_unwind(|| {
+        if author.is_null() || plugin_ptr.is_null() {
+            ESP_ERROR_NULL_POINTER
+        } else {
+            let plugin = &*plugin_ptr;
+
+            let author_option = match plugin.author() {
+                Ok(x) => x.map(|a| to_c_string(&a)),
+                Err(_) => return ESP_ERROR_NOT_UTF8,
+            };
+
+            *author = author_option.unwrap_or_else(ptr::null_mut);
+
+            ESP_OK
+        }
+    }).unwrap_or(ESP_ERROR_PANICKED)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn esp_plugin_next_object_id(
+    plugin_ptr: *const Plugin,
+    next_object_id: *mut uint32_t,
+    has_next_object_id: *mut bool,
+) -> uint32_t {
+    header_u32(plugin_ptr, next_object_id, has_next_object_id, |p| {
+        p.next_object_id()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn esp_plugin_intv(
+    plugin_ptr: *const Plugin,
+    intv: *mut uint32_t,
+    has_intv: *mut bool,
+) -> uint32_t {
+    header_u32(plugin_ptr, intv, has_intv, |p| p.intv())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn esp_plugin_incc(
+    plugin_ptr: *const Plugin,
+    incc: *mut uint32_t,
+    has_incc: *mut bool,
+) -> uint32_t {
+    header_u32(plugin_ptr, incc, has_incc, |p| p.incc())
+}
+
+// Outputs the form type of each of the plugin's transient types, in the order
+// they are listed. The array must be freed using esp_uint32_array_free().
+#[no_mangle]
+pub unsafe extern "C" fn esp_plugin_transient_types(
+    plugin_ptr: *const Plugin,
+    form_types: *mut *mut uint32_t,
+    form_types_size: *mut size_t,
+) -> uint32_t {
+    panic::catch_unwind(|| {
+        if plugin_ptr.is_null() || form_types.is_null() || form_types_size.is_null() {
+            ESP_ERROR_NULL_POINTER
+        } else {
+            let plugin = &*plugin_ptr;
+
+            let vec = plugin
+                .transient_types()
+                .into_iter()
+                .map(|(form_type, _)| form_type)
+                .collect();
+
+            output_u32_array(vec, form_types, form_types_size);
+
+            ESP_OK
+        }
+    }).unwrap_or(ESP_ERROR_PANICKED)
+}
+
+// Outputs the FormIDs listed for the transient type at the given index in the
+// array output by esp_plugin_transient_types(). The array must be freed using
+// esp_uint32_array_free().
+#[no_mangle]
+pub unsafe extern "C" fn esp_plugin_transient_type_form_ids(
+    plugin_ptr: *const Plugin,
+    index: size_t,
+    form_ids: *mut *mut uint32_t,
+    form_ids_size: *mut size_t,
+) -> uint32_t {
+    panic::catch_unwind(|| {
+        if plugin_ptr.is_null() || form_ids.is_null() || form_ids_size.is_null() {
+            ESP_ERROR_NULL_POINTER
+        } else {
+            let plugin = &*plugin_ptr;
+
+            match plugin.transient_types().into_iter().nth(index) {
+                Some((_, vec)) => {
+                    output_u32_array(vec, form_ids, form_ids_size);
+                    ESP_OK
+                }
+                None => ESP_ERROR_INDEX_OUT_OF_RANGE,
+            }
+        }
+    }).unwrap_or(ESP_ERROR_PANICKED)
+}
+
+unsafe fn header_u32<F: Fn(&Plugin) -> Option<u32> + panic::UnwindSafe>(
+    plugin_ptr: *const Plugin,
+    value: *mut uint32_t,
+    has_value: *mut bool,
+    getter: F,
+) -> uint32_t {
+    panic::catch_unwind(move || {
+        if plugin_ptr.is_null() || value.is_null() || has_value.is_null() {
+            ESP_ERROR_NULL_POINTER
+        } else {
+            let plugin = &*plugin_ptr;
+
+            match getter(plugin) {
+                Some(v) => {
+                    *value = v;
+                    *has_value = true;
+                }
+                None => {
+                    *value = 0;
+                    *has_value = false;
+                }
+            }
+
+            ESP_OK
+        }
+    }).unwrap_or(ESP_ERROR_PANICKED)
+}
+
+unsafe fn output_u32_array(mut vec: Vec<u32>, array: *mut *mut uint32_t, size: *mut size_t) {
+    vec.shrink_to_fit();
+
+    *array = vec.as_mut_ptr();
+    *size = vec.len();
+
+    mem::forget(vec);
+}
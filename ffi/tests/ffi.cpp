@@ -212,6 +212,115 @@ void test_esp_plugin_is_valid_as_light_master() {
   esp_plugin_free(plugin);
 }
 
+void test_esp_plugin_crc() {
+  printf("testing esp_plugin_crc()...\n");
+  Plugin * plugin;
+  auto return_code = esp_plugin_new(&plugin, ESP_GAME_SKYRIM, "../../testing-plugins/Skyrim/Data/Blank.esm");
+  assert(return_code == ESP_OK);
+
+  return_code = esp_plugin_parse(plugin, true);
+  assert(return_code == ESP_OK);
+
+  uint32_t crc;
+  bool has_crc;
+  return_code = esp_plugin_crc(plugin, &crc, &has_crc);
+  assert(return_code == ESP_OK);
+  assert(!has_crc);
+
+  return_code = esp_plugin_parse(plugin, false);
+  assert(return_code == ESP_OK);
+
+  return_code = esp_plugin_crc(plugin, &crc, &has_crc);
+  assert(return_code == ESP_OK);
+  assert(has_crc);
+
+  esp_plugin_free(plugin);
+}
+
+void test_esp_plugin_author() {
+  printf("testing esp_plugin_author()...\n");
+  Plugin * plugin;
+  auto return_code = esp_plugin_new(&plugin, ESP_GAME_SKYRIM, "../../testing-plugins/Skyrim/Data/Blank.esm");
+  assert(return_code == ESP_OK);
+
+  return_code = esp_plugin_parse(plugin, true);
+  assert(return_code == ESP_OK);
+
+  char * author;
+  return_code = esp_plugin_author(plugin, &author);
+  assert(return_code == ESP_OK);
+  assert(author != nullptr);
+
+  esp_string_free(author);
+  esp_plugin_free(plugin);
+}
+
+void test_esp_plugin_next_object_id() {
+  printf("testing esp_plugin_next_object_id()...\n");
+  Plugin * plugin;
+  auto return_code = esp_plugin_new(&plugin, ESP_GAME_SKYRIM, "../../testing-plugins/Skyrim/Data/Blank.esm");
+  assert(return_code == ESP_OK);
+
+  return_code = esp_plugin_parse(plugin, true);
+  assert(return_code == ESP_OK);
+
+  uint32_t next_object_id;
+  bool has_next_object_id;
+  return_code = esp_plugin_next_object_id(plugin, &next_object_id, &has_next_object_id);
+  assert(return_code == ESP_OK);
+  assert(has_next_object_id);
+
+  esp_plugin_free(plugin);
+}
+
+void test_esp_plugin_intv_and_incc() {
+  printf("testing esp_plugin_intv() and esp_plugin_incc()...\n");
+  Plugin * plugin;
+  auto return_code = esp_plugin_new(&plugin, ESP_GAME_SKYRIM, "../../testing-plugins/Skyrim/Data/Blank.esm");
+  assert(return_code == ESP_OK);
+
+  return_code = esp_plugin_parse(plugin, true);
+  assert(return_code == ESP_OK);
+
+  uint32_t value;
+  bool has_value;
+  return_code = esp_plugin_intv(plugin, &value, &has_value);
+  assert(return_code == ESP_OK);
+
+  return_code = esp_plugin_incc(plugin, &value, &has_value);
+  assert(return_code == ESP_OK);
+
+  return_code = esp_plugin_intv(plugin, nullptr, &has_value);
+  assert(return_code == ESP_ERROR_NULL_POINTER);
+
+  esp_plugin_free(plugin);
+}
+
+void test_esp_plugin_transient_types() {
+  printf("testing esp_plugin_transient_types()...\n");
+  Plugin * plugin;
+  auto return_code = esp_plugin_new(&plugin, ESP_GAME_SKYRIM, "../../testing-plugins/Skyrim/Data/Blank.esm");
+  assert(return_code == ESP_OK);
+
+  return_code = esp_plugin_parse(plugin, true);
+  assert(return_code == ESP_OK);
+
+  uint32_t * form_types;
+  size_t form_types_size;
+  return_code = esp_plugin_transient_types(plugin, &form_types, &form_types_size);
+  assert(return_code == ESP_OK);
+  assert(form_types_size == 0);
+
+  esp_uint32_array_free(form_types, form_types_size);
+
+  uint32_t * form_ids;
+  size_t form_ids_size;
+  return_code = esp_plugin_transient_type_form_ids(plugin, 0, &form_ids, &form_ids_size);
+  assert(return_code == ESP_ERROR_INDEX_OUT_OF_RANGE);
+
+  esp_plugin_free(plugin);
+}
+
 int main() {
   test_game_id_values();
 
@@ -228,6 +337,11 @@ int main() {
   test_esp_plugin_count_override_records();
   test_esp_plugin_do_records_overlap();
   test_esp_plugin_is_valid_as_light_master();
+  test_esp_plugin_crc();
+  test_esp_plugin_author();
+  test_esp_plugin_next_object_id();
+  test_esp_plugin_intv_and_incc();
+  test_esp_plugin_transient_types();
 
   printf("SUCCESS\n");
   return 0;
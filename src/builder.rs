@@ -0,0 +1,439 @@
+/*
+ * This file is part of esplugin
+ *
+ * Copyright (C) 2017 Oliver Hamlet
+ *
+ * esplugin is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * esplugin is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with esplugin. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use byteorder::{ByteOrder, LittleEndian};
+use encoding::all::WINDOWS_1252;
+use encoding::{EncoderTrap, Encoding};
+
+use error::Error;
+use game_id::GameId;
+use record::{header_length, Record, RecordHeader};
+use subrecord::Subrecord;
+
+const MORROWIND_AUTHOR_LENGTH: usize = 32;
+const MORROWIND_DESCRIPTION_LENGTH: usize = 256;
+
+// Assembles the bytes of a plugin, for writing tests and tools without
+// needing existing plugin files. The header's record and group count is
+// calculated from the added groups and records. Record, subrecord and group
+// types must be four characters long.
+#[derive(Clone, PartialEq, Debug)]
+pub struct PluginBuilder {
+    game_id: GameId,
+    flags: u32,
+    version: f32,
+    author: String,
+    description: Option<String>,
+    masters: Vec<(String, u64)>,
+    next_object_id: u32,
+    header_subrecords: Vec<Subrecord>,
+    groups: Vec<GroupBuilder>,
+}
+
+impl PluginBuilder {
+    pub fn new(game_id: GameId) -> PluginBuilder {
+        PluginBuilder {
+            game_id,
+            flags: 0,
+            version: default_version(game_id),
+            author: String::new(),
+            description: None,
+            masters: Vec::new(),
+            next_object_id: 0x800,
+            header_subrecords: Vec::new(),
+            groups: Vec::new(),
+        }
+    }
+
+    // The header record's flags, e.g. 0x1 for a master file.
+    pub fn with_flags(mut self, flags: u32) -> PluginBuilder {
+        self.flags = flags;
+        self
+    }
+
+    pub fn with_version(mut self, version: f32) -> PluginBuilder {
+        self.version = version;
+        self
+    }
+
+    pub fn with_author(mut self, author: &str) -> PluginBuilder {
+        self.author = author.to_string();
+        self
+    }
+
+    pub fn with_description(mut self, description: &str) -> PluginBuilder {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    pub fn with_master(self, master: &str) -> PluginBuilder {
+        self.with_master_and_size(master, 0)
+    }
+
+    // Morrowind plugins record the file size of each master, other games
+    // write zero.
+    pub fn with_master_and_size(mut self, master: &str, size: u64) -> PluginBuilder {
+        self.masters.push((master.to_string(), size));
+        self
+    }
+
+    // Ignored for Morrowind, which has no next object ID field.
+    pub fn with_next_object_id(mut self, next_object_id: u32) -> PluginBuilder {
+        self.next_object_id = next_object_id;
+        self
+    }
+
+    // Adds a subrecord, e.g. ONAM or INTV, to the end of the header record.
+    pub fn with_header_subrecord(mut self, subrecord_type: &str, data: &[u8]) -> PluginBuilder {
+        self.header_subrecords
+            .push(Subrecord::from_data(subrecord_type, data.to_vec()));
+        self
+    }
+
+    // Adds the record to the end of the top-level group for its type,
+    // creating the group if necessary.
+    pub fn with_record(mut self, record: RecordBuilder) -> PluginBuilder {
+        let position = self
+            .groups
+            .iter()
+            .position(|g| g.group_type == 0 && g.label == record.record_type.as_bytes());
+
+        match position {
+            Some(index) => self.groups[index]
+                .contents
+                .push(GroupContent::Record(record)),
+            None => self
+                .groups
+                .push(GroupBuilder::top_level(&record.record_type.clone()).with_record(record)),
+        }
+
+        self
+    }
+
+    // Adds a top-level group. Morrowind plugins have no groups, so for them
+    // only the group's records are written.
+    pub fn with_group(mut self, group: GroupBuilder) -> PluginBuilder {
+        self.groups.push(group);
+        self
+    }
+
+    pub fn build(&self) -> Result<Vec<u8>, Error> {
+        let mut contents = Vec::new();
+        let mut count = 0;
+        for group in &self.groups {
+            count += group.write(&mut contents, self.game_id)?;
+        }
+
+        let mut output = Vec::new();
+        self.header_record(count).write(&mut output, self.game_id)?;
+        output.append(&mut contents);
+
+        Ok(output)
+    }
+
+    fn header_record(&self, record_and_group_count: u32) -> Record {
+        let mut subrecords = Vec::new();
+
+        if self.game_id == GameId::Morrowind {
+            let mut data = Vec::new();
+            write_f32(&mut data, self.version);
+            write_u32(&mut data, self.flags & 0x1);
+            data.extend(fixed_length_string(&self.author, MORROWIND_AUTHOR_LENGTH));
+            data.extend(fixed_length_string(
+                self.description.as_ref().map_or("", |d| d.as_str()),
+                MORROWIND_DESCRIPTION_LENGTH,
+            ));
+            write_u32(&mut data, record_and_group_count);
+            subrecords.push(Subrecord::from_data("HEDR", data));
+        } else {
+            let mut data = Vec::new();
+            write_f32(&mut data, self.version);
+            write_u32(&mut data, record_and_group_count);
+            write_u32(&mut data, self.next_object_id);
+            subrecords.push(Subrecord::from_data("HEDR", data));
+
+            subrecords.push(Subrecord::from_data("CNAM", zstring(&self.author)));
+            if let Some(ref description) = self.description {
+                subrecords.push(Subrecord::from_data("SNAM", zstring(description)));
+            }
+        }
+
+        for &(ref master, size) in &self.masters {
+            let mut data = vec![0; 8];
+            LittleEndian::write_u64(&mut data, size);
+
+            subrecords.push(Subrecord::from_data("MAST", zstring(master)));
+            subrecords.push(Subrecord::from_data("DATA", data));
+        }
+
+        subrecords.extend(self.header_subrecords.iter().cloned());
+
+        let record_type = match self.game_id {
+            GameId::Morrowind => "TES3",
+            _ => "TES4",
+        };
+        let header = RecordHeader::new(record_type, self.flags, 0, 0, 0, 0);
+
+        Record::new(header, subrecords, self.game_id)
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub struct GroupBuilder {
+    label: Vec<u8>,
+    group_type: u32,
+    contents: Vec<GroupContent>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+enum GroupContent {
+    Record(RecordBuilder),
+    Group(GroupBuilder),
+}
+
+impl GroupBuilder {
+    // A group of type 0, labelled with the type of the records it holds.
+    pub fn top_level(record_type: &str) -> GroupBuilder {
+        GroupBuilder {
+            label: record_type.as_bytes().to_vec(),
+            group_type: 0,
+            contents: Vec::new(),
+        }
+    }
+
+    // Child groups are labelled with a FormID, block number or grid
+    // coordinates depending on their type, which are all stored in a u32.
+    // E.g. a group of type 9 holds a cell's temporary children, and its label
+    // is the cell's FormID.
+    pub fn new(label: u32, group_type: u32) -> GroupBuilder {
+        let mut label_bytes = vec![0; 4];
+        LittleEndian::write_u32(&mut label_bytes, label);
+
+        GroupBuilder {
+            label: label_bytes,
+            group_type,
+            contents: Vec::new(),
+        }
+    }
+
+    pub fn with_record(mut self, record: RecordBuilder) -> GroupBuilder {
+        self.contents.push(GroupContent::Record(record));
+        self
+    }
+
+    pub fn with_group(mut self, group: GroupBuilder) -> GroupBuilder {
+        self.contents.push(GroupContent::Group(group));
+        self
+    }
+
+    // Returns the number of records and groups written.
+    fn write(&self, output: &mut Vec<u8>, game_id: GameId) -> Result<u32, Error> {
+        let is_morrowind = game_id == GameId::Morrowind;
+        let start = output.len();
+        let mut count = 0;
+
+        if !is_morrowind {
+            output.extend_from_slice(b"GRUP\0\0\0\0");
+            output.extend_from_slice(&self.label);
+            write_u32(output, self.group_type);
+            output.resize(start + header_length(game_id), 0);
+            count += 1;
+        }
+
+        for content in &self.contents {
+            count += match *content {
+                GroupContent::Record(ref record) => {
+                    record.build(game_id).write(output, game_id)?;
+                    1
+                }
+                GroupContent::Group(ref group) => group.write(output, game_id)?,
+            };
+        }
+
+        if !is_morrowind {
+            let size = (output.len() - start) as u32;
+            LittleEndian::write_u32(&mut output[start + 4..], size);
+        }
+
+        Ok(count)
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub struct RecordBuilder {
+    record_type: String,
+    form_id: u32,
+    flags: u32,
+    subrecords: Vec<Subrecord>,
+}
+
+impl RecordBuilder {
+    // The FormID is ignored for Morrowind, as its records don't have one.
+    pub fn new(record_type: &str, form_id: u32) -> RecordBuilder {
+        RecordBuilder {
+            record_type: record_type.to_string(),
+            form_id,
+            flags: 0,
+            subrecords: Vec::new(),
+        }
+    }
+
+    pub fn with_flags(mut self, flags: u32) -> RecordBuilder {
+        self.flags = flags;
+        self
+    }
+
+    // Adds an EDID subrecord, or a NAME subrecord for Morrowind, holding
+    // the null-terminated string.
+    pub fn with_editor_id(self, editor_id: &str, game_id: GameId) -> RecordBuilder {
+        let subrecord_type = match game_id {
+            GameId::Morrowind => "NAME",
+            _ => "EDID",
+        };
+
+        self.with_subrecord(subrecord_type, &zstring(editor_id))
+    }
+
+    pub fn with_subrecord(mut self, subrecord_type: &str, data: &[u8]) -> RecordBuilder {
+        self.subrecords
+            .push(Subrecord::from_data(subrecord_type, data.to_vec()));
+        self
+    }
+
+    pub fn build(&self, game_id: GameId) -> Record {
+        let header = RecordHeader::new(&self.record_type, self.flags, self.form_id, 0, 0, 0);
+
+        Record::new(header, self.subrecords.clone(), game_id)
+    }
+}
+
+fn default_version(game_id: GameId) -> f32 {
+    match game_id {
+        GameId::Morrowind => 1.3,
+        GameId::Oblivion | GameId::Fallout4 => 1.0,
+        GameId::Skyrim | GameId::Fallout3 => 0.94,
+        GameId::SkyrimSE => 1.7,
+        GameId::FalloutNV => 1.34,
+    }
+}
+
+fn encode(string: &str) -> Vec<u8> {
+    WINDOWS_1252
+        .encode(string, EncoderTrap::Replace)
+        .unwrap_or_default()
+}
+
+fn zstring(string: &str) -> Vec<u8> {
+    let mut data = encode(string);
+    data.push(0);
+    data
+}
+
+// Longer strings are truncated, keeping a null terminator.
+fn fixed_length_string(string: &str, length: usize) -> Vec<u8> {
+    let mut data = encode(string);
+    data.truncate(length - 1);
+    data.resize(length, 0);
+    data
+}
+
+fn write_u32(data: &mut Vec<u8>, value: u32) {
+    let start = data.len();
+    data.resize(start + 4, 0);
+    LittleEndian::write_u32(&mut data[start..], value);
+}
+
+fn write_f32(data: &mut Vec<u8>, value: f32) {
+    let start = data.len();
+    data.resize(start + 4, 0);
+    LittleEndian::write_f32(&mut data[start..], value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::Path;
+
+    use plugin::Plugin;
+
+    #[test]
+    fn build_should_write_a_plugin_with_the_given_header_data() {
+        let bytes = PluginBuilder::new(GameId::SkyrimSE)
+            .with_flags(0x1)
+            .with_description("A description")
+            .with_master("Blank.esm")
+            .build()
+            .unwrap();
+
+        let mut plugin = Plugin::new(GameId::SkyrimSE, Path::new("Test.esm"));
+        assert!(plugin.parse(&bytes, false).is_ok());
+
+        assert!(plugin.is_master_file());
+        assert_eq!(Some(1.7), plugin.header_version());
+        assert_eq!(
+            Some("A description".to_string()),
+            plugin.description().unwrap()
+        );
+        assert_eq!(vec!["Blank.esm".to_string()], plugin.masters().unwrap());
+        assert_eq!(Some(0), plugin.record_and_group_count());
+    }
+
+    #[test]
+    fn build_should_put_records_in_top_level_groups_and_count_them() {
+        let bytes = PluginBuilder::new(GameId::Skyrim)
+            .with_record(RecordBuilder::new("WEAP", 0x800).with_editor_id("A", GameId::Skyrim))
+            .with_record(RecordBuilder::new("NPC_", 0x801))
+            .with_record(RecordBuilder::new("WEAP", 0x802))
+            .with_group(
+                GroupBuilder::top_level("CELL")
+                    .with_record(RecordBuilder::new("CELL", 0x803))
+                    .with_group(
+                        GroupBuilder::new(0x803, 9).with_record(RecordBuilder::new("REFR", 0x804)),
+                    ),
+            )
+            .build()
+            .unwrap();
+
+        let mut plugin = Plugin::new(GameId::Skyrim, Path::new("Test.esp"));
+        assert!(plugin.parse(&bytes, false).is_ok());
+
+        assert_eq!(Some(9), plugin.record_and_group_count());
+        assert!(plugin.validate_record_and_group_count().is_none());
+        assert_eq!(Some(0x800), plugin.form_id_by_editor_id("A"));
+        assert_eq!(Some(9), plugin.count_records_and_groups());
+    }
+
+    #[test]
+    fn build_should_write_morrowind_records_without_groups() {
+        let bytes = PluginBuilder::new(GameId::Morrowind)
+            .with_master("Morrowind.esm")
+            .with_record(RecordBuilder::new("BOOK", 0).with_editor_id("A", GameId::Morrowind))
+            .with_record(RecordBuilder::new("NPC_", 0))
+            .build()
+            .unwrap();
+
+        let mut plugin = Plugin::new(GameId::Morrowind, Path::new("Test.esp"));
+        assert!(plugin.parse(&bytes, false).is_ok());
+
+        assert_eq!(vec!["Morrowind.esm".to_string()], plugin.masters().unwrap());
+        assert_eq!(Some(2), plugin.record_and_group_count());
+        assert!(plugin.validate_record_and_group_count().is_none());
+    }
+}
@@ -0,0 +1,535 @@
+/*
+ * This file is part of esplugin
+ *
+ * Copyright (C) 2017 Oliver Hamlet
+ *
+ * esplugin is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * esplugin is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with esplugin. If not, see <http://www.gnu.org/licenses/>.
+ */
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use crc32fast::Hasher;
+
+use error::Error;
+use game_id::GameId;
+use record::Record;
+
+const CACHE_MAGIC: &[u8] = b"ESPC";
+const CACHE_VERSION: u32 = 5;
+
+// Identifies the state of a plugin file when it was parsed. The CRC is only
+// calculated if the cache is configured to check it.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub(crate) struct FileKey {
+    size: u64,
+    modified_secs: u64,
+    modified_nanos: u32,
+    crc: Option<u32>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub(crate) struct CacheEntry {
+    pub header_only: bool,
+    pub header_record: Record,
+    pub form_ids: Vec<u32>,
+    pub editor_ids: Vec<(u32, String)>,
+    pub crc: Option<u32>,
+    pub record_and_group_count: Option<u32>,
+    pub temporary_form_ids: Vec<u32>,
+}
+
+// Stores the header record and FormIDs of parsed plugins in the given
+// directory, one file per plugin path. Entries are only reused if the plugin
+// file's size and modification time (and optionally its CRC) are unchanged.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub struct ParseCache {
+    directory: PathBuf,
+    check_crc: bool,
+}
+
+impl ParseCache {
+    pub fn new(directory: &Path) -> ParseCache {
+        ParseCache {
+            directory: directory.to_path_buf(),
+            check_crc: false,
+        }
+    }
+
+    // Also compare the CRC of the plugin file's content, which means reading
+    // the whole file but still avoids parsing it.
+    pub fn with_crc_check(mut self) -> ParseCache {
+        self.check_crc = true;
+        self
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub(crate) fn file_key(&self, path: &Path) -> Result<FileKey, Error> {
+        let metadata = fs::metadata(path)?;
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        let crc = if self.check_crc {
+            Some(crc_of_file(path)?)
+        } else {
+            None
+        };
+
+        Ok(FileKey {
+            size: metadata.len(),
+            modified_secs: modified.as_secs(),
+            modified_nanos: modified.subsec_nanos(),
+            crc,
+        })
+    }
+
+    // Returns None if there is no entry, or if it is stale, corrupt or was
+    // written for a header-only parse when a full parse is needed. An entry
+    // written for a full parse only gives its header record for a
+    // header-only parse, to match what parsing the header alone would give.
+    pub(crate) fn load(
+        &self,
+        path: &Path,
+        game_id: GameId,
+        key: &FileKey,
+        load_header_only: bool,
+    ) -> Option<CacheEntry> {
+        let bytes = fs::read(self.entry_path(path)).ok()?;
+        let entry = read_entry(&mut Cursor::new(&bytes[..]), path, game_id, key).ok()??;
+
+        if entry.header_only == load_header_only {
+            Some(entry)
+        } else if load_header_only {
+            Some(CacheEntry {
+                header_only: true,
+                header_record: entry.header_record,
+                form_ids: Vec::new(),
+                editor_ids: Vec::new(),
+                crc: None,
+                record_and_group_count: None,
+                temporary_form_ids: Vec::new(),
+            })
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn save(
+        &self,
+        path: &Path,
+        game_id: GameId,
+        key: &FileKey,
+        entry: &CacheEntry,
+    ) -> Result<(), Error> {
+        fs::create_dir_all(&self.directory)?;
+
+        // Write to a temporary file first so that an interrupted write can't
+        // leave a truncated entry behind.
+        let entry_path = self.entry_path(path);
+        let temp_path = entry_path.with_extension("tmp");
+        {
+            let mut writer = BufWriter::new(File::create(&temp_path)?);
+            write_entry(&mut writer, path, game_id, key, entry)?;
+            writer.flush()?;
+        }
+
+        fs::rename(&temp_path, &entry_path)?;
+
+        Ok(())
+    }
+
+    fn entry_path(&self, path: &Path) -> PathBuf {
+        let mut hasher = Hasher::new();
+        hasher.update(path.to_string_lossy().as_bytes());
+
+        self.directory
+            .join(format!("{:08x}.cache", hasher.finalize()))
+    }
+}
+
+fn crc_of_file(path: &Path) -> Result<u32, io::Error> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut hasher = Hasher::new();
+    let mut buffer = [0; 8192];
+
+    loop {
+        let count = reader.read(&mut buffer)?;
+        if count == 0 {
+            break;
+        }
+        hasher.update(&buffer[..count]);
+    }
+
+    Ok(hasher.finalize())
+}
+
+fn game_id_to_u8(game_id: GameId) -> u8 {
+    match game_id {
+        GameId::Oblivion => 0,
+        GameId::Skyrim => 1,
+        GameId::Fallout3 => 2,
+        GameId::FalloutNV => 3,
+        GameId::Morrowind => 4,
+        GameId::Fallout4 => 5,
+        GameId::SkyrimSE => 6,
+    }
+}
+
+fn write_entry<W: Write>(
+    writer: &mut W,
+    path: &Path,
+    game_id: GameId,
+    key: &FileKey,
+    entry: &CacheEntry,
+) -> Result<(), io::Error> {
+    writer.write_all(CACHE_MAGIC)?;
+    writer.write_u32::<LittleEndian>(CACHE_VERSION)?;
+    writer.write_u8(game_id_to_u8(game_id))?;
+
+    let path = path.to_string_lossy();
+    writer.write_u32::<LittleEndian>(path.len() as u32)?;
+    writer.write_all(path.as_bytes())?;
+
+    writer.write_u64::<LittleEndian>(key.size)?;
+    writer.write_u64::<LittleEndian>(key.modified_secs)?;
+    writer.write_u32::<LittleEndian>(key.modified_nanos)?;
+    writer.write_u8(key.crc.is_some() as u8)?;
+    writer.write_u32::<LittleEndian>(key.crc.unwrap_or(0))?;
+
+    writer.write_u8(entry.header_only as u8)?;
+
+    let mut header_record = Vec::new();
+    entry.header_record.write(&mut header_record, game_id)?;
+    writer.write_u32::<LittleEndian>(header_record.len() as u32)?;
+    writer.write_all(&header_record)?;
+
+    writer.write_u32::<LittleEndian>(entry.form_ids.len() as u32)?;
+    for form_id in &entry.form_ids {
+        writer.write_u32::<LittleEndian>(*form_id)?;
+    }
+
+    writer.write_u32::<LittleEndian>(entry.editor_ids.len() as u32)?;
+    for &(form_id, ref editor_id) in &entry.editor_ids {
+        writer.write_u32::<LittleEndian>(form_id)?;
+        writer.write_u32::<LittleEndian>(editor_id.len() as u32)?;
+        writer.write_all(editor_id.as_bytes())?;
+    }
+
+    writer.write_u8(entry.crc.is_some() as u8)?;
+    writer.write_u32::<LittleEndian>(entry.crc.unwrap_or(0))?;
+
+    writer.write_u8(entry.record_and_group_count.is_some() as u8)?;
+    writer.write_u32::<LittleEndian>(entry.record_and_group_count.unwrap_or(0))?;
+
+    writer.write_u32::<LittleEndian>(entry.temporary_form_ids.len() as u32)?;
+    for form_id in &entry.temporary_form_ids {
+        writer.write_u32::<LittleEndian>(*form_id)?;
+    }
+
+    Ok(())
+}
+
+// Checks that there is enough input left for the given number of elements
+// before space is allocated for them, so that a corrupt count can't cause a
+// huge allocation.
+fn check_remaining_length(
+    reader: &Cursor<&[u8]>,
+    count: usize,
+    element_size: usize,
+) -> Result<(), Error> {
+    let remaining_length = (reader.get_ref().len() as u64).saturating_sub(reader.position());
+
+    match count.checked_mul(element_size) {
+        Some(length) if length as u64 <= remaining_length => Ok(()),
+        _ => Err(Error::ParsingIncomplete),
+    }
+}
+
+fn read_entry(
+    reader: &mut Cursor<&[u8]>,
+    path: &Path,
+    game_id: GameId,
+    key: &FileKey,
+) -> Result<Option<CacheEntry>, Error> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if magic != CACHE_MAGIC
+        || reader.read_u32::<LittleEndian>()? != CACHE_VERSION
+        || reader.read_u8()? != game_id_to_u8(game_id)
+    {
+        return Ok(None);
+    }
+
+    let path_length = reader.read_u32::<LittleEndian>()? as usize;
+    check_remaining_length(reader, path_length, 1)?;
+    let mut cached_path = vec![0; path_length];
+    reader.read_exact(&mut cached_path)?;
+    if cached_path != path.to_string_lossy().as_bytes() {
+        return Ok(None);
+    }
+
+    let size = reader.read_u64::<LittleEndian>()?;
+    let modified_secs = reader.read_u64::<LittleEndian>()?;
+    let modified_nanos = reader.read_u32::<LittleEndian>()?;
+    let has_crc = reader.read_u8()? != 0;
+    let crc = reader.read_u32::<LittleEndian>()?;
+
+    let crc_matches = match key.crc {
+        Some(key_crc) => has_crc && crc == key_crc,
+        None => true,
+    };
+
+    if size != key.size
+        || modified_secs != key.modified_secs
+        || modified_nanos != key.modified_nanos
+        || !crc_matches
+    {
+        return Ok(None);
+    }
+
+    let header_only = reader.read_u8()? != 0;
+
+    let header_record_length = reader.read_u32::<LittleEndian>()? as usize;
+    check_remaining_length(reader, header_record_length, 1)?;
+    let mut header_record = vec![0; header_record_length];
+    reader.read_exact(&mut header_record)?;
+    let header_record = Record::parse(&header_record, game_id, false)?.1;
+
+    let form_ids_count = reader.read_u32::<LittleEndian>()? as usize;
+    check_remaining_length(reader, form_ids_count, 4)?;
+    let mut form_ids = vec![0; form_ids_count];
+    reader.read_u32_into::<LittleEndian>(&mut form_ids)?;
+
+    // Each EditorID entry has at least a FormID and a length.
+    let editor_ids_count = reader.read_u32::<LittleEndian>()? as usize;
+    check_remaining_length(reader, editor_ids_count, 8)?;
+    let mut editor_ids = Vec::with_capacity(editor_ids_count);
+    for _ in 0..editor_ids_count {
+        let form_id = reader.read_u32::<LittleEndian>()?;
+        let length = reader.read_u32::<LittleEndian>()? as usize;
+        check_remaining_length(reader, length, 1)?;
+        let mut editor_id = vec![0; length];
+        reader.read_exact(&mut editor_id)?;
+        let editor_id = String::from_utf8(editor_id).map_err(|_| Error::ParsingError)?;
+
+        editor_ids.push((form_id, editor_id));
+    }
+
+    let has_plugin_crc = reader.read_u8()? != 0;
+    let plugin_crc = reader.read_u32::<LittleEndian>()?;
+
+    let has_record_and_group_count = reader.read_u8()? != 0;
+    let record_and_group_count = reader.read_u32::<LittleEndian>()?;
+
+    let temporary_form_ids_count = reader.read_u32::<LittleEndian>()? as usize;
+    check_remaining_length(reader, temporary_form_ids_count, 4)?;
+    let mut temporary_form_ids = vec![0; temporary_form_ids_count];
+    reader.read_u32_into::<LittleEndian>(&mut temporary_form_ids)?;
+
+    Ok(Some(CacheEntry {
+        header_only,
+        header_record,
+        form_ids,
+        editor_ids,
+        crc: if has_plugin_crc {
+            Some(plugin_crc)
+        } else {
+            None
+        },
+        record_and_group_count: if has_record_and_group_count {
+            Some(record_and_group_count)
+        } else {
+            None
+        },
+        temporary_form_ids,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+
+    const PLUGIN_PATH: &str = "testing-plugins/Skyrim/Data/Blank.esm";
+
+    fn cache_directory(name: &str) -> PathBuf {
+        let directory = env::temp_dir().join("esplugin-cache-tests").join(name);
+        if directory.exists() {
+            fs::remove_dir_all(&directory).unwrap();
+        }
+        directory
+    }
+
+    fn entry(header_only: bool) -> CacheEntry {
+        let data = include_bytes!("../testing-plugins/Skyrim/Data/Blank.esm");
+        CacheEntry {
+            header_only,
+            header_record: Record::parse(data, GameId::Skyrim, false).unwrap().1,
+            form_ids: vec![0xCF0, 0xCF1, 0x0100_0CF2],
+            editor_ids: vec![(0x0100_0CF2, "IronSword".to_string())],
+            crc: if header_only { None } else { Some(0x1234_5678) },
+            record_and_group_count: if header_only { None } else { Some(4) },
+            temporary_form_ids: if header_only { Vec::new() } else { vec![0xCF1] },
+        }
+    }
+
+    #[test]
+    fn load_should_return_none_if_there_is_no_entry() {
+        let cache = ParseCache::new(&cache_directory("no_entry"));
+        let path = Path::new(PLUGIN_PATH);
+        let key = cache.file_key(path).unwrap();
+
+        assert!(cache.load(path, GameId::Skyrim, &key, true).is_none());
+    }
+
+    #[test]
+    fn load_should_return_a_saved_entry_if_the_key_is_unchanged() {
+        let cache = ParseCache::new(&cache_directory("saved_entry"));
+        let path = Path::new(PLUGIN_PATH);
+        let key = cache.file_key(path).unwrap();
+
+        cache
+            .save(path, GameId::Skyrim, &key, &entry(false))
+            .unwrap();
+
+        let loaded = cache.load(path, GameId::Skyrim, &key, false).unwrap();
+        assert_eq!(entry(false), loaded);
+    }
+
+    #[test]
+    fn load_should_return_none_if_the_file_size_or_modification_time_has_changed() {
+        let cache = ParseCache::new(&cache_directory("changed_key"));
+        let path = Path::new(PLUGIN_PATH);
+        let key = cache.file_key(path).unwrap();
+
+        cache
+            .save(path, GameId::Skyrim, &key, &entry(false))
+            .unwrap();
+
+        let mut changed_key = key.clone();
+        changed_key.size += 1;
+        assert!(cache
+            .load(path, GameId::Skyrim, &changed_key, false)
+            .is_none());
+
+        let mut changed_key = key.clone();
+        changed_key.modified_nanos += 1;
+        assert!(cache
+            .load(path, GameId::Skyrim, &changed_key, false)
+            .is_none());
+    }
+
+    #[test]
+    fn load_should_return_none_if_the_crc_is_checked_and_has_changed() {
+        let cache = ParseCache::new(&cache_directory("changed_crc")).with_crc_check();
+        let path = Path::new(PLUGIN_PATH);
+        let key = cache.file_key(path).unwrap();
+
+        assert!(key.crc.is_some());
+
+        cache
+            .save(path, GameId::Skyrim, &key, &entry(false))
+            .unwrap();
+
+        let mut changed_key = key.clone();
+        changed_key.crc = key.crc.map(|c| c.wrapping_add(1));
+        assert!(cache
+            .load(path, GameId::Skyrim, &changed_key, false)
+            .is_none());
+        assert!(cache.load(path, GameId::Skyrim, &key, false).is_some());
+    }
+
+    #[test]
+    fn load_should_return_none_for_a_header_only_entry_if_a_full_parse_is_needed() {
+        let cache = ParseCache::new(&cache_directory("header_only"));
+        let path = Path::new(PLUGIN_PATH);
+        let key = cache.file_key(path).unwrap();
+
+        cache
+            .save(path, GameId::Skyrim, &key, &entry(true))
+            .unwrap();
+
+        assert!(cache.load(path, GameId::Skyrim, &key, false).is_none());
+        assert!(cache.load(path, GameId::Skyrim, &key, true).is_some());
+    }
+
+    #[test]
+    fn load_should_return_only_the_header_record_of_a_full_entry_if_only_the_header_is_needed() {
+        let cache = ParseCache::new(&cache_directory("full_entry_header_only"));
+        let path = Path::new(PLUGIN_PATH);
+        let key = cache.file_key(path).unwrap();
+
+        cache
+            .save(path, GameId::Skyrim, &key, &entry(false))
+            .unwrap();
+
+        let loaded = cache.load(path, GameId::Skyrim, &key, true).unwrap();
+        assert!(loaded.header_only);
+        assert_eq!(entry(false).header_record, loaded.header_record);
+        assert!(loaded.form_ids.is_empty());
+        assert!(loaded.editor_ids.is_empty());
+        assert!(loaded.crc.is_none());
+        assert!(loaded.record_and_group_count.is_none());
+        assert!(loaded.temporary_form_ids.is_empty());
+    }
+
+    #[test]
+    fn read_entry_should_error_if_a_count_is_larger_than_the_remaining_input() {
+        let cache = ParseCache::new(&cache_directory("corrupt_count"));
+        let path = Path::new(PLUGIN_PATH);
+        let key = cache.file_key(path).unwrap();
+
+        let mut bytes = Vec::new();
+        write_entry(&mut bytes, path, GameId::Skyrim, &key, &entry(false)).unwrap();
+
+        let count_index = bytes
+            .windows(8)
+            .position(|w| w == [3, 0, 0, 0, 0xF0, 0x0C, 0, 0])
+            .unwrap();
+        bytes[count_index..count_index + 4].copy_from_slice(&[0xFF; 4]);
+
+        let result = read_entry(&mut Cursor::new(&bytes[..]), path, GameId::Skyrim, &key);
+        match result {
+            Err(Error::ParsingIncomplete) => {}
+            x => panic!("Unexpected result: {:?}", x),
+        }
+    }
+
+    #[test]
+    fn load_should_return_none_for_a_different_game_or_a_corrupt_entry() {
+        let cache = ParseCache::new(&cache_directory("corrupt"));
+        let path = Path::new(PLUGIN_PATH);
+        let key = cache.file_key(path).unwrap();
+
+        cache
+            .save(path, GameId::Skyrim, &key, &entry(false))
+            .unwrap();
+
+        assert!(cache.load(path, GameId::SkyrimSE, &key, false).is_none());
+
+        let entry_path = cache.entry_path(path);
+        let bytes = fs::read(&entry_path).unwrap();
+        fs::write(&entry_path, &bytes[..bytes.len() - 2]).unwrap();
+
+        assert!(cache.load(path, GameId::Skyrim, &key, false).is_none());
+    }
+}
@@ -0,0 +1,288 @@
+/*
+ * This file is part of esplugin
+ *
+ * Copyright (C) 2017 Oliver Hamlet
+ *
+ * esplugin is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * esplugin is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with esplugin. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::fmt;
+
+use form_id::ResolvedFormId;
+
+// Problems with one of a plugin's masters, given the load order that the
+// plugin is in. Each holds the master's filename.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum MasterDiagnostic {
+    // The master is not in the load order.
+    Missing(String),
+    // The master is in the load order, but loads after the plugin.
+    LoadsAfter(String),
+    // The master is a light plugin, and loads after the plugin. Only
+    // reported for games with light plugins, instead of LoadsAfter.
+    LightMasterLoadsAfter(String),
+    // The plugin is a master file but the master is not. Master files load
+    // before other plugins, so the game either loads the master after the
+    // plugin or, in games with light plugins, moves it ahead of the plugin
+    // out of load order. Not reported for Morrowind.
+    NotAMasterFile(String),
+}
+
+impl MasterDiagnostic {
+    pub fn master(&self) -> &str {
+        match *self {
+            MasterDiagnostic::Missing(ref x)
+            | MasterDiagnostic::LoadsAfter(ref x)
+            | MasterDiagnostic::LightMasterLoadsAfter(ref x)
+            | MasterDiagnostic::NotAMasterFile(ref x) => x,
+        }
+    }
+}
+
+impl fmt::Display for MasterDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MasterDiagnostic::Missing(ref x) => write!(f, "The master \"{}\" is missing", x),
+            MasterDiagnostic::LoadsAfter(ref x) => {
+                write!(f, "The master \"{}\" loads after the plugin", x)
+            }
+            MasterDiagnostic::LightMasterLoadsAfter(ref x) => write!(
+                f,
+                "The master \"{}\" is a light plugin that loads after the plugin",
+                x
+            ),
+            MasterDiagnostic::NotAMasterFile(ref x) => write!(
+                f,
+                "The master \"{}\" is not a master file, but the plugin is",
+                x
+            ),
+        }
+    }
+}
+
+// The record and group count in a plugin's header doesn't match the number
+// of records and groups that it contains.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct RecordAndGroupCountMismatch {
+    header_count: u32,
+    actual_count: u32,
+}
+
+impl RecordAndGroupCountMismatch {
+    pub(crate) fn new(header_count: u32, actual_count: u32) -> Self {
+        RecordAndGroupCountMismatch {
+            header_count,
+            actual_count,
+        }
+    }
+
+    pub fn header_count(&self) -> u32 {
+        self.header_count
+    }
+
+    pub fn actual_count(&self) -> u32 {
+        self.actual_count
+    }
+}
+
+impl fmt::Display for RecordAndGroupCountMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "The header says there are {} records and groups, but there are {}",
+            self.header_count, self.actual_count
+        )
+    }
+}
+
+// A Morrowind plugin's recorded file size for one of its masters doesn't
+// match the size of the master file.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub struct MasterSizeMismatch {
+    master: String,
+    expected_size: u64,
+    actual_size: u64,
+}
+
+impl MasterSizeMismatch {
+    pub(crate) fn new(master: String, expected_size: u64, actual_size: u64) -> Self {
+        MasterSizeMismatch {
+            master,
+            expected_size,
+            actual_size,
+        }
+    }
+
+    pub fn master(&self) -> &str {
+        &self.master
+    }
+
+    pub fn expected_size(&self) -> u64 {
+        self.expected_size
+    }
+
+    pub fn actual_size(&self) -> u64 {
+        self.actual_size
+    }
+}
+
+impl fmt::Display for MasterSizeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "The master \"{}\" has changed: the plugin expects it to be {} bytes, but it is {} bytes",
+            self.master, self.expected_size, self.actual_size
+        )
+    }
+}
+
+// Problems with the list of overridden forms in a plugin's ONAM subrecord.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum OverriddenFormDiagnostic {
+    // The plugin overrides a record in a temporary children group that isn't
+    // listed, so the game will ignore the override if the plugin is a master
+    // file.
+    NotListed(ResolvedFormId),
+    // The listed record isn't overridden by the plugin.
+    NotOverridden(ResolvedFormId),
+}
+
+impl OverriddenFormDiagnostic {
+    pub fn form_id(&self) -> &ResolvedFormId {
+        match *self {
+            OverriddenFormDiagnostic::NotListed(ref x)
+            | OverriddenFormDiagnostic::NotOverridden(ref x) => x,
+        }
+    }
+}
+
+impl fmt::Display for OverriddenFormDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            OverriddenFormDiagnostic::NotListed(ref x) => write!(
+                f,
+                "The overridden temporary record {:06X} from \"{}\" is not listed in ONAM",
+                x.object_index(),
+                x.plugin_name()
+            ),
+            OverriddenFormDiagnostic::NotOverridden(ref x) => write!(
+                f,
+                "The record {:06X} from \"{}\" is listed in ONAM but is not overridden",
+                x.object_index(),
+                x.plugin_name()
+            ),
+        }
+    }
+}
+
+// Problems with the FormIDs of a plugin's records.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum FormIdDiagnostic {
+    // More than one of the plugin's records has the FormID.
+    Duplicate(ResolvedFormId),
+    // A record that the plugin adds has an object index that isn't below the
+    // next object ID in the plugin's header.
+    NotBelowNextObjectId(ResolvedFormId),
+    // A record that the plugin adds has an object index in the range reserved
+    // by the game engine.
+    InReservedRange(ResolvedFormId),
+    // The record's mod index is greater than the plugin's own mod index, so
+    // it belongs to no plugin. The game's behaviour is undefined, but the
+    // record is treated as one that the plugin adds. Also holds the mod index.
+    InvalidModIndex(ResolvedFormId, u8),
+}
+
+impl FormIdDiagnostic {
+    pub fn form_id(&self) -> &ResolvedFormId {
+        match *self {
+            FormIdDiagnostic::Duplicate(ref x)
+            | FormIdDiagnostic::NotBelowNextObjectId(ref x)
+            | FormIdDiagnostic::InReservedRange(ref x)
+            | FormIdDiagnostic::InvalidModIndex(ref x, _) => x,
+        }
+    }
+}
+
+impl fmt::Display for FormIdDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FormIdDiagnostic::Duplicate(ref x) => write!(
+                f,
+                "More than one record has the FormID {:06X} from \"{}\"",
+                x.object_index(),
+                x.plugin_name()
+            ),
+            FormIdDiagnostic::NotBelowNextObjectId(ref x) => write!(
+                f,
+                "The new record {:06X} from \"{}\" is not below the next object ID",
+                x.object_index(),
+                x.plugin_name()
+            ),
+            FormIdDiagnostic::InReservedRange(ref x) => write!(
+                f,
+                "The new record {:06X} from \"{}\" is in the reserved range 000000 to 0007FF",
+                x.object_index(),
+                x.plugin_name()
+            ),
+            FormIdDiagnostic::InvalidModIndex(ref x, mod_index) => write!(
+                f,
+                "The record {:02X}{:06X} in \"{}\" has a mod index greater than the number of masters",
+                mod_index,
+                x.object_index(),
+                x.plugin_name()
+            ),
+        }
+    }
+}
+
+// Differences between the plugins that a save game was made with and the
+// current load order. Each holds a plugin's filename.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum SavePluginDiagnostic {
+    // The save uses the plugin, but it is not in the load order.
+    Missing(String),
+    // The plugin is in the load order, but the save doesn't use it.
+    NotInSave(String),
+    // The plugin is in the save and the load order, but its position relative
+    // to the other plugins they share has changed.
+    OutOfOrder(String),
+}
+
+impl SavePluginDiagnostic {
+    pub fn plugin(&self) -> &str {
+        match *self {
+            SavePluginDiagnostic::Missing(ref x)
+            | SavePluginDiagnostic::NotInSave(ref x)
+            | SavePluginDiagnostic::OutOfOrder(ref x) => x,
+        }
+    }
+}
+
+impl fmt::Display for SavePluginDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SavePluginDiagnostic::Missing(ref x) => {
+                write!(f, "The save uses \"{}\", which is not in the load order", x)
+            }
+            SavePluginDiagnostic::NotInSave(ref x) => {
+                write!(f, "The save does not use \"{}\"", x)
+            }
+            SavePluginDiagnostic::OutOfOrder(ref x) => write!(
+                f,
+                "\"{}\" is in a different position in the load order than in the save",
+                x
+            ),
+        }
+    }
+}
@@ -0,0 +1,477 @@
+/*
+ * This file is part of esplugin
+ *
+ * Copyright (C) 2017 Oliver Hamlet
+ *
+ * esplugin is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * esplugin is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with esplugin. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::collections::{BTreeMap, HashMap};
+
+use form_id::ResolvedFormId;
+use game_id::GameId;
+use record::Record;
+
+// The differences between two versions of a plugin, going from the older
+// version to the newer version.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct PluginDiff {
+    header_changes: Vec<HeaderChange>,
+    record_diffs: Vec<RecordDiff>,
+}
+
+impl PluginDiff {
+    pub(crate) fn new(header_changes: Vec<HeaderChange>, record_diffs: Vec<RecordDiff>) -> Self {
+        PluginDiff {
+            header_changes,
+            record_diffs,
+        }
+    }
+
+    pub fn header_changes(&self) -> &[HeaderChange] {
+        &self.header_changes
+    }
+
+    // Ordered by FormID, or for Morrowind by record type and NAME ID.
+    pub fn record_diffs(&self) -> &[RecordDiff] {
+        &self.record_diffs
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.header_changes.is_empty() && self.record_diffs.is_empty()
+    }
+}
+
+// A change to a plugin's header. Each holds the older value then the newer
+// value.
+#[derive(Clone, PartialEq, Debug)]
+pub enum HeaderChange {
+    Masters(Vec<String>, Vec<String>),
+    Description(Option<String>, Option<String>),
+    Flags(u32, u32),
+    Version(Option<f32>, Option<f32>),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub struct RecordDiff {
+    form_id: ResolvedFormId,
+    // Morrowind records all have a FormID of zero, so are identified by
+    // their NAME ID instead. None for other games.
+    name_id: Option<String>,
+    record_type: String,
+    change: RecordChange,
+}
+
+impl RecordDiff {
+    pub fn form_id(&self) -> &ResolvedFormId {
+        &self.form_id
+    }
+
+    pub fn name_id(&self) -> Option<&str> {
+        self.name_id.as_ref().map(|x| &x[..])
+    }
+
+    pub fn record_type(&self) -> &str {
+        &self.record_type
+    }
+
+    pub fn change(&self) -> &RecordChange {
+        &self.change
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum RecordChange {
+    Added,
+    Removed,
+    // Holds the older and newer record header flags, and the subrecords that
+    // changed.
+    Modified(u32, u32, Vec<SubrecordDiff>),
+}
+
+// Subrecords are matched up by their type and the order in which subrecords
+// of that type appear in the record, so the index counts only subrecords of
+// the same type.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub struct SubrecordDiff {
+    subrecord_type: String,
+    index: usize,
+    change: SubrecordChange,
+}
+
+impl SubrecordDiff {
+    pub fn subrecord_type(&self) -> &str {
+        &self.subrecord_type
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn change(&self) -> SubrecordChange {
+        self.change
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum SubrecordChange {
+    Added,
+    Removed,
+    Modified,
+}
+
+// Morrowind records are matched up by their type and NAME ID, and records
+// with the same type and ID are matched up in the order they appear.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+enum RecordKey {
+    FormId(ResolvedFormId),
+    NameId(String, Option<String>, usize),
+}
+
+fn record_keys(
+    records: Vec<(ResolvedFormId, &Record)>,
+    game_id: GameId,
+) -> Vec<(RecordKey, ResolvedFormId, &Record)> {
+    let mut name_id_counts: HashMap<(String, Option<String>), usize> = HashMap::new();
+    let mut keys: Vec<(RecordKey, ResolvedFormId, &Record)> = Vec::new();
+    for (form_id, record) in records {
+        let key = if game_id == GameId::Morrowind {
+            let record_type = record.header().record_type().to_string();
+            let name_id = record.name_id();
+            let count = name_id_counts
+                .entry((record_type.clone(), name_id.clone()))
+                .or_insert(0);
+            let index = *count;
+            *count += 1;
+
+            RecordKey::NameId(record_type, name_id, index)
+        } else {
+            RecordKey::FormId(form_id.clone())
+        };
+
+        keys.push((key, form_id, record));
+    }
+
+    keys
+}
+
+// FormIDs must be resolved so that records are matched up even if the order
+// of the plugin's masters has changed. If a record's type has changed, it's
+// treated as the old record being removed and a new record being added.
+pub(crate) fn diff_records(
+    old_records: Vec<(ResolvedFormId, &Record)>,
+    new_records: Vec<(ResolvedFormId, &Record)>,
+    game_id: GameId,
+) -> Vec<RecordDiff> {
+    let mut records: BTreeMap<RecordKey, (ResolvedFormId, Option<&Record>, Option<&Record>)> =
+        BTreeMap::new();
+
+    for (key, form_id, record) in record_keys(old_records, game_id) {
+        records.entry(key).or_insert((form_id, None, None)).1 = Some(record);
+    }
+
+    for (key, form_id, record) in record_keys(new_records, game_id) {
+        records.entry(key).or_insert((form_id, None, None)).2 = Some(record);
+    }
+
+    let mut diffs = Vec::new();
+    for (_, (form_id, old, new)) in records {
+        match (old, new) {
+            (Some(old), Some(new)) if old.header().record_type() == new.header().record_type() => {
+                let subrecord_diffs = diff_subrecords(old, new);
+                if old.header().flags() != new.header().flags()
+                    || !subrecord_diffs.is_empty()
+                    || has_unread_changes(old, new)
+                {
+                    diffs.push(record_diff(
+                        form_id,
+                        new,
+                        game_id,
+                        RecordChange::Modified(
+                            old.header().flags(),
+                            new.header().flags(),
+                            subrecord_diffs,
+                        ),
+                    ));
+                }
+            }
+            (old, new) => {
+                if let Some(old) = old {
+                    diffs.push(record_diff(
+                        form_id.clone(),
+                        old,
+                        game_id,
+                        RecordChange::Removed,
+                    ));
+                }
+                if let Some(new) = new {
+                    diffs.push(record_diff(form_id, new, game_id, RecordChange::Added));
+                }
+            }
+        }
+    }
+
+    diffs
+}
+
+// Compressed records that couldn't be decompressed can only be compared by
+// their compressed data.
+fn has_unread_changes(old: &Record, new: &Record) -> bool {
+    (old.has_unread_subrecords() || new.has_unread_subrecords())
+        && old.compressed_data() != new.compressed_data()
+}
+
+fn record_diff(
+    form_id: ResolvedFormId,
+    record: &Record,
+    game_id: GameId,
+    change: RecordChange,
+) -> RecordDiff {
+    RecordDiff {
+        form_id,
+        name_id: if game_id == GameId::Morrowind {
+            record.name_id()
+        } else {
+            None
+        },
+        record_type: record.header().record_type().to_string(),
+        change,
+    }
+}
+
+fn diff_subrecords(old: &Record, new: &Record) -> Vec<SubrecordDiff> {
+    let mut subrecord_types: Vec<&str> = Vec::new();
+    for subrecord in old.subrecords().iter().chain(new.subrecords().iter()) {
+        if !subrecord_types.contains(&subrecord.subrecord_type()) {
+            subrecord_types.push(subrecord.subrecord_type());
+        }
+    }
+
+    let mut diffs = Vec::new();
+    for subrecord_type in subrecord_types {
+        let old_data = subrecords_data(old, subrecord_type);
+        let new_data = subrecords_data(new, subrecord_type);
+
+        for index in 0..old_data.len().max(new_data.len()) {
+            let change = match (old_data.get(index), new_data.get(index)) {
+                (Some(old), Some(new)) if old == new => continue,
+                (Some(_), Some(_)) => SubrecordChange::Modified,
+                (Some(_), None) => SubrecordChange::Removed,
+                (None, _) => SubrecordChange::Added,
+            };
+
+            diffs.push(SubrecordDiff {
+                subrecord_type: subrecord_type.to_string(),
+                index,
+                change,
+            });
+        }
+    }
+
+    diffs
+}
+
+fn subrecords_data<'a>(record: &'a Record, subrecord_type: &str) -> Vec<&'a [u8]> {
+    record
+        .subrecords()
+        .iter()
+        .filter(|s| s.subrecord_type() == subrecord_type)
+        .map(|s| s.data())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use byteorder::{LittleEndian, WriteBytesExt};
+
+    use builder::RecordBuilder;
+
+    fn record(record_type: &str, flags: u32, subrecords: &[(&str, &[u8])]) -> Record {
+        let mut data = Vec::new();
+        for &(subrecord_type, subrecord_data) in subrecords {
+            data.extend_from_slice(subrecord_type.as_bytes());
+            data.write_u16::<LittleEndian>(subrecord_data.len() as u16)
+                .unwrap();
+            data.extend_from_slice(subrecord_data);
+        }
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(record_type.as_bytes());
+        bytes.write_u32::<LittleEndian>(data.len() as u32).unwrap();
+        bytes.write_u32::<LittleEndian>(flags).unwrap();
+        bytes.write_u32::<LittleEndian>(0xCF0).unwrap();
+        bytes.extend_from_slice(&[0; 8]);
+        bytes.extend_from_slice(&data);
+
+        Record::parse(&bytes, GameId::Skyrim, false).unwrap().1
+    }
+
+    fn form_id(object_index: u32) -> ResolvedFormId {
+        ResolvedFormId::new("Blank.esp", &[], object_index)
+    }
+
+    #[test]
+    fn diff_records_should_find_added_removed_and_modified_records() {
+        let unchanged = record("WEAP", 0, &[("EDID", b"A\0")]);
+        let removed = record("NPC_", 0, &[("EDID", b"B\0")]);
+        let old_modified = record("ARMO", 0, &[("EDID", b"C\0"), ("KWDA", b"1")]);
+        let new_modified = record("ARMO", 0x20, &[("EDID", b"D\0")]);
+        let added = record("MISC", 0, &[]);
+
+        let diffs = diff_records(
+            vec![
+                (form_id(1), &unchanged),
+                (form_id(2), &removed),
+                (form_id(3), &old_modified),
+            ],
+            vec![
+                (form_id(1), &unchanged),
+                (form_id(3), &new_modified),
+                (form_id(4), &added),
+            ],
+            GameId::Skyrim,
+        );
+
+        assert_eq!(3, diffs.len());
+        assert_eq!(&form_id(2), diffs[0].form_id());
+        assert_eq!("NPC_", diffs[0].record_type());
+        assert_eq!(&RecordChange::Removed, diffs[0].change());
+
+        assert_eq!(&form_id(3), diffs[1].form_id());
+        match *diffs[1].change() {
+            RecordChange::Modified(0, 0x20, ref subrecord_diffs) => {
+                assert_eq!(2, subrecord_diffs.len());
+                assert_eq!("EDID", subrecord_diffs[0].subrecord_type());
+                assert_eq!(SubrecordChange::Modified, subrecord_diffs[0].change());
+                assert_eq!("KWDA", subrecord_diffs[1].subrecord_type());
+                assert_eq!(SubrecordChange::Removed, subrecord_diffs[1].change());
+            }
+            _ => panic!("Expected a modified record"),
+        }
+
+        assert_eq!(&form_id(4), diffs[2].form_id());
+        assert_eq!(&RecordChange::Added, diffs[2].change());
+    }
+
+    #[test]
+    fn diff_records_should_remove_and_add_a_record_whose_type_changed() {
+        let old = record("WEAP", 0, &[]);
+        let new = record("ARMO", 0, &[]);
+
+        let diffs = diff_records(
+            vec![(form_id(1), &old)],
+            vec![(form_id(1), &new)],
+            GameId::Skyrim,
+        );
+
+        assert_eq!(2, diffs.len());
+        assert_eq!("WEAP", diffs[0].record_type());
+        assert_eq!(&RecordChange::Removed, diffs[0].change());
+        assert_eq!("ARMO", diffs[1].record_type());
+        assert_eq!(&RecordChange::Added, diffs[1].change());
+    }
+
+    #[test]
+    fn diff_records_should_match_morrowind_records_by_type_and_name_id() {
+        let book = |id, data: &[u8]| {
+            RecordBuilder::new("BOOK", 0)
+                .with_editor_id(id, GameId::Morrowind)
+                .with_subrecord("TEXT", data)
+                .build(GameId::Morrowind)
+        };
+        let unchanged = book("a", b"1");
+        let old_modified = book("b", b"1");
+        let new_modified = book("b", b"2");
+        let removed = book("c", b"1");
+        let added = RecordBuilder::new("MISC", 0)
+            .with_editor_id("c", GameId::Morrowind)
+            .build(GameId::Morrowind);
+
+        let diffs = diff_records(
+            vec![
+                (form_id(0), &unchanged),
+                (form_id(0), &old_modified),
+                (form_id(0), &removed),
+            ],
+            vec![
+                (form_id(0), &unchanged),
+                (form_id(0), &new_modified),
+                (form_id(0), &added),
+            ],
+            GameId::Morrowind,
+        );
+
+        assert_eq!(3, diffs.len());
+        assert_eq!(Some("b"), diffs[0].name_id());
+        match *diffs[0].change() {
+            RecordChange::Modified(0, 0, ref subrecord_diffs) => {
+                assert_eq!(1, subrecord_diffs.len());
+                assert_eq!("TEXT", subrecord_diffs[0].subrecord_type());
+            }
+            _ => panic!("Expected a modified record"),
+        }
+
+        assert_eq!(Some("c"), diffs[1].name_id());
+        assert_eq!("BOOK", diffs[1].record_type());
+        assert_eq!(&RecordChange::Removed, diffs[1].change());
+
+        assert_eq!(Some("c"), diffs[2].name_id());
+        assert_eq!("MISC", diffs[2].record_type());
+        assert_eq!(&RecordChange::Added, diffs[2].change());
+    }
+
+    #[test]
+    fn diff_records_should_match_morrowind_records_with_the_same_type_and_name_id_in_order() {
+        let book = |data: &[u8]| {
+            RecordBuilder::new("BOOK", 0)
+                .with_editor_id("a", GameId::Morrowind)
+                .with_subrecord("TEXT", data)
+                .build(GameId::Morrowind)
+        };
+        let first = book(b"1");
+        let old_second = book(b"2");
+        let new_second = book(b"3");
+
+        let diffs = diff_records(
+            vec![(form_id(0), &first), (form_id(0), &old_second)],
+            vec![(form_id(0), &first), (form_id(0), &new_second)],
+            GameId::Morrowind,
+        );
+
+        assert_eq!(1, diffs.len());
+        assert_eq!(Some("a"), diffs[0].name_id());
+        match *diffs[0].change() {
+            RecordChange::Modified(0, 0, ref subrecord_diffs) => {
+                assert_eq!(1, subrecord_diffs.len());
+            }
+            _ => panic!("Expected a modified record"),
+        }
+    }
+
+    #[test]
+    fn diff_subrecords_should_match_repeated_subrecords_by_index() {
+        let old = record("NPC_", 0, &[("SPLO", b"1"), ("SPLO", b"2")]);
+        let new = record("NPC_", 0, &[("SPLO", b"1"), ("SPLO", b"3"), ("SPLO", b"4")]);
+
+        let diffs = diff_subrecords(&old, &new);
+
+        assert_eq!(2, diffs.len());
+        assert_eq!(1, diffs[0].index());
+        assert_eq!(SubrecordChange::Modified, diffs[0].change());
+        assert_eq!(2, diffs[1].index());
+        assert_eq!(SubrecordChange::Added, diffs[1].change());
+    }
+}
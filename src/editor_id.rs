@@ -0,0 +1,99 @@
+/*
+ * This file is part of esplugin
+ *
+ * Copyright (C) 2017 Oliver Hamlet
+ *
+ * esplugin is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * esplugin is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with esplugin. If not, see <http://www.gnu.org/licenses/>.
+ */
+use std::collections::BTreeMap;
+
+use unicase::UniCase;
+
+// EditorIDs are case-insensitive, so they're looked up using UniCase keys.
+#[derive(Clone, PartialEq, Eq, Debug, Hash, Default)]
+pub struct EditorIds {
+    form_ids: BTreeMap<UniCase<String>, u32>,
+    editor_ids: BTreeMap<u32, String>,
+}
+
+impl EditorIds {
+    pub fn new(editor_ids: &[(u32, String)]) -> EditorIds {
+        let mut index = EditorIds::default();
+
+        for &(form_id, ref editor_id) in editor_ids {
+            index
+                .form_ids
+                .insert(UniCase::new(editor_id.clone()), form_id);
+            index.editor_ids.insert(form_id, editor_id.clone());
+        }
+
+        index
+    }
+
+    pub fn form_id(&self, editor_id: &str) -> Option<u32> {
+        self.form_ids
+            .get(&UniCase::new(editor_id.to_string()))
+            .cloned()
+    }
+
+    pub fn editor_id(&self, form_id: u32) -> Option<&str> {
+        self.editor_ids.get(&form_id).map(|e| e.as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &str)> {
+        self.editor_ids.iter().map(|(f, e)| (*f, e.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor_ids() -> EditorIds {
+        EditorIds::new(&[
+            (0x0100_0800, "IronSword".to_string()),
+            (0xABC, "SteelSword".to_string()),
+        ])
+    }
+
+    #[test]
+    fn form_id_should_be_case_insensitive() {
+        let index = editor_ids();
+
+        assert_eq!(Some(0x0100_0800), index.form_id("IronSword"));
+        assert_eq!(Some(0x0100_0800), index.form_id("ironsword"));
+        assert_eq!(Some(0xABC), index.form_id("STEELSWORD"));
+        assert_eq!(None, index.form_id("Bob"));
+    }
+
+    #[test]
+    fn editor_id_should_return_the_editor_id_of_the_given_form_id() {
+        let index = editor_ids();
+
+        assert_eq!(Some("IronSword"), index.editor_id(0x0100_0800));
+        assert_eq!(Some("SteelSword"), index.editor_id(0xABC));
+        assert_eq!(None, index.editor_id(0xABD));
+    }
+
+    #[test]
+    fn iter_should_iterate_over_editor_ids_in_form_id_order() {
+        let index = editor_ids();
+        let pairs: Vec<_> = index.iter().collect();
+
+        assert_eq!(
+            vec![(0xABC, "SteelSword"), (0x0100_0800, "IronSword")],
+            pairs
+        );
+    }
+}
@@ -31,6 +31,14 @@ pub enum Error {
     ParsingIncomplete,
     ParsingError,
     DecodeError(Cow<'static, str>),
+    MasterNotRemovable(String),
+    MasterInUse(String),
+    PluginAddsRecords,
+    RecordsNotKept,
+    // Holds the line number, counting from 1.
+    TextParsingError(usize),
+    // Holds the compression type given in the save's header.
+    UnsupportedSaveCompression(u16),
 }
 
 impl<I> From<Err<I>> for Error {
@@ -65,6 +73,32 @@ impl fmt::Display for Error {
                 f,
                 "Plugin string content could not be decoded from Windows-1252"
             ),
+            Error::MasterNotRemovable(ref x) => write!(
+                f,
+                "The master \"{}\" cannot be removed without changing the mod indices of other masters",
+                x
+            ),
+            Error::MasterInUse(ref x) => write!(
+                f,
+                "The master \"{}\" cannot be removed because the plugin's records use it",
+                x
+            ),
+            Error::PluginAddsRecords => write!(
+                f,
+                "Masters cannot be removed from a plugin that adds records"
+            ),
+            Error::RecordsNotKept => write!(
+                f,
+                "The plugin's records needed for this were not kept when parsing it"
+            ),
+            Error::TextParsingError(x) => {
+                write!(f, "Line {} of the plugin text could not be parsed", x)
+            }
+            Error::UnsupportedSaveCompression(x) => write!(
+                f,
+                "The save uses compression type {}, which cannot be read",
+                x
+            ),
         }
     }
 }
@@ -77,6 +111,18 @@ impl error::Error for Error {
             Error::ParsingIncomplete => "More input was expected by the plugin parser",
             Error::ParsingError => "An error was encountered while parsing a plugin",
             Error::DecodeError(_) => "Plugin string content could not be decoded from Windows-1252",
+            Error::MasterNotRemovable(_) => {
+                "A master cannot be removed without changing the mod indices of other masters"
+            }
+            Error::MasterInUse(_) => {
+                "A master cannot be removed because the plugin's records use it"
+            }
+            Error::PluginAddsRecords => "Masters cannot be removed from a plugin that adds records",
+            Error::RecordsNotKept => {
+                "The plugin's records needed for this were not kept when parsing it"
+            }
+            Error::TextParsingError(_) => "A line of the plugin text could not be parsed",
+            Error::UnsupportedSaveCompression(_) => "The save uses compression that cannot be read",
         }
     }
 
@@ -86,7 +132,13 @@ impl error::Error for Error {
             Error::NoFilename
             | Error::ParsingIncomplete
             | Error::ParsingError
-            | Error::DecodeError(_) => None,
+            | Error::DecodeError(_)
+            | Error::MasterNotRemovable(_)
+            | Error::MasterInUse(_)
+            | Error::PluginAddsRecords
+            | Error::RecordsNotKept
+            | Error::TextParsingError(_)
+            | Error::UnsupportedSaveCompression(_) => None,
         }
     }
 }
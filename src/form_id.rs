@@ -19,6 +19,8 @@
 use std::cmp::Ordering;
 use std::hash::{Hash, Hasher};
 
+use unicase::UniCase;
+
 #[derive(Clone, Debug, Default)]
 pub struct HashedFormId {
     // Store mod index for easy plugin ownership checks and filename mapping.
@@ -50,6 +52,10 @@ impl HashedFormId {
     pub fn object_index(&self) -> u32 {
         self.object_index
     }
+
+    pub fn raw(&self) -> u32 {
+        (u32::from(self.mod_index) << 24) | self.object_index
+    }
 }
 
 impl Ord for HashedFormId {
@@ -83,6 +89,63 @@ impl Hash for HashedFormId {
     }
 }
 
+// A FormID that has had its mod index replaced by the filename of the plugin
+// that it belongs to, so that it can be compared with FormIDs from other
+// plugins. Plugin filenames are compared case-insensitively.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ResolvedFormId {
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "serialize_unicase",
+            deserialize_with = "deserialize_unicase"
+        )
+    )]
+    plugin_name: UniCase<String>,
+    object_index: u32,
+}
+
+impl ResolvedFormId {
+    pub fn new(parent_plugin_name: &str, masters: &[String], raw_form_id: u32) -> Self {
+        let mod_index = (raw_form_id >> 24) as usize;
+        let plugin_name = masters
+            .get(mod_index)
+            .map(|m| m.as_str())
+            .unwrap_or(parent_plugin_name);
+
+        Self {
+            plugin_name: UniCase::new(plugin_name.to_string()),
+            object_index: raw_form_id & 0xFF_FFFF,
+        }
+    }
+
+    pub fn plugin_name(&self) -> &str {
+        &self.plugin_name
+    }
+
+    pub fn object_index(&self) -> u32 {
+        self.object_index
+    }
+}
+
+#[cfg(feature = "serde")]
+fn serialize_unicase<S: serde::Serializer>(
+    string: &UniCase<String>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(string)
+}
+
+#[cfg(feature = "serde")]
+fn deserialize_unicase<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<UniCase<String>, D::Error> {
+    use serde::Deserialize;
+
+    String::deserialize(deserializer).map(UniCase::new)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -100,6 +163,12 @@ mod tests {
         hasher.finish()
     }
 
+    fn hash_resolved(form_id: &ResolvedFormId) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        form_id.hash(&mut hasher);
+        hasher.finish()
+    }
+
     #[test]
     fn mod_index_should_equal_first_byte_of_raw_form_id_value() {
         let form_id = HashedFormId::new(PARENT_PLUGIN_NAME, MASTERS, 0x01);
@@ -233,4 +302,37 @@ mod tests {
         assert_ne!(form_id1.mod_index, form_id2.mod_index);
         assert_eq!(hash(&form_id1), hash(&form_id2));
     }
+
+    #[test]
+    fn resolved_form_id_should_use_the_master_at_the_mod_index_as_the_plugin_name() {
+        let masters = vec!["Blank.esm".to_string()];
+
+        let form_id = ResolvedFormId::new("Blank.esp", &masters, 0x0000_0CF0);
+        assert_eq!("Blank.esm", form_id.plugin_name());
+        assert_eq!(0xCF0, form_id.object_index());
+
+        let form_id = ResolvedFormId::new("Blank.esp", &masters, 0x0100_0CF0);
+        assert_eq!("Blank.esp", form_id.plugin_name());
+        assert_eq!(0xCF0, form_id.object_index());
+    }
+
+    #[test]
+    fn resolved_form_ids_should_compare_plugin_names_case_insensitively() {
+        let form_id1 = ResolvedFormId::new("Blank.esm", &[], 0x0000_0CF0);
+        let form_id2 = ResolvedFormId::new("blank.esp", &["BLANK.ESM".to_string()], 0xCF0);
+
+        assert_eq!(form_id1, form_id2);
+        assert_eq!(hash_resolved(&form_id1), hash_resolved(&form_id2));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn resolved_form_id_should_serialize_its_plugin_name_as_a_string() {
+        let form_id = ResolvedFormId::new("plugin.esp", &["master.esm".to_string()], 0x0000_0CF0);
+
+        let json = ::serde_json::to_string(&form_id).unwrap();
+
+        assert_eq!(r#"{"plugin_name":"master.esm","object_index":3312}"#, json);
+        assert_eq!(form_id, ::serde_json::from_str(&json).unwrap());
+    }
 }
@@ -18,6 +18,7 @@
  */
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum GameId {
     Oblivion,
     Skyrim,
@@ -17,58 +17,274 @@
  * along with esplugin. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use std::cmp;
+use std::io;
+use std::io::SeekFrom;
 use std::mem;
 
+use byteorder::{ByteOrder, LittleEndian};
 use nom::{le_u32, IResult};
 
+use error::Error;
 use game_id::GameId;
-use record::Record;
+use parse_options::ParseOptions;
+use record::{header_length, parse_editor_id, Record, RecordHeader, SUBRECORD_HEADER_LENGTH};
 
 const GROUP_TYPE: &[u8] = b"GRUP";
+const GROUP_LABEL_LENGTH: u8 = 4;
+const TEMPORARY_CHILDREN_GROUP_TYPE: u32 = 9;
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash, Default)]
 pub struct Group;
 
+#[derive(Clone, PartialEq, Eq, Debug, Hash, Default)]
+pub struct GroupContents {
+    pub form_ids: Vec<u32>,
+    pub editor_ids: Vec<(u32, String)>,
+    pub records: Vec<Record>,
+    pub record_and_group_count: u32,
+    // The FormIDs of records in cells' temporary children groups.
+    pub temporary_form_ids: Vec<u32>,
+}
+
+impl GroupContents {
+    pub fn append(&mut self, other: &mut GroupContents) {
+        self.form_ids.append(&mut other.form_ids);
+        self.editor_ids.append(&mut other.editor_ids);
+        self.records.append(&mut other.records);
+        self.record_and_group_count += other.record_and_group_count;
+        self.temporary_form_ids
+            .append(&mut other.temporary_form_ids);
+    }
+
+    fn push_form_id(&mut self, form_id: u32, editor_id: Option<String>) {
+        self.form_ids.push(form_id);
+        if let Some(editor_id) = editor_id {
+            self.editor_ids.push((form_id, editor_id));
+        }
+    }
+}
+
 impl Group {
+    // Splits the input into its top-level groups without parsing their
+    // contents. A group's size includes its header, so a smaller size is an
+    // error.
+    pub fn split(input: &[u8], game_id: GameId) -> IResult<&[u8], Vec<&[u8]>> {
+        let mut groups = Vec::new();
+        let mut remaining_input = input;
+
+        while !remaining_input.is_empty() {
+            let (_, group_size) = try_parse!(
+                remaining_input,
+                peek!(preceded!(tag!(GROUP_TYPE), apply!(group_size, game_id)))
+            );
+            let (input1, group) = try_parse!(remaining_input, take!(group_size));
+
+            groups.push(group);
+            remaining_input = input1;
+        }
+
+        Ok((remaining_input, groups))
+    }
+
     pub fn parse_for_form_ids<'a>(
         input: &'a [u8],
         game_id: GameId,
-        form_ids: &mut Vec<u32>,
+        options: &ParseOptions,
+        contents: &mut GroupContents,
     ) -> IResult<&'a [u8], ()> {
-        do_parse!(
+        let (input1, header) = try_parse!(input, apply!(parse_header, game_id));
+        let (input2, records_data) = try_parse!(input1, take!(header.size_of_records));
+
+        if options.includes_top_level_group(header.label) {
+            contents.record_and_group_count += 1;
+            try_parse!(
+                records_data,
+                apply!(parse_records, game_id, false, options, contents)
+            );
+        }
+
+        Ok((input2, ()))
+    }
+
+    pub fn read_form_ids<T: io::Read + io::Seek>(
+        reader: &mut T,
+        game_id: GameId,
+        options: &ParseOptions,
+        contents: &mut GroupContents,
+    ) -> Result<(), Error> {
+        let mut header_bytes: Vec<u8> = vec![0; header_length(game_id)];
+        reader.read_exact(&mut header_bytes)?;
+
+        let header = parse_header(&header_bytes, game_id)?.1;
+        check_remaining_length(reader, header.size_of_records)?;
+
+        if options.includes_top_level_group(header.label) {
+            contents.record_and_group_count += 1;
+            read_records(reader, game_id, &header, options, contents)
+        } else {
+            reader.seek(SeekFrom::Current(i64::from(header.size_of_records)))?;
+            Ok(())
+        }
+    }
+}
+
+pub fn parse_record<'a>(
+    input: &'a [u8],
+    game_id: GameId,
+    options: &ParseOptions,
+    contents: &mut GroupContents,
+) -> IResult<&'a [u8], ()> {
+    let (_, record_type) = try_parse!(input, peek!(take!(4)));
+    contents.record_and_group_count += 1;
+
+    if !options.includes_record_type(record_type) {
+        let (input1, _) = try_parse!(input, apply!(Record::parse_form_id, game_id));
+        Ok((input1, ()))
+    } else if options.keeps_records() {
+        let (input1, record) = try_parse!(
             input,
-            length_value!(
-                apply!(parse_header, game_id),
-                apply!(parse_records, game_id, form_ids)
-            ) >> ()
-        )
+            apply!(Record::parse_keeping_compressed_data, game_id)
+        );
+        contents.push_form_id(record.header().form_id(), record.editor_id());
+        contents.records.push(record);
+        Ok((input1, ()))
+    } else {
+        let (input1, header) = try_parse!(input, apply!(RecordHeader::parse, game_id));
+        let (input2, subrecords_data) = try_parse!(input1, take!(header.size_of_subrecords()));
+        contents.push_form_id(
+            header.form_id(),
+            parse_editor_id(&header, subrecords_data, game_id),
+        );
+        Ok((input2, ()))
+    }
+}
+
+// The header bytes must already have been read from the reader.
+pub fn read_record<T: io::Read + io::Seek>(
+    reader: &mut T,
+    game_id: GameId,
+    header_bytes: &[u8],
+    options: &ParseOptions,
+    contents: &mut GroupContents,
+) -> Result<u32, Error> {
+    let header = RecordHeader::parse(header_bytes, game_id)?.1;
+    contents.record_and_group_count += 1;
+
+    if !options.includes_record_type(header.record_type().as_bytes()) {
+        reader.seek(SeekFrom::Current(i64::from(header.size_of_subrecords())))?;
+    } else if options.keeps_records() {
+        let mut record_bytes = header_bytes.to_vec();
+        record_bytes.resize(header_bytes.len() + header.size_of_subrecords() as usize, 0);
+        reader.read_exact(&mut record_bytes[header_bytes.len()..])?;
+
+        let record = Record::parse_keeping_compressed_data(&record_bytes, game_id)?.1;
+        contents.push_form_id(header.form_id(), record.editor_id());
+        contents.records.push(record);
+    } else {
+        let editor_id = read_editor_id(reader, game_id, &header)?;
+        contents.push_form_id(header.form_id(), editor_id);
+    }
+
+    Ok(header_bytes.len() as u32 + header.size_of_subrecords())
+}
+
+// Reads only as much of the record's subrecord data as is needed to get its
+// EditorID, then seeks past the rest.
+fn read_editor_id<T: io::Read + io::Seek>(
+    reader: &mut T,
+    game_id: GameId,
+    header: &RecordHeader,
+) -> Result<Option<String>, Error> {
+    let size_of_subrecords = header.size_of_subrecords() as usize;
+    let mut subrecords_data = Vec::new();
+
+    if game_id != GameId::Morrowind && size_of_subrecords >= SUBRECORD_HEADER_LENGTH {
+        subrecords_data.resize(SUBRECORD_HEADER_LENGTH, 0);
+        reader.read_exact(&mut subrecords_data)?;
+
+        if &subrecords_data[..4] == b"EDID" {
+            let data_length = cmp::min(
+                LittleEndian::read_u16(&subrecords_data[4..]) as usize,
+                size_of_subrecords - SUBRECORD_HEADER_LENGTH,
+            );
+            subrecords_data.resize(SUBRECORD_HEADER_LENGTH + data_length, 0);
+            reader.read_exact(&mut subrecords_data[SUBRECORD_HEADER_LENGTH..])?;
+        }
+    }
+
+    let remaining_size = size_of_subrecords - subrecords_data.len();
+    reader.seek(SeekFrom::Current(remaining_size as i64))?;
+
+    Ok(parse_editor_id(header, &subrecords_data, game_id))
+}
+
+// Seeking past the end of a stream succeeds, so a truncated group would
+// otherwise only be noticed if something was read from past its end. The
+// sizes of the group's contents are checked against the group's size, so
+// checking the group's size against the stream's length is enough.
+fn check_remaining_length<T: io::Seek>(reader: &mut T, length: u32) -> Result<(), Error> {
+    let position = reader.stream_position()?;
+    let end = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(position))?;
+
+    if position + u64::from(length) > end {
+        Err(Error::ParsingError)
+    } else {
+        Ok(())
     }
 }
 
 fn get_header_length_to_skip(game_id: GameId) -> u8 {
     match game_id {
-        GameId::Oblivion => 12,
-        _ => 16,
+        GameId::Oblivion => 8,
+        _ => 12,
     }
 }
 
-fn parse_header(input: &[u8], game_id: GameId) -> IResult<&[u8], u32> {
+struct GroupHeader<'a> {
+    label: &'a [u8],
+    group_type: u32,
+    size_of_records: u32,
+}
+
+impl<'a> GroupHeader<'a> {
+    fn is_temporary_children_group(&self) -> bool {
+        self.group_type == TEMPORARY_CHILDREN_GROUP_TYPE
+    }
+}
+
+fn parse_header<'a>(input: &'a [u8], game_id: GameId) -> IResult<&'a [u8], GroupHeader<'a>> {
     let skip_length = get_header_length_to_skip(game_id);
-    let group_header_length = GROUP_TYPE.len() as u8 + mem::size_of::<u32>() as u8 + skip_length;
+    let group_header_length =
+        GROUP_TYPE.len() as u8 + mem::size_of::<u32>() as u8 + GROUP_LABEL_LENGTH + skip_length;
 
     do_parse!(
         input,
         tag!(GROUP_TYPE)
-            >> group_size: le_u32
-            >> take!(skip_length)
-            >> (group_size - u32::from(group_header_length))
+            >> group_size: apply!(group_size, game_id)
+            >> label: take!(GROUP_LABEL_LENGTH)
+            >> group_type: le_u32
+            >> take!(skip_length - mem::size_of::<u32>() as u8)
+            >> (GroupHeader {
+                label,
+                group_type,
+                size_of_records: group_size - u32::from(group_header_length),
+            })
     )
 }
 
+fn group_size(input: &[u8], game_id: GameId) -> IResult<&[u8], u32> {
+    let min_size = header_length(game_id) as u32;
+    verify!(input, le_u32, |size: u32| size >= min_size)
+}
+
 fn parse_records<'a>(
     input: &'a [u8],
     game_id: GameId,
-    form_ids: &mut Vec<u32>,
+    are_temporary: bool,
+    options: &ParseOptions,
+    contents: &mut GroupContents,
 ) -> IResult<&'a [u8], ()> {
     let mut input1 = input;
 
@@ -76,30 +292,92 @@ fn parse_records<'a>(
         let (_, next_type) = try_parse!(input1, peek!(take!(GROUP_TYPE.len())));
 
         if next_type == GROUP_TYPE {
-            let (input2, _) =
-                try_parse!(input1, apply!(Group::parse_for_form_ids, game_id, form_ids));
-            input1 = input2;
+            contents.record_and_group_count += 1;
+            let (input2, header) = try_parse!(input1, apply!(parse_header, game_id));
+            let (input3, records_data) = try_parse!(input2, take!(header.size_of_records));
+            try_parse!(
+                records_data,
+                apply!(
+                    parse_records,
+                    game_id,
+                    header.is_temporary_children_group(),
+                    options,
+                    contents
+                )
+            );
+            input1 = input3;
         } else {
-            let (input2, form_id) = try_parse!(input1, apply!(Record::parse_form_id, game_id));
+            if are_temporary {
+                let (_, header) = try_parse!(input1, apply!(RecordHeader::parse, game_id));
+                contents.temporary_form_ids.push(header.form_id());
+            }
+
+            let (input2, _) = try_parse!(input1, apply!(parse_record, game_id, options, contents));
             input1 = input2;
-            form_ids.push(form_id);
         }
     }
 
     Ok((&input1, ()))
 }
 
+// Groups and records share a header length, so each header can be read in
+// full before checking which it is. Record data that isn't kept is skipped by
+// seeking past it.
+fn read_records<T: io::Read + io::Seek>(
+    reader: &mut T,
+    game_id: GameId,
+    group_header: &GroupHeader,
+    options: &ParseOptions,
+    contents: &mut GroupContents,
+) -> Result<(), Error> {
+    let mut header_bytes: Vec<u8> = vec![0; header_length(game_id)];
+    let mut remaining_size = group_header.size_of_records;
+
+    while remaining_size > 0 {
+        reader.read_exact(&mut header_bytes)?;
+
+        let element_size = if &header_bytes[0..4] == GROUP_TYPE {
+            contents.record_and_group_count += 1;
+            let subgroup_header = parse_header(&header_bytes, game_id)?.1;
+            read_records(reader, game_id, &subgroup_header, options, contents)?;
+
+            LittleEndian::read_u32(&header_bytes[4..])
+        } else {
+            if group_header.is_temporary_children_group() {
+                let form_id = RecordHeader::parse(&header_bytes, game_id)?.1.form_id();
+                contents.temporary_form_ids.push(form_id);
+            }
+
+            read_record(reader, game_id, &header_bytes, options, contents)?
+        };
+
+        remaining_size = remaining_size
+            .checked_sub(element_size)
+            .ok_or(Error::ParsingError)?;
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const WEAP_GROUP: &[u8] =
+        b"GRUP\x5E\x00\x00\x00WEAP\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\
+        WEAP\x16\x00\x00\x00\x00\x00\x00\x00\x00\x08\x00\x01\x00\x00\x00\x00\x2C\x00\x00\x00\
+        EDID\x0A\x00IronSword\x00DATA\x00\x00\
+        WEAP\x00\x00\x00\x00\x00\x00\x00\x00\x01\x08\x00\x01\x00\x00\x00\x00\x2C\x00\x00\x00";
+
     #[test]
     fn new_should_store_formids_for_all_records_in_a_group() {
         let data =
             &include_bytes!("../testing-plugins/Skyrim/Data/Blank - Master Dependent.esm")[0x56..];
 
-        let mut form_ids: Vec<u32> = Vec::new();
-        Group::parse_for_form_ids(data, GameId::Skyrim, &mut form_ids).unwrap();
+        let options = ParseOptions::whole_plugin();
+        let mut contents = GroupContents::default();
+        Group::parse_for_form_ids(data, GameId::Skyrim, &options, &mut contents).unwrap();
+        let form_ids = contents.form_ids;
 
         assert_eq!(8, form_ids.len());
         // Also check three FormIDs from near the beginning, middle and end of the group.
@@ -112,10 +390,342 @@ mod tests {
     fn new_should_store_formids_for_all_records_in_subgroups() {
         let data = &include_bytes!("../testing-plugins/Skyrim/Data/Blank.esm")[0x1004C..0x10114];
 
-        let mut form_ids: Vec<u32> = Vec::new();
-        Group::parse_for_form_ids(data, GameId::Skyrim, &mut form_ids).unwrap();
+        let options = ParseOptions::whole_plugin();
+        let mut contents = GroupContents::default();
+        Group::parse_for_form_ids(data, GameId::Skyrim, &options, &mut contents).unwrap();
+        let form_ids = contents.form_ids;
+
+        assert_eq!(1, form_ids.len());
+        assert!(form_ids.contains(&0xCF9));
+    }
+
+    #[test]
+    fn read_form_ids_should_store_formids_for_all_records_in_a_group() {
+        let data =
+            &include_bytes!("../testing-plugins/Skyrim/Data/Blank - Master Dependent.esm")[0x56..];
+        let mut reader = io::Cursor::new(data);
+
+        let options = ParseOptions::whole_plugin();
+        let mut contents = GroupContents::default();
+        Group::read_form_ids(&mut reader, GameId::Skyrim, &options, &mut contents).unwrap();
+        let form_ids = contents.form_ids;
+
+        let mut expected_contents = GroupContents::default();
+        Group::parse_for_form_ids(data, GameId::Skyrim, &options, &mut expected_contents).unwrap();
+
+        assert_eq!(8, form_ids.len());
+        assert_eq!(expected_contents.form_ids, form_ids);
+    }
+
+    #[test]
+    fn read_form_ids_should_store_formids_for_all_records_in_subgroups() {
+        let data = &include_bytes!("../testing-plugins/Skyrim/Data/Blank.esm")[0x1004C..0x10114];
+        let mut reader = io::Cursor::new(data);
+
+        let options = ParseOptions::whole_plugin();
+        let mut contents = GroupContents::default();
+        Group::read_form_ids(&mut reader, GameId::Skyrim, &options, &mut contents).unwrap();
+        let form_ids = contents.form_ids;
 
         assert_eq!(1, form_ids.len());
         assert!(form_ids.contains(&0xCF9));
+        assert_eq!(data.len() as u64, reader.position());
+    }
+
+    #[test]
+    fn read_form_ids_should_error_if_the_group_type_is_not_grup() {
+        let data =
+            &include_bytes!("../testing-plugins/Skyrim/Data/Blank - Master Dependent.esm")[..0x56];
+        let mut reader = io::Cursor::new(data);
+
+        let options = ParseOptions::whole_plugin();
+        let mut contents = GroupContents::default();
+        let result = Group::read_form_ids(&mut reader, GameId::Skyrim, &options, &mut contents);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parse_for_form_ids_should_skip_a_top_level_group_that_is_not_included() {
+        let data =
+            &include_bytes!("../testing-plugins/Skyrim/Data/Blank - Master Dependent.esm")[0x56..];
+
+        let options = ParseOptions::whole_plugin().with_group_labels(&["NPC_"]);
+        let mut contents = GroupContents::default();
+        let (remaining_input, _) =
+            Group::parse_for_form_ids(data, GameId::Skyrim, &options, &mut contents).unwrap();
+
+        assert_ne!(data.len(), remaining_input.len());
+        assert!(contents.form_ids.is_empty());
+    }
+
+    #[test]
+    fn parse_for_form_ids_should_keep_records_of_included_types() {
+        let data =
+            &include_bytes!("../testing-plugins/Skyrim/Data/Blank - Master Dependent.esm")[0x56..];
+
+        let label = ::std::str::from_utf8(&data[8..12]).unwrap();
+
+        let options = ParseOptions::whole_plugin().with_record_types(&[label]);
+        let mut contents = GroupContents::default();
+        Group::parse_for_form_ids(data, GameId::Skyrim, &options, &mut contents).unwrap();
+
+        assert!(!contents.records.is_empty());
+        assert_eq!(contents.form_ids.len(), contents.records.len());
+        assert!(contents
+            .records
+            .iter()
+            .all(|r| r.header().record_type() == label));
+    }
+
+    const COMPRESSED_WEAP_GROUP: &[u8] =
+        b"GRUP\x56\x00\x00\x00WEAP\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\
+        WEAP\x26\x00\x00\x00\x00\x00\x04\x00\x00\x08\x00\x00\x00\x00\x00\x00\x2C\x00\x00\x00\
+        \x1A\x00\x00\x00\x78\x9C\x73\x75\xF1\x74\xE1\x62\xF0\x2C\xCA\xCF\x0B\x2E\xCF\x2F\
+        \x4A\x61\x70\x71\x0C\x71\x64\x61\x60\xE0\x60\x60\x04\x00\x5F\x14\x05\xEF";
+
+    fn assert_compressed_record_is_kept(contents: &GroupContents) {
+        assert_eq!(vec![0x800], contents.form_ids);
+        assert_eq!(1, contents.records.len());
+
+        let record = &contents.records[0];
+        assert_eq!(&COMPRESSED_WEAP_GROUP[48..], record.compressed_data());
+
+        let mut written = Vec::new();
+        record.write(&mut written, GameId::Skyrim).unwrap();
+        assert_eq!(&COMPRESSED_WEAP_GROUP[24..], written.as_slice());
+
+        if cfg!(feature = "compressed-fields") {
+            assert_eq!(2, record.subrecords().len());
+            assert_eq!("EDID", record.subrecords()[0].subrecord_type());
+            assert_eq!(b"IronSword\0", record.subrecords()[0].data());
+        } else {
+            assert!(record.subrecords().is_empty());
+        }
+    }
+
+    #[test]
+    fn parse_for_form_ids_should_keep_compressed_records_with_their_compressed_data() {
+        let options = ParseOptions::whole_plugin().keeping_all_records();
+        let mut contents = GroupContents::default();
+        Group::parse_for_form_ids(
+            COMPRESSED_WEAP_GROUP,
+            GameId::Skyrim,
+            &options,
+            &mut contents,
+        )
+        .unwrap();
+
+        assert_compressed_record_is_kept(&contents);
+    }
+
+    #[test]
+    fn read_form_ids_should_keep_compressed_records_with_their_compressed_data() {
+        let mut reader = io::Cursor::new(COMPRESSED_WEAP_GROUP);
+
+        let options = ParseOptions::whole_plugin().with_record_types(&["WEAP"]);
+        let mut contents = GroupContents::default();
+        Group::read_form_ids(&mut reader, GameId::Skyrim, &options, &mut contents).unwrap();
+
+        assert_compressed_record_is_kept(&contents);
+    }
+
+    #[test]
+    fn read_form_ids_should_skip_a_top_level_group_that_is_not_included() {
+        let data =
+            &include_bytes!("../testing-plugins/Skyrim/Data/Blank - Master Dependent.esm")[0x56..];
+        let mut reader = io::Cursor::new(data);
+
+        let options = ParseOptions::whole_plugin().with_group_labels(&["NPC_"]);
+        let mut contents = GroupContents::default();
+        Group::read_form_ids(&mut reader, GameId::Skyrim, &options, &mut contents).unwrap();
+
+        assert_ne!(0, reader.position());
+        assert!(contents.form_ids.is_empty());
+    }
+
+    // The group's size is reduced to exclude the second record, and the data
+    // is truncated two bytes before the end of the first record.
+    fn truncated_weap_group() -> Vec<u8> {
+        let mut data = WEAP_GROUP[..WEAP_GROUP.len() - 26].to_vec();
+        data[4] = 0x46;
+        data
+    }
+
+    #[test]
+    fn read_form_ids_should_error_if_a_group_is_truncated() {
+        let data = truncated_weap_group();
+        let mut reader = io::Cursor::new(&data);
+
+        let options = ParseOptions::whole_plugin();
+        let mut contents = GroupContents::default();
+        let result = Group::read_form_ids(&mut reader, GameId::Skyrim, &options, &mut contents);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn read_form_ids_should_error_if_a_skipped_group_is_truncated() {
+        let data = truncated_weap_group();
+        let mut reader = io::Cursor::new(&data);
+
+        let options = ParseOptions::whole_plugin().with_group_labels(&["NPC_"]);
+        let mut contents = GroupContents::default();
+        let result = Group::read_form_ids(&mut reader, GameId::Skyrim, &options, &mut contents);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parse_for_form_ids_should_error_if_a_group_is_truncated() {
+        let data = truncated_weap_group();
+
+        let options = ParseOptions::whole_plugin();
+        let mut contents = GroupContents::default();
+        let result = Group::parse_for_form_ids(&data, GameId::Skyrim, &options, &mut contents);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn split_should_return_each_top_level_group_in_order() {
+        let data = &include_bytes!("../testing-plugins/Skyrim/Data/Blank.esm")[0x1004C..];
+
+        let groups = Group::split(data, GameId::Skyrim).unwrap().1;
+
+        assert!(groups.len() > 1);
+        assert_eq!(data.len(), groups.iter().map(|g| g.len()).sum::<usize>());
+        assert!(groups.iter().all(|g| &g[..4] == GROUP_TYPE));
+        assert_eq!(&data[..groups[0].len()], groups[0]);
+    }
+
+    #[test]
+    fn split_should_error_if_a_top_level_record_is_found() {
+        let data =
+            &include_bytes!("../testing-plugins/Skyrim/Data/Blank - Master Dependent.esm")[..0x56];
+
+        assert!(Group::split(data, GameId::Skyrim).is_err());
+    }
+
+    #[test]
+    fn split_should_error_if_a_group_is_smaller_than_its_header() {
+        let mut data = WEAP_GROUP.to_vec();
+        data[4] = 0;
+
+        assert!(Group::split(&data, GameId::Skyrim).is_err());
+
+        data[4] = 0x17;
+
+        assert!(Group::split(&data, GameId::Skyrim).is_err());
+    }
+
+    #[test]
+    fn parse_for_form_ids_should_error_if_a_group_is_smaller_than_its_header() {
+        let mut data = WEAP_GROUP.to_vec();
+        data[4] = 0x17;
+
+        let options = ParseOptions::whole_plugin();
+        let mut contents = GroupContents::default();
+        let result = Group::parse_for_form_ids(&data, GameId::Skyrim, &options, &mut contents);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parse_for_form_ids_should_store_the_editor_ids_of_records() {
+        let options = ParseOptions::whole_plugin();
+        let mut contents = GroupContents::default();
+        Group::parse_for_form_ids(WEAP_GROUP, GameId::Skyrim, &options, &mut contents).unwrap();
+
+        assert_eq!(vec![0x0100_0800, 0x0100_0801], contents.form_ids);
+        assert_eq!(
+            vec![(0x0100_0800, "IronSword".to_string())],
+            contents.editor_ids
+        );
+    }
+
+    #[test]
+    fn read_form_ids_should_store_the_editor_ids_of_records() {
+        let mut reader = io::Cursor::new(WEAP_GROUP);
+
+        let options = ParseOptions::whole_plugin();
+        let mut contents = GroupContents::default();
+        Group::read_form_ids(&mut reader, GameId::Skyrim, &options, &mut contents).unwrap();
+
+        assert_eq!(WEAP_GROUP.len() as u64, reader.position());
+        assert_eq!(vec![0x0100_0800, 0x0100_0801], contents.form_ids);
+        assert_eq!(
+            vec![(0x0100_0800, "IronSword".to_string())],
+            contents.editor_ids
+        );
+    }
+
+    #[test]
+    fn parse_for_form_ids_should_store_the_editor_ids_of_kept_records() {
+        let options = ParseOptions::whole_plugin().with_record_types(&["WEAP"]);
+        let mut contents = GroupContents::default();
+        Group::parse_for_form_ids(WEAP_GROUP, GameId::Skyrim, &options, &mut contents).unwrap();
+
+        assert_eq!(2, contents.records.len());
+        assert_eq!(
+            vec![(0x0100_0800, "IronSword".to_string())],
+            contents.editor_ids
+        );
+    }
+
+    // A top-level CELL group that holds the WEAP group as a subgroup of the
+    // given type.
+    fn cell_group(subgroup_type: u32) -> Vec<u8> {
+        let mut data =
+            b"GRUP\x00\x00\x00\x00CELL\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00".to_vec();
+        data.extend_from_slice(WEAP_GROUP);
+        let size = data.len() as u32;
+        LittleEndian::write_u32(&mut data[4..], size);
+        LittleEndian::write_u32(&mut data[36..], subgroup_type);
+
+        data
+    }
+
+    #[test]
+    fn parse_and_read_should_count_all_records_and_groups() {
+        let data = cell_group(0);
+
+        let options = ParseOptions::whole_plugin();
+        let mut contents = GroupContents::default();
+        Group::parse_for_form_ids(&data, GameId::Skyrim, &options, &mut contents).unwrap();
+
+        assert_eq!(4, contents.record_and_group_count);
+
+        let mut contents = GroupContents::default();
+        let mut reader = io::Cursor::new(data);
+        Group::read_form_ids(&mut reader, GameId::Skyrim, &options, &mut contents).unwrap();
+
+        assert_eq!(4, contents.record_and_group_count);
+    }
+
+    #[test]
+    fn parse_and_read_should_store_the_form_ids_of_records_in_temporary_children_groups() {
+        let options = ParseOptions::whole_plugin();
+
+        for &(subgroup_type, ref expected_form_ids) in &[
+            (0, Vec::new()),
+            (
+                TEMPORARY_CHILDREN_GROUP_TYPE,
+                vec![0x0100_0800, 0x0100_0801],
+            ),
+        ] {
+            let data = cell_group(subgroup_type);
+
+            let mut contents = GroupContents::default();
+            Group::parse_for_form_ids(&data, GameId::Skyrim, &options, &mut contents).unwrap();
+
+            assert_eq!(*expected_form_ids, contents.temporary_form_ids);
+
+            let mut contents = GroupContents::default();
+            let mut reader = io::Cursor::new(data);
+            Group::read_form_ids(&mut reader, GameId::Skyrim, &options, &mut contents).unwrap();
+
+            assert_eq!(*expected_form_ids, contents.temporary_form_ids);
+        }
     }
 }
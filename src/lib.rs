@@ -18,23 +18,61 @@
  */
 
 extern crate byteorder;
+extern crate crc32fast;
 extern crate encoding;
 extern crate memmap;
 #[macro_use]
 extern crate nom;
+extern crate rayon;
 extern crate unicase;
 
-#[cfg(feature = "compressed-fields")]
+#[cfg(any(feature = "compressed-fields", feature = "compressed-saves"))]
 extern crate flate2;
+#[cfg(feature = "compressed-saves")]
+extern crate lz4_flex;
 
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
+
+pub use builder::{GroupBuilder, PluginBuilder, RecordBuilder};
+pub use cache::ParseCache;
+pub use diagnostics::{
+    FormIdDiagnostic, MasterDiagnostic, MasterSizeMismatch, OverriddenFormDiagnostic,
+    RecordAndGroupCountMismatch, SavePluginDiagnostic,
+};
+pub use diff::{
+    HeaderChange, PluginDiff, RecordChange, RecordDiff, SubrecordChange, SubrecordDiff,
+};
 pub use error::Error;
+pub use form_id::ResolvedFormId;
 pub use game_id::GameId;
+pub use parse_options::ParseOptions;
 pub use plugin::Plugin;
+pub use record::{Record, RecordHeader};
+pub use save::SaveGame;
+pub use schema::{
+    Armor, Cell, FactionRank, Field, FieldType, FieldValue, Global, ItemCount, Keyword,
+    LeveledEntry, LeveledItem, Npc, Quest, Reference, Text, TypedRecord, TypedSubrecord, Weapon,
+};
+pub use subrecord::Subrecord;
+pub use text::{plugin_from_text, plugin_to_text};
 
+mod builder;
+mod cache;
+mod diagnostics;
+mod diff;
+mod editor_id;
 mod error;
 mod form_id;
 mod game_id;
 mod group;
+mod parse_options;
 mod plugin;
 mod record;
+mod save;
+mod schema;
 mod subrecord;
+mod text;
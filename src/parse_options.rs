@@ -0,0 +1,227 @@
+/*
+ * This file is part of esplugin
+ *
+ * Copyright (C) 2017 Oliver Hamlet
+ *
+ * esplugin is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * esplugin is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with esplugin. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+// Records of these types are not stored in a top-level group with a matching
+// label, but are nested inside the top-level group of another record type.
+const CELL_CHILD_RECORD_TYPES: &[&[u8]] = &[
+    b"REFR", b"ACHR", b"ACRE", b"PGRE", b"PMIS", b"PARW", b"PBAR", b"PBEA", b"PCON", b"PFLA",
+    b"PHZD", b"LAND", b"NAVM", b"PGRD",
+];
+const WRLD_CHILD_RECORD_TYPES: &[&[u8]] = &[b"CELL", b"ROAD"];
+const DIAL_CHILD_RECORD_TYPES: &[&[u8]] = &[b"INFO"];
+
+#[derive(Clone, PartialEq, Eq, Debug, Hash, Default)]
+pub struct ParseOptions {
+    header_only: bool,
+    record_types: Option<Vec<String>>,
+    group_labels: Option<Vec<String>>,
+    keep_all_records: bool,
+    parallel: bool,
+}
+
+impl ParseOptions {
+    pub fn header_only() -> ParseOptions {
+        ParseOptions {
+            header_only: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn whole_plugin() -> ParseOptions {
+        ParseOptions::default()
+    }
+
+    // Only FormIDs of records with the given types are kept, along with the
+    // parsed records themselves. Top-level groups that cannot contain any of
+    // the given types are skipped without being parsed.
+    pub fn with_record_types(mut self, record_types: &[&str]) -> ParseOptions {
+        self.record_types = Some(record_types.iter().map(|t| t.to_string()).collect());
+        self
+    }
+
+    // Only top-level groups with the given labels are parsed.
+    pub fn with_group_labels(mut self, group_labels: &[&str]) -> ParseOptions {
+        self.group_labels = Some(group_labels.iter().map(|l| l.to_string()).collect());
+        self
+    }
+
+    // All parsed records are kept, not just those of the given record types.
+    // This uses a lot more memory than only keeping FormIDs.
+    pub fn keeping_all_records(mut self) -> ParseOptions {
+        self.keep_all_records = true;
+        self
+    }
+
+    // Top-level groups are located first and then parsed in parallel. This
+    // has no effect when parsing from a reader.
+    pub fn in_parallel(mut self) -> ParseOptions {
+        self.parallel = true;
+        self
+    }
+
+    pub fn is_header_only(&self) -> bool {
+        self.header_only
+    }
+
+    pub(crate) fn is_parallel(&self) -> bool {
+        self.parallel
+    }
+
+    // If no top-level groups are skipped, every record and group in the
+    // plugin is visited while parsing.
+    pub(crate) fn includes_all_top_level_groups(&self) -> bool {
+        self.record_types.is_none() && self.group_labels.is_none()
+    }
+
+    pub(crate) fn keeps_records(&self) -> bool {
+        self.keep_all_records || self.record_types.is_some()
+    }
+
+    pub(crate) fn keeps_all_records(&self) -> bool {
+        self.keep_all_records && self.includes_all_top_level_groups()
+    }
+
+    pub(crate) fn includes_record_type(&self, record_type: &[u8]) -> bool {
+        match self.record_types {
+            Some(ref record_types) => contains(record_types, record_type),
+            None => true,
+        }
+    }
+
+    pub(crate) fn includes_top_level_group(&self, label: &[u8]) -> bool {
+        if let Some(ref group_labels) = self.group_labels {
+            if !contains(group_labels, label) {
+                return false;
+            }
+        }
+
+        match self.record_types {
+            Some(ref record_types) => {
+                contains(record_types, label)
+                    || child_record_types(label)
+                        .iter()
+                        .any(|t| contains(record_types, t))
+            }
+            None => true,
+        }
+    }
+}
+
+impl From<bool> for ParseOptions {
+    fn from(load_header_only: bool) -> Self {
+        if load_header_only {
+            ParseOptions::header_only()
+        } else {
+            ParseOptions::whole_plugin()
+        }
+    }
+}
+
+fn contains(strings: &[String], bytes: &[u8]) -> bool {
+    strings.iter().any(|s| s.as_bytes() == bytes)
+}
+
+fn child_record_types(label: &[u8]) -> Vec<&'static [u8]> {
+    match label {
+        b"CELL" => CELL_CHILD_RECORD_TYPES.to_vec(),
+        b"WRLD" => WRLD_CHILD_RECORD_TYPES
+            .iter()
+            .chain(CELL_CHILD_RECORD_TYPES.iter())
+            .cloned()
+            .collect(),
+        b"DIAL" => DIAL_CHILD_RECORD_TYPES.to_vec(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whole_plugin_should_include_all_record_types_and_groups() {
+        let options = ParseOptions::whole_plugin();
+
+        assert!(!options.is_header_only());
+        assert!(!options.is_parallel());
+        assert!(!options.keeps_records());
+        assert!(options.includes_all_top_level_groups());
+        assert!(options.includes_record_type(b"NPC_"));
+        assert!(options.includes_top_level_group(b"NPC_"));
+    }
+
+    #[test]
+    fn with_record_types_should_only_include_the_given_record_types() {
+        let options = ParseOptions::whole_plugin().with_record_types(&["NPC_", "WEAP"]);
+
+        assert!(options.keeps_records());
+        assert!(!options.includes_all_top_level_groups());
+        assert!(options.includes_record_type(b"NPC_"));
+        assert!(options.includes_record_type(b"WEAP"));
+        assert!(!options.includes_record_type(b"ARMO"));
+    }
+
+    #[test]
+    fn with_record_types_should_include_top_level_groups_that_can_contain_the_given_types() {
+        let options = ParseOptions::whole_plugin().with_record_types(&["REFR"]);
+
+        assert!(options.includes_top_level_group(b"CELL"));
+        assert!(options.includes_top_level_group(b"WRLD"));
+        assert!(!options.includes_top_level_group(b"DIAL"));
+        assert!(!options.includes_top_level_group(b"NPC_"));
+
+        let options = ParseOptions::whole_plugin().with_record_types(&["INFO", "NPC_"]);
+
+        assert!(options.includes_top_level_group(b"DIAL"));
+        assert!(options.includes_top_level_group(b"NPC_"));
+        assert!(!options.includes_top_level_group(b"CELL"));
+    }
+
+    #[test]
+    fn with_group_labels_should_exclude_top_level_groups_with_other_labels() {
+        let options = ParseOptions::whole_plugin()
+            .with_record_types(&["REFR"])
+            .with_group_labels(&["CELL"]);
+
+        assert!(options.includes_top_level_group(b"CELL"));
+        assert!(!options.includes_top_level_group(b"WRLD"));
+        assert!(options.keeps_records());
+    }
+
+    #[test]
+    fn keeping_all_records_should_keep_records_of_all_types() {
+        let options = ParseOptions::whole_plugin().keeping_all_records();
+
+        assert!(options.keeps_records());
+        assert!(options.includes_all_top_level_groups());
+        assert!(options.includes_record_type(b"NPC_"));
+    }
+
+    #[test]
+    fn in_parallel_should_not_change_other_options() {
+        let options = ParseOptions::whole_plugin()
+            .with_record_types(&["NPC_"])
+            .in_parallel();
+
+        assert!(options.is_parallel());
+        assert!(!options.is_header_only());
+        assert!(options.includes_record_type(b"NPC_"));
+        assert!(!options.includes_record_type(b"WEAP"));
+    }
+}
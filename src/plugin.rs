@@ -17,8 +17,8 @@
  * along with esplugin. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use std::fs::File;
-use std::io::{BufReader, Read};
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
 use std::str;
@@ -32,23 +32,55 @@ use nom::{self, ErrorKind, IResult};
 
 use memmap::Mmap;
 
+use rayon::prelude::*;
+
 use unicase::eq;
 
+use cache::{CacheEntry, ParseCache};
+use diagnostics::{
+    FormIdDiagnostic, MasterDiagnostic, MasterSizeMismatch, OverriddenFormDiagnostic,
+    RecordAndGroupCountMismatch,
+};
+use diff::{diff_records, HeaderChange, PluginDiff};
+use editor_id::EditorIds;
 use error::Error;
-use form_id::HashedFormId;
+use form_id::{HashedFormId, ResolvedFormId};
 use game_id::GameId;
-use group::Group;
-use record::Record;
+use group::{parse_record, read_record, Group, GroupContents};
+use parse_options::ParseOptions;
+use record::{header_length, Record, RecordHeader};
+use schema::{undecoded_data, TypedRecord};
 
 // 1 MB is around the file size at which memory-mapping becomes more performant.
 const MIN_MMAP_FILE_SIZE: u64 = 1_000_000;
 
+// Object indices below this are reserved by the game engine, so records that
+// plugins add should not use them.
+const RESERVED_OBJECT_INDEX_END: u32 = 0x800;
+
+// Which of the plugin's records were kept when parsing it, if any were.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+enum KeptRecords {
+    Selected,
+    All,
+}
+
 #[derive(Clone, PartialEq, Eq, Debug, Hash, Default)]
 struct PluginData {
     header_record: Record,
     form_ids: Vec<HashedFormId>,
+    records: Vec<Record>,
+    kept_records: Option<KeptRecords>,
+    editor_ids: EditorIds,
+    crc: Option<u32>,
+    record_and_group_count: Option<u32>,
+    temporary_form_ids: Vec<u32>,
 }
 
+/// A plugin file's header and, depending on how it was parsed, its records.
+///
+/// `Plugin` is guaranteed to be `Send + Sync`, so parsed plugins can be moved
+/// to and shared between threads.
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub struct Plugin {
     game_id: GameId,
@@ -56,6 +88,12 @@ pub struct Plugin {
     data: PluginData,
 }
 
+// Fails to compile if Plugin stops being Send + Sync.
+fn _assert_plugin_is_send_and_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Plugin>();
+}
+
 impl Plugin {
     pub fn new(game_id: GameId, filepath: &Path) -> Plugin {
         Plugin {
@@ -66,22 +104,88 @@ impl Plugin {
     }
 
     pub fn parse(&mut self, input: &[u8], load_header_only: bool) -> Result<(), Error> {
+        self.parse_with_options(input, &ParseOptions::from(load_header_only))
+    }
+
+    pub fn parse_with_options(
+        &mut self,
+        input: &[u8],
+        options: &ParseOptions,
+    ) -> Result<(), Error> {
         match self.filename() {
             None => Err(Error::NoFilename),
             Some(filename) => {
-                self.data = parse_plugin(input, self.game_id, &filename, load_header_only)?.1;
+                self.data = parse_plugin(input, self.game_id, &filename, options)?.1;
 
                 Ok(())
             }
         }
     }
 
+    pub fn parse_reader<R: Read + Seek>(
+        &mut self,
+        reader: R,
+        load_header_only: bool,
+    ) -> Result<(), Error> {
+        self.parse_reader_with_options(reader, &ParseOptions::from(load_header_only))
+    }
+
+    pub fn parse_reader_with_options<R: Read + Seek>(
+        &mut self,
+        mut reader: R,
+        options: &ParseOptions,
+    ) -> Result<(), Error> {
+        let filename = self.filename().ok_or(Error::NoFilename)?;
+
+        let content = Record::read_and_validate(&mut reader, self.game_id, self.header_type())?;
+        let header_record = Record::parse(&content, self.game_id, false)?.1;
+
+        if options.is_header_only() {
+            self.data = PluginData {
+                header_record,
+                form_ids: Vec::new(),
+                records: Vec::new(),
+                kept_records: None,
+                editor_ids: EditorIds::default(),
+                crc: None,
+                record_and_group_count: None,
+                temporary_form_ids: Vec::new(),
+            };
+
+            return Ok(());
+        }
+
+        let contents = read_records(&mut reader, self.game_id, options)?;
+        let masters = masters(&header_record)?;
+
+        self.data = PluginData {
+            record_and_group_count: counted_records_and_groups(&contents, options),
+            form_ids: hashed_form_ids(&contents.form_ids, &filename, &masters),
+            header_record,
+            records: contents.records,
+            kept_records: kept_records(options),
+            editor_ids: EditorIds::new(&contents.editor_ids),
+            crc: None,
+            temporary_form_ids: contents.temporary_form_ids,
+        };
+
+        Ok(())
+    }
+
     pub fn parse_open_file(&mut self, file: File, load_header_only: bool) -> Result<(), Error> {
+        self.parse_open_file_with_options(file, &ParseOptions::from(load_header_only))
+    }
+
+    pub fn parse_open_file_with_options(
+        &mut self,
+        file: File,
+        options: &ParseOptions,
+    ) -> Result<(), Error> {
         let mut reader = BufReader::new(&file);
 
-        if load_header_only {
+        if options.is_header_only() {
             let content = Record::read_and_validate(&mut reader, self.game_id, self.header_type())?;
-            self.parse(&content, load_header_only)
+            self.parse_with_options(&content, options)
         } else {
             let file_size = file.metadata().map(|m| m.len()).unwrap_or(0);
 
@@ -91,7 +195,7 @@ impl Plugin {
                 if &mmap[0..4] != self.header_type() {
                     Err(Error::ParsingError)
                 } else {
-                    self.parse(&mmap, false)
+                    self.parse_with_options(&mmap, options)
                 }
             } else {
                 let mut content = vec![0; 4];
@@ -103,15 +207,91 @@ impl Plugin {
                 content.reserve(file_size as usize - 3);
                 reader.read_to_end(&mut content)?;
 
-                self.parse(&content, false)
+                self.parse_with_options(&content, options)
             }
         }
     }
 
     pub fn parse_file(&mut self, load_header_only: bool) -> Result<(), Error> {
+        self.parse_file_with_options(&ParseOptions::from(load_header_only))
+    }
+
+    pub fn parse_file_with_options(&mut self, options: &ParseOptions) -> Result<(), Error> {
         let file = File::open(&self.path)?;
 
-        self.parse_open_file(file, load_header_only)
+        self.parse_open_file_with_options(file, options)
+    }
+
+    // Reuses the header record and FormIDs cached by a previous call if the
+    // file hasn't changed since, otherwise parses the file and caches the
+    // result. Records are never cached.
+    pub fn parse_file_cached(
+        &mut self,
+        cache: &ParseCache,
+        load_header_only: bool,
+    ) -> Result<(), Error> {
+        let filename = self.filename().ok_or(Error::NoFilename)?;
+        let key = cache.file_key(&self.path)?;
+
+        if let Some(entry) = cache.load(&self.path, self.game_id, &key, load_header_only) {
+            let form_ids = if load_header_only {
+                Vec::new()
+            } else {
+                let masters = masters(&entry.header_record)?;
+                hashed_form_ids(&entry.form_ids, &filename, &masters)
+            };
+
+            self.data = PluginData {
+                header_record: entry.header_record,
+                form_ids,
+                records: Vec::new(),
+                kept_records: None,
+                editor_ids: EditorIds::new(&entry.editor_ids),
+                crc: entry.crc,
+                record_and_group_count: entry.record_and_group_count,
+                temporary_form_ids: entry.temporary_form_ids,
+            };
+
+            return Ok(());
+        }
+
+        self.parse_file(load_header_only)?;
+
+        let entry = CacheEntry {
+            header_only: load_header_only,
+            header_record: self.data.header_record.clone(),
+            form_ids: self.data.form_ids.iter().map(|f| f.raw()).collect(),
+            editor_ids: self
+                .data
+                .editor_ids
+                .iter()
+                .map(|(f, e)| (f, e.to_string()))
+                .collect(),
+            crc: self.data.crc,
+            record_and_group_count: self.data.record_and_group_count,
+            temporary_form_ids: self.data.temporary_form_ids.clone(),
+        };
+
+        // The plugin has been parsed successfully, so failing to cache it
+        // shouldn't cause an error.
+        let _ = cache.save(&self.path, self.game_id, &key, &entry);
+
+        Ok(())
+    }
+
+    // Parses the given files concurrently using a thread pool, returning the
+    // results in the same order as the given files.
+    pub fn parse_files<P: AsRef<Path> + Sync>(
+        game_id: GameId,
+        files: &[(P, ParseOptions)],
+    ) -> Vec<Result<Plugin, Error>> {
+        files
+            .par_iter()
+            .map(|(path, options)| {
+                let mut plugin = Plugin::new(game_id, path.as_ref());
+                plugin.parse_file_with_options(options).map(|_| plugin)
+            })
+            .collect()
     }
 
     pub fn game_id(&self) -> &GameId {
@@ -133,6 +313,331 @@ impl Plugin {
         masters(&self.data.header_record)
     }
 
+    // The file size of each master when the plugin was last saved, taken from
+    // the DATA subrecord that follows each MAST subrecord. Only Morrowind
+    // records the sizes, so this is empty for other games.
+    pub fn master_sizes(&self) -> Result<Vec<(String, u64)>, Error> {
+        if self.game_id != GameId::Morrowind {
+            return Ok(Vec::new());
+        }
+
+        let subrecords = self.data.header_record.subrecords();
+        let master_indices = subrecords
+            .iter()
+            .enumerate()
+            .filter(|&(_, s)| s.subrecord_type() == "MAST")
+            .map(|(i, _)| i);
+
+        masters(&self.data.header_record)?
+            .into_iter()
+            .zip(master_indices)
+            .map(|(master, index)| match subrecords.get(index + 1) {
+                Some(s) if s.subrecord_type() == "DATA" && s.data().len() > 7 => {
+                    Ok((master, LittleEndian::read_u64(s.data())))
+                }
+                _ => Err(Error::ParsingError),
+            })
+            .collect()
+    }
+
+    // Morrowind warns that a master has changed if its file size differs
+    // from the size recorded in the plugin. Masters that are not in the given
+    // directory are skipped.
+    pub fn validate_master_sizes(
+        &self,
+        data_path: &Path,
+    ) -> Result<Vec<MasterSizeMismatch>, Error> {
+        let mut mismatches = Vec::new();
+        for (master, expected_size) in self.master_sizes()? {
+            let actual_size = match fs::metadata(data_path.join(&master)) {
+                Ok(metadata) => metadata.len(),
+                Err(ref e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(Error::IoError(e)),
+            };
+
+            if actual_size != expected_size {
+                mismatches.push(MasterSizeMismatch::new(master, expected_size, actual_size));
+            }
+        }
+
+        Ok(mismatches)
+    }
+
+    // Records are only kept if they were selected by record type when parsing,
+    // or if all records were kept.
+    pub fn records(&self) -> &[Record] {
+        &self.data.records
+    }
+
+    // Decodes the kept records that have a schema for this plugin's game,
+    // skipping any that do not.
+    pub fn typed_records(&self) -> Result<Vec<TypedRecord>, Error> {
+        let is_localized = self.is_localized_flag_set();
+        let mut typed_records = Vec::new();
+        for record in &self.data.records {
+            if let Some(typed_record) = TypedRecord::decode(record, self.game_id, is_localized)? {
+                typed_records.push(typed_record);
+            }
+        }
+
+        Ok(typed_records)
+    }
+
+    pub fn resolve_form_id(&self, form_id: u32) -> Result<ResolvedFormId, Error> {
+        let filename = self.filename().ok_or(Error::NoFilename)?;
+        let masters = self.masters()?;
+
+        Ok(ResolvedFormId::new(&filename, &masters, form_id))
+    }
+
+    // Returns the FormID of each kept record that has a schema, along with
+    // the FormIDs that the record references. All FormIDs are resolved
+    // against this plugin's masters.
+    pub fn references(&self) -> Result<Vec<(ResolvedFormId, Vec<ResolvedFormId>)>, Error> {
+        let filename = self.filename().ok_or(Error::NoFilename)?;
+        let masters = self.masters()?;
+
+        Ok(self
+            .typed_records()?
+            .iter()
+            .map(|r| {
+                let references = r
+                    .referenced_form_ids()
+                    .into_iter()
+                    .map(|f| ResolvedFormId::new(&filename, &masters, f))
+                    .collect();
+
+                (
+                    ResolvedFormId::new(&filename, &masters, r.form_id()),
+                    references,
+                )
+            })
+            .collect())
+    }
+
+    // Finds the kept records in the given plugins that reference the given
+    // FormID, returning each record's plugin and resolved FormID.
+    pub fn find_references<'a>(
+        plugins: &'a [Plugin],
+        form_id: &ResolvedFormId,
+    ) -> Result<Vec<(&'a Plugin, ResolvedFormId)>, Error> {
+        let mut found = Vec::new();
+        for plugin in plugins {
+            for (record_form_id, references) in plugin.references()? {
+                if references.contains(form_id) {
+                    found.push((plugin, record_form_id));
+                }
+            }
+        }
+
+        Ok(found)
+    }
+
+    // Compares this plugin with a newer version of it. Only records that
+    // were kept when parsing both plugins are compared, so the same record
+    // types should be selected for both.
+    pub fn diff(&self, newer: &Plugin) -> Result<PluginDiff, Error> {
+        if self.data.kept_records.is_none() || newer.data.kept_records.is_none() {
+            return Err(Error::RecordsNotKept);
+        }
+
+        let mut header_changes = Vec::new();
+
+        let (masters, newer_masters) = (self.masters()?, newer.masters()?);
+        if masters != newer_masters {
+            header_changes.push(HeaderChange::Masters(masters, newer_masters));
+        }
+
+        let (description, newer_description) = (self.description()?, newer.description()?);
+        if description != newer_description {
+            header_changes.push(HeaderChange::Description(description, newer_description));
+        }
+
+        let flags = self.data.header_record.header().flags();
+        let newer_flags = newer.data.header_record.header().flags();
+        if flags != newer_flags {
+            header_changes.push(HeaderChange::Flags(flags, newer_flags));
+        }
+
+        let (version, newer_version) = (self.header_version(), newer.header_version());
+        if version != newer_version {
+            header_changes.push(HeaderChange::Version(version, newer_version));
+        }
+
+        // The versions may have different filenames, so resolve both against
+        // this plugin's filename.
+        let filename = self.filename().ok_or(Error::NoFilename)?;
+        let record_diffs = diff_records(
+            self.resolved_records(&filename)?,
+            newer.resolved_records(&filename)?,
+            self.game_id,
+        );
+
+        Ok(PluginDiff::new(header_changes, record_diffs))
+    }
+
+    // EditorIDs are only collected when parsing the whole plugin, and are
+    // not collected from compressed records. The FormIDs are as they appear
+    // in this plugin. Morrowind records have no FormIDs to look their NAME
+    // IDs up by, so no EditorIDs are collected for Morrowind plugins.
+    pub fn editor_id(&self, form_id: u32) -> Option<&str> {
+        self.data.editor_ids.editor_id(form_id)
+    }
+
+    pub fn form_id_by_editor_id(&self, editor_id: &str) -> Option<u32> {
+        self.data.editor_ids.form_id(editor_id)
+    }
+
+    // Finds the record with the given EditorID that was last defined or
+    // overridden in the given load order, returning the plugin that contains
+    // it and the record's FormID as it appears in that plugin.
+    pub fn resolve_editor_id<'a>(
+        plugins: &'a [Plugin],
+        editor_id: &str,
+    ) -> Option<(&'a Plugin, u32)> {
+        let form_id = plugins
+            .iter()
+            .rev()
+            .filter_map(|plugin| {
+                plugin
+                    .form_id_by_editor_id(editor_id)
+                    .and_then(|f| plugin.hashed_form_id(f))
+            })
+            .next()?;
+
+        plugins
+            .iter()
+            .rev()
+            .filter_map(|plugin| {
+                plugin
+                    .data
+                    .form_ids
+                    .binary_search(&form_id)
+                    .ok()
+                    .map(|i| (plugin, plugin.data.form_ids[i].raw()))
+            })
+            .next()
+    }
+
+    // Checks the masters of each plugin in the given load order, returning
+    // the problems found for each plugin in load order. Only the plugins'
+    // headers need to have been parsed. Morrowind doesn't use the master
+    // flag, so masters that aren't master files aren't reported for it.
+    pub fn validate_masters(load_order: &[Plugin]) -> Result<Vec<Vec<MasterDiagnostic>>, Error> {
+        let filenames: Vec<_> = load_order.iter().map(|p| p.unghosted_filename()).collect();
+
+        load_order
+            .iter()
+            .enumerate()
+            .map(|(index, plugin)| {
+                let mut diagnostics = Vec::new();
+                for master in plugin.masters()? {
+                    let master_index = filenames.iter().position(|f| match *f {
+                        Some(ref f) => eq(f.as_str(), master.as_str()),
+                        None => false,
+                    });
+
+                    match master_index {
+                        None => diagnostics.push(MasterDiagnostic::Missing(master)),
+                        Some(i) if i >= index && load_order[i].is_light_master_file() => {
+                            diagnostics.push(MasterDiagnostic::LightMasterLoadsAfter(master))
+                        }
+                        Some(i) if i >= index => {
+                            diagnostics.push(MasterDiagnostic::LoadsAfter(master))
+                        }
+                        Some(i)
+                            if plugin.game_id != GameId::Morrowind
+                                && plugin.is_master_file()
+                                && !load_order[i].is_master_file() =>
+                        {
+                            diagnostics.push(MasterDiagnostic::NotAMasterFile(master))
+                        }
+                        _ => {}
+                    }
+                }
+
+                Ok(diagnostics)
+            })
+            .collect()
+    }
+
+    // Finds the records of each plugin in the given load order that have the
+    // FormID of a master's record, but which the master doesn't add, i.e.
+    // records that are injected into the master. Returns the injected records
+    // for each plugin in load order, ordered by object index. Only the
+    // plugins' FormIDs are needed, so their records don't need to be kept.
+    // Masters that aren't in the load order are skipped, as are masters that
+    // were parsed header-only or with record type or group filters, as not
+    // all their FormIDs are loaded.
+    pub fn find_injected_records(load_order: &[Plugin]) -> Result<Vec<Vec<ResolvedFormId>>, Error> {
+        let filenames: Vec<_> = load_order.iter().map(|p| p.unghosted_filename()).collect();
+        let new_object_indices = load_order
+            .iter()
+            .map(|p| {
+                if p.has_all_form_ids() {
+                    p.new_object_indices().map(Some)
+                } else {
+                    Ok(None)
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        load_order
+            .iter()
+            .map(|plugin| {
+                let master_indices: Vec<_> = plugin
+                    .masters()?
+                    .iter()
+                    .map(|master| {
+                        filenames.iter().position(|f| match *f {
+                            Some(ref f) => eq(f.as_str(), master.as_str()),
+                            None => false,
+                        })
+                    })
+                    .collect();
+
+                let mut injected_records = Vec::new();
+                for form_id in &plugin.data.form_ids {
+                    let master_object_indices =
+                        match master_indices.get(form_id.mod_index() as usize) {
+                            Some(&Some(i)) => match new_object_indices[i] {
+                                Some(ref x) => x,
+                                None => continue,
+                            },
+                            _ => continue,
+                        };
+
+                    if master_object_indices
+                        .binary_search(&form_id.object_index())
+                        .is_err()
+                    {
+                        injected_records.push(plugin.resolve_form_id(form_id.raw())?);
+                    }
+                }
+
+                Ok(injected_records)
+            })
+            .collect()
+    }
+
+    // The CRC is calculated from the same bytes that are parsed, so it's only
+    // available after parsing a whole plugin from a byte slice or file.
+    pub fn crc(&self) -> Option<u32> {
+        self.data.crc
+    }
+
+    // Ghosted plugins are referred to by their filenames without the
+    // .ghost extension.
+    fn unghosted_filename(&self) -> Option<String> {
+        self.filename().map(|f| {
+            let stem_length = f.len().saturating_sub(6);
+            match f.get(stem_length..) {
+                Some(extension) if eq(extension, ".ghost") => f[..stem_length].to_string(),
+                _ => f,
+            }
+        })
+    }
+
     fn has_extension(&self, extension: &str) -> bool {
         if extension.is_empty() {
             return false;
@@ -181,7 +686,7 @@ impl Plugin {
         let mut plugin = Plugin::new(game_id, &filepath.to_path_buf());
 
         match plugin.parse_file(load_header_only) {
-            Ok(_) => true,
+            Ok(_) => !plugin.has_duplicate_form_ids(),
             Err(_) => false,
         }
     }
@@ -210,6 +715,34 @@ impl Plugin {
         Ok(None)
     }
 
+    // Morrowind stores the author in a fixed-length field of the header's HEDR
+    // subrecord, and other games in a CNAM subrecord.
+    pub fn author(&self) -> Result<Option<String>, Error> {
+        let (target_subrecord_type, author_range) = match self.game_id {
+            GameId::Morrowind => ("HEDR", 8..40),
+            _ => ("CNAM", 0..usize::MAX),
+        };
+
+        for subrecord in self.data.header_record.subrecords() {
+            if subrecord.subrecord_type() == target_subrecord_type {
+                let data = subrecord.data();
+                if data.len() < author_range.start {
+                    return Err(Error::ParsingError);
+                }
+
+                let data = &data[author_range.start..data.len().min(author_range.end)];
+                let data = data.split(|b| *b == 0).next().unwrap_or(data);
+
+                return WINDOWS_1252
+                    .decode(data, DecoderTrap::Strict)
+                    .map(Some)
+                    .map_err(Error::DecodeError);
+            }
+        }
+
+        Ok(None)
+    }
+
     pub fn header_version(&self) -> Option<f32> {
         self.data
             .header_record
@@ -220,17 +753,228 @@ impl Plugin {
     }
 
     pub fn record_and_group_count(&self) -> Option<u32> {
-        let count_offset = match self.game_id {
-            GameId::Morrowind => 296,
-            _ => 4,
-        };
+        self.header_subrecord_u32("HEDR", record_and_group_count_offset(self.game_id))
+    }
+
+    // The object index that the Creation Kit will give the next record the
+    // plugin adds. Morrowind plugins don't store one.
+    pub fn next_object_id(&self) -> Option<u32> {
+        match self.game_id {
+            GameId::Morrowind => None,
+            _ => self.header_subrecord_u32("HEDR", 8),
+        }
+    }
+
+    // The value of the header's INTV subrecord, which Skyrim and Fallout 4
+    // plugins use to count the strings that can be tagified.
+    pub fn intv(&self) -> Option<u32> {
+        self.header_subrecord_u32("INTV", 0)
+    }
+
+    // The value of the header's INCC subrecord, which only Skyrim and
+    // Fallout 4 plugins have. Its purpose is unknown.
+    pub fn incc(&self) -> Option<u32> {
+        self.header_subrecord_u32("INCC", 0)
+    }
+
+    // Fallout 4 plugins list transient types in TNAM subrecords, each holding
+    // a form type followed by FormIDs. Other games' plugins have no list, so
+    // this is empty for them.
+    pub fn transient_types(&self) -> Vec<(u32, Vec<u32>)> {
+        if self.game_id != GameId::Fallout4 {
+            return Vec::new();
+        }
 
         self.data
             .header_record
             .subrecords()
             .iter()
+            .filter(|s| s.subrecord_type() == "TNAM" && s.data().len() > 3)
+            .map(|s| {
+                let form_ids = s.data()[4..]
+                    .chunks(4)
+                    .filter(|c| c.len() == 4)
+                    .map(LittleEndian::read_u32)
+                    .collect();
+
+                (LittleEndian::read_u32(s.data()), form_ids)
+            })
+            .collect()
+    }
+
+    // Records and groups are only counted when parsing the whole plugin
+    // without skipping any top-level groups.
+    pub fn count_records_and_groups(&self) -> Option<u32> {
+        self.data.record_and_group_count
+    }
+
+    // Returns None if the count in the header matches the number of records
+    // and groups that were counted, or if either is unavailable.
+    pub fn validate_record_and_group_count(&self) -> Option<RecordAndGroupCountMismatch> {
+        let header_count = self.record_and_group_count()?;
+        let actual_count = self.count_records_and_groups()?;
+
+        if header_count == actual_count {
+            None
+        } else {
+            Some(RecordAndGroupCountMismatch::new(header_count, actual_count))
+        }
+    }
+
+    // Sets the count in the header record held by this plugin, which can
+    // then be written using save_header().
+    pub fn set_record_and_group_count(&mut self, count: u32) -> Result<(), Error> {
+        let count_offset = record_and_group_count_offset(self.game_id);
+
+        match self
+            .data
+            .header_record
+            .subrecords_mut()
+            .iter_mut()
             .find(|s| s.subrecord_type() == "HEDR" && s.data().len() > count_offset)
-            .map(|s| LittleEndian::read_u32(&s.data()[count_offset..count_offset + 4]))
+        {
+            Some(subrecord) => {
+                LittleEndian::write_u32(&mut subrecord.data_mut()[count_offset..], count);
+                Ok(())
+            }
+            None => Err(Error::ParsingError),
+        }
+    }
+
+    // Skyrim and Fallout 4 plugins list some of the records they override in
+    // their header's ONAM subrecord, as the game ignores a master file's
+    // overrides of temporary records that aren't listed. Other games' plugins
+    // have no list, so this is empty for them.
+    pub fn overridden_forms(&self) -> Result<Vec<ResolvedFormId>, Error> {
+        if !self.has_overridden_forms_list() {
+            return Ok(Vec::new());
+        }
+
+        let filename = self.filename().ok_or(Error::NoFilename)?;
+        let masters = self.masters()?;
+
+        Ok(self
+            .data
+            .header_record
+            .subrecords()
+            .iter()
+            .filter(|s| s.subrecord_type() == "ONAM")
+            .flat_map(|s| s.data().chunks(4))
+            .filter(|c| c.len() == 4)
+            .map(|c| ResolvedFormId::new(&filename, &masters, LittleEndian::read_u32(c)))
+            .collect())
+    }
+
+    // Lists this plugin's overrides of records in its masters' temporary
+    // children groups that are missing from its ONAM subrecord. The game
+    // ignores these overrides if the plugin is a master file, so they will be
+    // lost if the plugin is given the master or light master flag. The whole
+    // plugin must have been parsed.
+    pub fn unlisted_temporary_overrides(&self) -> Result<Vec<ResolvedFormId>, Error> {
+        if !self.has_overridden_forms_list() {
+            return Ok(Vec::new());
+        }
+
+        let filename = self.filename().ok_or(Error::NoFilename)?;
+        let masters = self.masters()?;
+        let listed_form_ids = self.overridden_forms()?;
+
+        let mut form_ids: Vec<_> = self
+            .data
+            .temporary_form_ids
+            .iter()
+            .filter(|f| ((*f >> 24) as usize) < masters.len())
+            .map(|f| ResolvedFormId::new(&filename, &masters, *f))
+            .filter(|f| !listed_form_ids.contains(f))
+            .collect();
+
+        form_ids.sort();
+        form_ids.dedup();
+
+        Ok(form_ids)
+    }
+
+    // Checks that the ONAM list holds all of this plugin's overrides of
+    // records in temporary children groups, and only records that this
+    // plugin overrides. The whole plugin must have been parsed.
+    pub fn validate_overridden_forms(&self) -> Result<Vec<OverriddenFormDiagnostic>, Error> {
+        if !self.has_overridden_forms_list() {
+            return Ok(Vec::new());
+        }
+
+        let filename = self.filename().ok_or(Error::NoFilename)?;
+        let masters = self.masters()?;
+        let listed_form_ids = self.overridden_forms()?;
+
+        let mut diagnostics: Vec<_> = self
+            .unlisted_temporary_overrides()?
+            .into_iter()
+            .map(OverriddenFormDiagnostic::NotListed)
+            .collect();
+
+        let form_ids: Vec<_> = self
+            .data
+            .form_ids
+            .iter()
+            .map(|f| ResolvedFormId::new(&filename, &masters, f.raw()))
+            .collect();
+
+        diagnostics.extend(
+            listed_form_ids
+                .into_iter()
+                .filter(|f| !form_ids.contains(f))
+                .map(OverriddenFormDiagnostic::NotOverridden),
+        );
+
+        Ok(diagnostics)
+    }
+
+    // Checks the FormIDs of the records the plugin contains, which requires
+    // the whole plugin to have been parsed. Records that the plugin adds must
+    // have the plugin's own mod index, and object indices below the next
+    // object ID and outside the reserved range. Diagnostics are ordered by
+    // object index. Morrowind records have no FormIDs, so there is nothing to
+    // check for them.
+    pub fn validate_form_ids(&self) -> Result<Vec<FormIdDiagnostic>, Error> {
+        if self.game_id == GameId::Morrowind {
+            return Ok(Vec::new());
+        }
+
+        let masters_count = self.masters()?.len();
+        let next_object_id = self.next_object_id();
+
+        let mut diagnostics = Vec::new();
+        let mut previous_form_id: Option<&HashedFormId> = None;
+        for form_id in &self.data.form_ids {
+            let resolved_form_id = self.resolve_form_id(form_id.raw())?;
+            let is_duplicate = previous_form_id == Some(form_id);
+            previous_form_id = Some(form_id);
+
+            // A record with an invalid mod index resolves to the plugin itself,
+            // so it's reported even if it duplicates one of its records.
+            let mod_index = form_id.mod_index() as usize;
+            if mod_index > masters_count {
+                diagnostics.push(FormIdDiagnostic::InvalidModIndex(
+                    resolved_form_id,
+                    form_id.mod_index(),
+                ));
+            } else if is_duplicate {
+                let diagnostic = FormIdDiagnostic::Duplicate(resolved_form_id);
+                if !diagnostics.contains(&diagnostic) {
+                    diagnostics.push(diagnostic);
+                }
+            } else if mod_index < masters_count {
+                continue;
+            } else if form_id.object_index() < RESERVED_OBJECT_INDEX_END {
+                diagnostics.push(FormIdDiagnostic::InReservedRange(resolved_form_id));
+            } else if let Some(next_object_id) = next_object_id {
+                if form_id.object_index() >= next_object_id {
+                    diagnostics.push(FormIdDiagnostic::NotBelowNextObjectId(resolved_form_id));
+                }
+            }
+        }
+
+        Ok(diagnostics)
     }
 
     pub fn count_override_records(&self) -> usize {
@@ -243,6 +987,159 @@ impl Plugin {
             .count()
     }
 
+    // A master is unused if none of this plugin's records or the FormIDs
+    // they reference have its mod index, so the plugin must have been parsed
+    // keeping all its records. References are read from the fields that the
+    // schemas decode, and any data that they don't decode is checked as if
+    // every four bytes at every offset could be a FormID, so a master is only
+    // unused if nothing could refer to it. No master is unused if any
+    // compressed records couldn't be decompressed, or for Morrowind plugins,
+    // which refer to records by ID instead of by FormID.
+    pub fn unused_masters(&self) -> Result<Vec<String>, Error> {
+        if self.data.kept_records != Some(KeptRecords::All) {
+            return Err(Error::RecordsNotKept);
+        }
+
+        if self.game_id == GameId::Morrowind
+            || self.data.records.iter().any(|r| r.has_unread_subrecords())
+        {
+            return Ok(Vec::new());
+        }
+
+        let masters = self.masters()?;
+        let mut is_used = vec![false; masters.len()];
+
+        let is_localized = self.is_localized_flag_set();
+        let possible_form_ids: Vec<u32> = self
+            .data
+            .records
+            .iter()
+            .flat_map(|r| undecoded_data(r, self.game_id, is_localized))
+            .flat_map(|d| d.windows(4).map(LittleEndian::read_u32))
+            .filter(|f| *f != 0)
+            .collect();
+
+        let referenced_mod_indices: Vec<usize> = self
+            .typed_records()?
+            .iter()
+            .flat_map(|r| r.referenced_form_ids())
+            .chain(possible_form_ids)
+            .map(|f| (f >> 24) as usize)
+            .collect();
+
+        for mod_index in self
+            .data
+            .form_ids
+            .iter()
+            .map(|f| f.mod_index() as usize)
+            .chain(referenced_mod_indices)
+        {
+            if let Some(x) = is_used.get_mut(mod_index) {
+                *x = true;
+            }
+        }
+
+        Ok(masters
+            .into_iter()
+            .zip(is_used)
+            .filter(|&(_, is_used)| !is_used)
+            .map(|(master, _)| master)
+            .collect())
+    }
+
+    // Removes the given masters from the header record held by this plugin.
+    // Only unused masters can be removed, so the plugin must have been parsed
+    // keeping all its records. Removing a master would change the mod indices
+    // of the masters after it, so only masters that come after all the
+    // masters that are kept can be removed. Removing masters would also change
+    // the mod index of the records that the plugin adds, which would mean
+    // rewriting every FormID that refers to them, so masters can't be removed
+    // from a plugin that adds records.
+    pub fn remove_masters(&mut self, masters: &[String]) -> Result<(), Error> {
+        let current_masters = self.masters()?;
+        let is_removed: Vec<bool> = current_masters
+            .iter()
+            .map(|m| masters.iter().any(|r| eq(r.as_str(), m.as_str())))
+            .collect();
+
+        let unused_masters = self.unused_masters()?;
+        if let Some(master) = current_masters
+            .iter()
+            .zip(&is_removed)
+            .filter(|&(_, is_removed)| *is_removed)
+            .map(|(master, _)| master)
+            .find(|m| !unused_masters.contains(m))
+        {
+            return Err(Error::MasterInUse(master.clone()));
+        }
+
+        if let Some(last_kept) = is_removed.iter().rposition(|r| !r) {
+            if let Some(index) = is_removed[..last_kept].iter().position(|r| *r) {
+                return Err(Error::MasterNotRemovable(current_masters[index].clone()));
+            }
+        }
+
+        if is_removed.contains(&true) && self.count_override_records() < self.data.form_ids.len() {
+            return Err(Error::PluginAddsRecords);
+        }
+
+        // Each MAST subrecord is followed by a DATA subrecord.
+        let mut master_index = 0;
+        let mut is_removing = false;
+        self.data.header_record.retain_subrecords(
+            |s| match s.subrecord_type() {
+                "MAST" => {
+                    is_removing = is_removed[master_index];
+                    master_index += 1;
+                    !is_removing
+                }
+                "DATA" if is_removing => {
+                    is_removing = false;
+                    false
+                }
+                _ => {
+                    is_removing = false;
+                    true
+                }
+            },
+            self.game_id,
+        );
+
+        // FormIDs are hashed using the masters, so must be hashed again.
+        let filename = self.filename().ok_or(Error::NoFilename)?;
+        let raw_form_ids: Vec<u32> = self.data.form_ids.iter().map(|f| f.raw()).collect();
+        self.data.form_ids = hashed_form_ids(&raw_form_ids, &filename, &self.masters()?);
+
+        Ok(())
+    }
+
+    // Writes a copy of the file at this plugin's path to the given path,
+    // replacing its header record with the one held by this plugin. The
+    // rest of the file is copied unchanged. The output path may be the same
+    // as this plugin's path.
+    pub fn save_header(&self, output_path: &Path) -> Result<(), Error> {
+        let mut input = Vec::new();
+        File::open(&self.path)?.read_to_end(&mut input)?;
+
+        if !input.starts_with(self.header_type()) {
+            return Err(Error::ParsingError);
+        }
+
+        let header_length = header_length(self.game_id);
+        let original_header = RecordHeader::parse(&input, self.game_id)?.1;
+        let remaining_input = input
+            .get(header_length + original_header.size_of_subrecords() as usize..)
+            .ok_or(Error::ParsingIncomplete)?;
+
+        let mut output = Vec::with_capacity(input.len());
+        self.data.header_record.write(&mut output, self.game_id)?;
+        output.extend_from_slice(remaining_input);
+
+        fs::write(output_path, output)?;
+
+        Ok(())
+    }
+
     pub fn overlaps_with(&self, other: &Self) -> bool {
         let form_ids = &self.data.form_ids;
         let other_form_ids = &other.data.form_ids;
@@ -281,6 +1178,28 @@ impl Plugin {
         }
     }
 
+    fn resolved_records(&self, filename: &str) -> Result<Vec<(ResolvedFormId, &Record)>, Error> {
+        let masters = self.masters()?;
+
+        Ok(self
+            .data
+            .records
+            .iter()
+            .map(|r| {
+                let form_id = ResolvedFormId::new(filename, &masters, r.header().form_id());
+                (form_id, r)
+            })
+            .collect())
+    }
+
+    fn hashed_form_id(&self, form_id: u32) -> Option<HashedFormId> {
+        let filename = self.filename()?;
+        let masters = self.masters().ok()?;
+        let hashed_masters: Vec<_> = masters.iter().map(|m| hash(m)).collect();
+
+        Some(HashedFormId::new(hash(&filename), &hashed_masters, form_id))
+    }
+
     fn header_type(&self) -> &'static [u8] {
         match self.game_id {
             GameId::Morrowind => b"TES3",
@@ -295,11 +1214,84 @@ impl Plugin {
     fn is_light_master_flag_set(&self) -> bool {
         self.data.header_record.header().flags() & 0x200 != 0
     }
+
+    // The sorted object indices of the records that the plugin adds.
+    // Records and groups are only counted if every record was visited, which
+    // is also when every FormID is loaded.
+    fn has_all_form_ids(&self) -> bool {
+        self.data.record_and_group_count.is_some()
+    }
+
+    fn new_object_indices(&self) -> Result<Vec<u32>, Error> {
+        let masters_count = self.masters()?.len();
+
+        let mut object_indices: Vec<_> = self
+            .data
+            .form_ids
+            .iter()
+            .filter(|f| f.mod_index() as usize >= masters_count)
+            .map(|f| f.object_index())
+            .collect();
+        object_indices.sort();
+
+        Ok(object_indices)
+    }
+
+    // FormIDs are sorted, so duplicates are adjacent. Morrowind records all
+    // have a FormID of zero, so they can't be duplicates.
+    fn has_duplicate_form_ids(&self) -> bool {
+        self.game_id != GameId::Morrowind && self.data.form_ids.windows(2).any(|w| w[0] == w[1])
+    }
+
+    fn header_subrecord_u32(&self, subrecord_type: &str, offset: usize) -> Option<u32> {
+        self.data
+            .header_record
+            .subrecords()
+            .iter()
+            .find(|s| s.subrecord_type() == subrecord_type && s.data().len() >= offset + 4)
+            .map(|s| LittleEndian::read_u32(&s.data()[offset..offset + 4]))
+    }
+
+    fn has_overridden_forms_list(&self) -> bool {
+        self.game_id == GameId::Skyrim
+            || self.game_id == GameId::SkyrimSE
+            || self.game_id == GameId::Fallout4
+    }
+
+    fn is_localized_flag_set(&self) -> bool {
+        match self.game_id {
+            GameId::Skyrim | GameId::SkyrimSE | GameId::Fallout4 => {
+                self.data.header_record.header().flags() & 0x80 != 0
+            }
+            _ => false,
+        }
+    }
+}
+
+// Only the data read from the plugin is serialized: FormIDs are given as they
+// appear in the plugin, and records are only included if they were kept. A
+// plugin can't be deserialized, as it can't be rebuilt from that data.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Plugin {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let form_ids: Vec<_> = self.data.form_ids.iter().map(|f| f.raw()).collect();
+
+        let mut state = serializer.serialize_struct("Plugin", 6)?;
+        state.serialize_field("game_id", &self.game_id)?;
+        state.serialize_field("path", &self.path)?;
+        state.serialize_field("header_record", &self.data.header_record)?;
+        state.serialize_field("form_ids", &form_ids)?;
+        state.serialize_field("records", &self.data.records)?;
+        state.serialize_field("crc", &self.data.crc)?;
+        state.end()
+    }
 }
 
 fn hashed_form_ids(form_ids: &[u32], filename: &str, masters: &[String]) -> Vec<HashedFormId> {
     let hashed_filename = hash(filename);
-    let hashed_masters: Vec<_> = masters.iter().map(|m| hash(&m)).collect();
+    let hashed_masters: Vec<_> = masters.iter().map(|m| hash(m)).collect();
 
     let mut form_ids: Vec<_> = form_ids
         .iter()
@@ -319,6 +1311,31 @@ fn hash(string: &str) -> u64 {
     hasher.finish()
 }
 
+fn kept_records(options: &ParseOptions) -> Option<KeptRecords> {
+    if options.keeps_all_records() {
+        Some(KeptRecords::All)
+    } else if options.keeps_records() {
+        Some(KeptRecords::Selected)
+    } else {
+        None
+    }
+}
+
+fn counted_records_and_groups(contents: &GroupContents, options: &ParseOptions) -> Option<u32> {
+    if options.includes_all_top_level_groups() {
+        Some(contents.record_and_group_count)
+    } else {
+        None
+    }
+}
+
+fn record_and_group_count_offset(game_id: GameId) -> usize {
+    match game_id {
+        GameId::Morrowind => 296,
+        _ => 4,
+    }
+}
+
 fn count_masters(header_record: &Record) -> usize {
     header_record
         .subrecords()
@@ -340,57 +1357,118 @@ fn masters(header_record: &Record) -> Result<Vec<String>, Error> {
         }).collect::<Result<Vec<String>, Error>>()
 }
 
-fn parse_form_ids<'a>(input: &'a [u8], game_id: GameId) -> IResult<&'a [u8], Vec<u32>> {
-    let mut form_ids = Vec::new();
+fn parse_records<'a>(
+    input: &'a [u8],
+    game_id: GameId,
+    options: &ParseOptions,
+) -> IResult<&'a [u8], GroupContents> {
+    let mut contents = GroupContents::default();
     let mut remaining_input = input;
 
     if game_id == GameId::Morrowind {
         while !remaining_input.is_empty() {
-            let (input, form_id) = Record::parse_form_id(remaining_input, game_id)?;
+            let (input, _) = parse_record(remaining_input, game_id, options, &mut contents)?;
             remaining_input = input;
-
-            form_ids.push(form_id);
+        }
+    } else if options.is_parallel() {
+        let (input, groups) = Group::split(remaining_input, game_id)?;
+        remaining_input = input;
+
+        let groups_contents = groups
+            .par_iter()
+            .map(|group| {
+                let mut group_contents = GroupContents::default();
+                Group::parse_for_form_ids(group, game_id, options, &mut group_contents)
+                    .map(|_| group_contents)
+            })
+            .collect::<Result<Vec<GroupContents>, _>>()?;
+
+        for mut group_contents in groups_contents {
+            contents.append(&mut group_contents);
         }
     } else {
         while !remaining_input.is_empty() {
-            let (input, _) = Group::parse_for_form_ids(remaining_input, game_id, &mut form_ids)?;
+            let (input, _) =
+                Group::parse_for_form_ids(remaining_input, game_id, options, &mut contents)?;
             remaining_input = input;
         }
     }
 
-    Ok((remaining_input, form_ids))
+    Ok((remaining_input, contents))
+}
+
+fn read_records<R: Read + Seek>(
+    reader: &mut R,
+    game_id: GameId,
+    options: &ParseOptions,
+) -> Result<GroupContents, Error> {
+    let position = reader.stream_position()?;
+    let end = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(position))?;
+
+    let mut contents = GroupContents::default();
+    let mut header_bytes: Vec<u8> = vec![0; header_length(game_id)];
+
+    while reader.stream_position()? < end {
+        if game_id == GameId::Morrowind {
+            reader.read_exact(&mut header_bytes)?;
+            read_record(reader, game_id, &header_bytes, options, &mut contents)?;
+        } else {
+            Group::read_form_ids(reader, game_id, options, &mut contents)?;
+        }
+    }
+
+    // Skipping a truncated record seeks past the end of the stream.
+    if reader.stream_position()? != end {
+        return Err(Error::ParsingError);
+    }
+
+    Ok(contents)
 }
 
 fn parse_plugin<'a>(
     input: &'a [u8],
     game_id: GameId,
     filename: &str,
-    load_header_only: bool,
+    options: &ParseOptions,
 ) -> IResult<&'a [u8], PluginData> {
     let (input1, header_record) = try_parse!(input, apply!(Record::parse, game_id, false));
 
-    if load_header_only {
+    if options.is_header_only() {
         return Ok((
             input1,
             PluginData {
                 header_record,
                 form_ids: Vec::new(),
+                records: Vec::new(),
+                kept_records: None,
+                editor_ids: EditorIds::default(),
+                crc: None,
+                record_and_group_count: None,
+                temporary_form_ids: Vec::new(),
             },
         ));
     }
 
-    let (input2, form_ids) = try_parse!(input1, apply!(parse_form_ids, game_id));
+    let (input2, contents) = try_parse!(input1, apply!(parse_records, game_id, options));
 
     let masters = masters(&header_record)
         .map_err(|_| nom::Err::Error(nom::Context::Code(input, ErrorKind::Custom(1))))?;
 
-    let form_ids = hashed_form_ids(&form_ids, filename, &masters);
+    let form_ids = hashed_form_ids(&contents.form_ids, filename, &masters);
+    let record_and_group_count = counted_records_and_groups(&contents, options);
 
     Ok((
         input2,
         PluginData {
             header_record,
             form_ids,
+            records: contents.records,
+            kept_records: kept_records(options),
+            editor_ids: EditorIds::new(&contents.editor_ids),
+            crc: Some(crc32fast::hash(input)),
+            record_and_group_count,
+            temporary_form_ids: contents.temporary_form_ids,
         },
     ))
 }
@@ -399,7 +1477,13 @@ fn parse_plugin<'a>(
 mod tests {
     use super::*;
 
+    use std::env;
     use std::fs::read;
+    use std::io::Write;
+
+    use byteorder::WriteBytesExt;
+
+    use builder::{GroupBuilder, PluginBuilder, RecordBuilder};
 
     fn write_invalid_plugin() {
         use std::io::Write;
@@ -439,13 +1523,389 @@ mod tests {
     }
 
     #[test]
-    fn parse_file_should_succeed_for_morrowind_plugin() {
-        let mut plugin = Plugin::new(
-            GameId::Morrowind,
-            Path::new("testing-plugins/Morrowind/Data Files/Blank.esm"),
-        );
+    fn parse_file_should_succeed_for_morrowind_plugin() {
+        let mut plugin = Plugin::new(
+            GameId::Morrowind,
+            Path::new("testing-plugins/Morrowind/Data Files/Blank.esm"),
+        );
+
+        assert!(plugin.parse_file(false).is_ok());
+    }
+
+    #[test]
+    fn parse_reader_should_read_the_same_data_as_parse_file() {
+        use std::io::Cursor;
+
+        let path = Path::new("testing-plugins/Skyrim/Data/Blank.esm");
+        let mut plugin = Plugin::new(GameId::Skyrim, path);
+        let mut other_plugin = Plugin::new(GameId::Skyrim, path);
+
+        let reader = Cursor::new(read(path).unwrap());
+
+        assert!(plugin.parse_file(false).is_ok());
+        assert!(other_plugin.parse_reader(reader, false).is_ok());
+
+        assert_eq!(10, other_plugin.data.form_ids.len());
+        assert_eq!(plugin, other_plugin);
+    }
+
+    #[test]
+    fn parse_reader_should_succeed_for_morrowind_plugin() {
+        use std::io::Cursor;
+
+        let path = Path::new("testing-plugins/Morrowind/Data Files/Blank.esm");
+        let mut plugin = Plugin::new(GameId::Morrowind, path);
+        let mut other_plugin = Plugin::new(GameId::Morrowind, path);
+
+        let reader = Cursor::new(read(path).unwrap());
+
+        assert!(plugin.parse_file(false).is_ok());
+        assert!(other_plugin.parse_reader(reader, false).is_ok());
+
+        assert_eq!(plugin, other_plugin);
+    }
+
+    #[test]
+    fn parse_reader_should_only_read_the_header_record_if_load_header_only_is_true() {
+        use std::io::Cursor;
+
+        let path = Path::new("testing-plugins/Skyrim/Data/Blank.esm");
+        let mut plugin = Plugin::new(GameId::Skyrim, path);
+        let mut reader = Cursor::new(read(path).unwrap());
+
+        assert!(plugin.parse_reader(&mut reader, true).is_ok());
+
+        assert_eq!(0, plugin.data.form_ids.len());
+        assert_eq!(0x1004C, reader.position());
+    }
+
+    #[test]
+    fn parse_reader_should_fail_for_a_non_plugin_file() {
+        use std::io::Cursor;
+
+        let mut plugin = Plugin::new(GameId::Skyrim, Path::new("README.md"));
+        let reader = Cursor::new(read("README.md").unwrap());
+
+        assert!(plugin.parse_reader(reader, false).is_err());
+    }
+
+    #[test]
+    fn parse_reader_should_fail_if_the_last_record_or_group_is_truncated() {
+        use std::io::Cursor;
+
+        for &game_id in &[GameId::Morrowind, GameId::Skyrim] {
+            let mut bytes = PluginBuilder::new(game_id)
+                .with_record(RecordBuilder::new("BOOK", 0).with_subrecord("DATA", &[0; 8]))
+                .build()
+                .unwrap();
+            let length = bytes.len() - 2;
+            bytes.truncate(length);
+
+            let mut plugin = Plugin::new(game_id, Path::new("Test.esp"));
+            assert!(plugin.parse(&bytes, false).is_err());
+            assert!(plugin.parse_reader(Cursor::new(&bytes), false).is_err());
+        }
+    }
+
+    #[test]
+    fn parse_file_with_options_should_only_keep_records_of_the_given_types() {
+        let mut plugin = Plugin::new(
+            GameId::Skyrim,
+            Path::new("testing-plugins/Skyrim/Data/Blank.esm"),
+        );
+
+        let options = ParseOptions::whole_plugin().with_record_types(&["NPC_"]);
+        assert!(plugin.parse_file_with_options(&options).is_ok());
+
+        assert!(!plugin.records().is_empty());
+        assert_eq!(plugin.records().len(), plugin.data.form_ids.len());
+        assert!(plugin
+            .records()
+            .iter()
+            .all(|r| r.header().record_type() == "NPC_"));
+    }
+
+    #[test]
+    fn parse_reader_with_options_should_keep_the_same_records_as_parse_file_with_options() {
+        use std::io::Cursor;
+
+        let path = Path::new("testing-plugins/Skyrim/Data/Blank.esm");
+        let mut plugin = Plugin::new(GameId::Skyrim, path);
+        let mut other_plugin = Plugin::new(GameId::Skyrim, path);
+        let reader = Cursor::new(read(path).unwrap());
+
+        let options = ParseOptions::whole_plugin().with_record_types(&["NPC_", "CELL"]);
+        assert!(plugin.parse_file_with_options(&options).is_ok());
+        assert!(other_plugin
+            .parse_reader_with_options(reader, &options)
+            .is_ok());
+
+        assert_eq!(plugin, other_plugin);
+    }
+
+    #[test]
+    fn plugin_should_be_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<Plugin>();
+    }
+
+    #[test]
+    fn parse_files_should_parse_each_file_with_its_options_and_keep_their_order() {
+        let files = vec![
+            (
+                Path::new("testing-plugins/Skyrim/Data/Blank.esm"),
+                ParseOptions::whole_plugin(),
+            ),
+            (Path::new("Blank.esm"), ParseOptions::whole_plugin()),
+            (
+                Path::new("testing-plugins/Skyrim/Data/Blank - Master Dependent.esm"),
+                ParseOptions::header_only(),
+            ),
+        ];
+
+        let results = Plugin::parse_files(GameId::Skyrim, &files);
+
+        assert_eq!(3, results.len());
+
+        let plugin = results[0].as_ref().unwrap();
+        assert_eq!(files[0].0, plugin.path());
+        assert_eq!(10, plugin.data.form_ids.len());
+
+        assert!(results[1].is_err());
+
+        let plugin = results[2].as_ref().unwrap();
+        assert_eq!(files[2].0, plugin.path());
+        assert_eq!(1, plugin.masters().unwrap().len());
+        assert!(plugin.data.form_ids.is_empty());
+    }
+
+    #[test]
+    fn parse_file_with_options_in_parallel_should_give_the_same_result_as_parsing_sequentially() {
+        let path = Path::new("testing-plugins/SkyrimSE/Data/Hearthfires.esm");
+        let mut plugin = Plugin::new(GameId::SkyrimSE, path);
+        let mut other_plugin = Plugin::new(GameId::SkyrimSE, path);
+
+        let options = ParseOptions::whole_plugin().in_parallel();
+        assert!(plugin.parse_file(false).is_ok());
+        assert!(other_plugin.parse_file_with_options(&options).is_ok());
+
+        assert!(!other_plugin.data.form_ids.is_empty());
+        assert_eq!(plugin, other_plugin);
+
+        let options = ParseOptions::whole_plugin().with_record_types(&["CELL", "REFR"]);
+        assert!(plugin.parse_file_with_options(&options).is_ok());
+        assert!(other_plugin
+            .parse_file_with_options(&options.in_parallel())
+            .is_ok());
+
+        assert_eq!(plugin, other_plugin);
+    }
+
+    #[test]
+    fn parse_with_options_in_parallel_should_error_for_a_top_level_group_with_a_size_of_zero() {
+        let mut bytes = PluginBuilder::new(GameId::Skyrim)
+            .with_record(RecordBuilder::new("WEAP", 0x800))
+            .build()
+            .unwrap();
+        let group_index = bytes.windows(4).position(|w| w == b"GRUP").unwrap();
+        LittleEndian::write_u32(&mut bytes[group_index + 4..], 0);
+
+        let options = ParseOptions::whole_plugin().in_parallel();
+        let mut plugin = Plugin::new(GameId::Skyrim, Path::new("Test.esp"));
+
+        assert!(plugin.parse_with_options(&bytes, &options).is_err());
+    }
+
+    #[test]
+    fn parse_file_should_not_keep_records() {
+        let mut plugin = Plugin::new(
+            GameId::Skyrim,
+            Path::new("testing-plugins/Skyrim/Data/Blank.esm"),
+        );
+
+        assert!(plugin.parse_file(false).is_ok());
+
+        assert!(plugin.records().is_empty());
+    }
+
+    #[test]
+    fn typed_records_should_decode_the_kept_records_that_have_a_schema() {
+        let mut plugin = Plugin::new(
+            GameId::Skyrim,
+            Path::new("testing-plugins/Skyrim/Data/Blank.esm"),
+        );
+
+        let options = ParseOptions::whole_plugin().with_record_types(&["NPC_"]);
+        assert!(plugin.parse_file_with_options(&options).is_ok());
+
+        let typed_records = plugin.typed_records().unwrap();
+
+        assert_eq!(plugin.records().len(), typed_records.len());
+        assert!(typed_records.iter().all(|r| r.record_type() == "NPC_"));
+        assert!(typed_records.iter().all(|r| r.editor_id().is_some()));
+    }
+
+    #[test]
+    fn resolve_form_id_should_resolve_against_the_plugins_masters() {
+        let mut plugin = Plugin::new(
+            GameId::Skyrim,
+            Path::new("testing-plugins/Skyrim/Data/Blank - Master Dependent.esm"),
+        );
+
+        assert!(plugin.parse_file(true).is_ok());
+
+        let form_id = plugin.resolve_form_id(0xCF0).unwrap();
+        assert_eq!("Blank.esm", form_id.plugin_name());
+        assert_eq!(0xCF0, form_id.object_index());
+
+        let form_id = plugin.resolve_form_id(0x0100_0CF0).unwrap();
+        assert_eq!("Blank - Master Dependent.esm", form_id.plugin_name());
+    }
+
+    #[test]
+    fn references_should_be_empty_if_no_records_were_kept() {
+        let mut plugin = Plugin::new(
+            GameId::Skyrim,
+            Path::new("testing-plugins/Skyrim/Data/Blank.esm"),
+        );
+
+        assert!(plugin.parse_file(false).is_ok());
+
+        assert!(plugin.references().unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_file_cached_should_give_the_same_result_as_parse_file() {
+        let directory = std::env::temp_dir().join("esplugin-parse-file-cached-test");
+        if directory.exists() {
+            std::fs::remove_dir_all(&directory).unwrap();
+        }
+        let cache = ParseCache::new(&directory);
+        let path = Path::new("testing-plugins/Skyrim/Data/Blank - Master Dependent.esm");
+
+        let mut plugin = Plugin::new(GameId::Skyrim, path);
+        assert!(plugin.parse_file(false).is_ok());
+
+        let mut uncached_plugin = Plugin::new(GameId::Skyrim, path);
+        assert!(uncached_plugin.parse_file_cached(&cache, false).is_ok());
+        assert_eq!(plugin, uncached_plugin);
+        assert_eq!(1, std::fs::read_dir(&directory).unwrap().count());
+
+        let mut cached_plugin = Plugin::new(GameId::Skyrim, path);
+        assert!(cached_plugin.parse_file_cached(&cache, false).is_ok());
+        assert_eq!(plugin.masters().unwrap(), cached_plugin.masters().unwrap());
+        assert_eq!(
+            plugin.count_override_records(),
+            cached_plugin.count_override_records()
+        );
+        assert!(plugin.overlaps_with(&cached_plugin));
+        assert_eq!(plugin.crc(), cached_plugin.crc());
+    }
+
+    #[test]
+    fn editor_ids_should_be_empty_if_only_the_header_was_parsed() {
+        let mut plugin = Plugin::new(
+            GameId::Skyrim,
+            Path::new("testing-plugins/Skyrim/Data/Blank.esm"),
+        );
+
+        assert!(plugin.parse_file(true).is_ok());
+        assert!(plugin.data.editor_ids.iter().next().is_none());
+    }
+
+    #[test]
+    fn editor_ids_should_be_empty_for_a_morrowind_plugin() {
+        let bytes = PluginBuilder::new(GameId::Morrowind)
+            .with_record(RecordBuilder::new("BOOK", 0).with_editor_id("a", GameId::Morrowind))
+            .build()
+            .unwrap();
+
+        let mut plugin = Plugin::new(GameId::Morrowind, Path::new("Test.esp"));
+        assert!(plugin.parse(&bytes, false).is_ok());
+
+        assert!(plugin.editor_id(0).is_none());
+        assert!(plugin.form_id_by_editor_id("a").is_none());
+    }
+
+    #[test]
+    fn form_id_by_editor_id_should_be_case_insensitive() {
+        let mut plugin = Plugin::new(
+            GameId::Skyrim,
+            Path::new("testing-plugins/Skyrim/Data/Blank.esm"),
+        );
+
+        assert!(plugin.parse_file(false).is_ok());
+
+        let (form_id, editor_id) = plugin.data.editor_ids.iter().next().unwrap();
+        let editor_id = editor_id.to_string();
+
+        assert_eq!(Some(editor_id.as_str()), plugin.editor_id(form_id));
+        assert_eq!(Some(form_id), plugin.form_id_by_editor_id(&editor_id));
+        assert_eq!(
+            Some(form_id),
+            plugin.form_id_by_editor_id(&editor_id.to_uppercase())
+        );
+    }
+
+    #[test]
+    fn resolve_editor_id_should_find_the_last_plugin_containing_the_record() {
+        let mut master = Plugin::new(
+            GameId::Skyrim,
+            Path::new("testing-plugins/Skyrim/Data/Blank.esm"),
+        );
+        let mut dependent = Plugin::new(
+            GameId::Skyrim,
+            Path::new("testing-plugins/Skyrim/Data/Blank - Master Dependent.esm"),
+        );
+
+        assert!(master.parse_file(false).is_ok());
+        assert!(dependent.parse_file(false).is_ok());
+
+        let (form_id, editor_id) = master.data.editor_ids.iter().next().unwrap();
+        let editor_id = editor_id.to_string();
+        let plugins = vec![master.clone(), dependent];
+
+        let (plugin, _) = Plugin::resolve_editor_id(&plugins, &editor_id).unwrap();
+        let hashed_form_id = master.hashed_form_id(form_id).unwrap();
+        assert!(plugin.data.form_ids.binary_search(&hashed_form_id).is_ok());
+
+        assert_eq!(
+            Some(form_id),
+            Plugin::resolve_editor_id(&plugins[..1], &editor_id).map(|(_, f)| f)
+        );
+        assert!(Plugin::resolve_editor_id(&plugins, "NotAnEditorId").is_none());
+    }
+
+    #[test]
+    fn crc_should_be_none_if_only_the_header_was_parsed() {
+        let mut plugin = Plugin::new(
+            GameId::Skyrim,
+            Path::new("testing-plugins/Skyrim/Data/Blank.esm"),
+        );
+
+        assert!(plugin.crc().is_none());
+        assert!(plugin.parse_file(true).is_ok());
+        assert!(plugin.crc().is_none());
+    }
+
+    #[test]
+    fn crc_should_be_the_crc32_of_the_whole_plugin_file() {
+        let path = Path::new("testing-plugins/Skyrim/Data/Blank.esm");
+        let mut plugin = Plugin::new(GameId::Skyrim, path);
 
         assert!(plugin.parse_file(false).is_ok());
+        assert_eq!(Some(crc32fast::hash(&read(path).unwrap())), plugin.crc());
+    }
+
+    #[test]
+    fn crc_should_be_calculated_when_parsing_in_parallel() {
+        let path = Path::new("testing-plugins/Skyrim/Data/Blank.esm");
+        let mut plugin = Plugin::new(GameId::Skyrim, path);
+        let options = ParseOptions::whole_plugin().in_parallel();
+
+        assert!(plugin.parse_file_with_options(&options).is_ok());
+        assert_eq!(Some(crc32fast::hash(&read(path).unwrap())), plugin.crc());
     }
 
     #[test]
@@ -879,6 +2339,127 @@ mod tests {
         assert!(plugin.description().is_err());
     }
 
+    #[test]
+    fn author_should_return_the_morrowind_plugin_header_hedr_subrecord_field() {
+        let bytes = PluginBuilder::new(GameId::Morrowind)
+            .with_author("An author")
+            .with_description("A description")
+            .build()
+            .unwrap();
+
+        let mut plugin = Plugin::new(GameId::Morrowind, Path::new("Test.esp"));
+        assert!(plugin.parse(&bytes, true).is_ok());
+
+        assert_eq!("An author", plugin.author().unwrap().unwrap());
+    }
+
+    #[test]
+    fn author_should_return_the_plugin_header_cnam_subrecord_content() {
+        let bytes = PluginBuilder::new(GameId::Skyrim)
+            .with_author("An author")
+            .build()
+            .unwrap();
+
+        let mut plugin = Plugin::new(GameId::Skyrim, Path::new("Test.esp"));
+        assert!(plugin.parse(&bytes, true).is_ok());
+
+        assert_eq!("An author", plugin.author().unwrap().unwrap());
+    }
+
+    #[test]
+    fn next_object_id_intv_and_incc_should_return_the_plugin_header_values() {
+        let bytes = PluginBuilder::new(GameId::Skyrim)
+            .with_next_object_id(0x1234)
+            .with_header_subrecord("INTV", &[0x10, 0, 0, 0])
+            .with_header_subrecord("INCC", &[0x20, 0, 0, 0])
+            .build()
+            .unwrap();
+
+        let mut plugin = Plugin::new(GameId::Skyrim, Path::new("Test.esp"));
+        assert!(plugin.parse(&bytes, true).is_ok());
+
+        assert_eq!(Some(0x1234), plugin.next_object_id());
+        assert_eq!(Some(0x10), plugin.intv());
+        assert_eq!(Some(0x20), plugin.incc());
+    }
+
+    #[test]
+    fn next_object_id_should_be_none_for_morrowind() {
+        let bytes = PluginBuilder::new(GameId::Morrowind).build().unwrap();
+
+        let mut plugin = Plugin::new(GameId::Morrowind, Path::new("Test.esp"));
+        assert!(plugin.parse(&bytes, true).is_ok());
+
+        assert!(plugin.next_object_id().is_none());
+        assert!(plugin.intv().is_none());
+    }
+
+    #[test]
+    fn transient_types_should_return_the_fallout4_plugin_header_tnam_subrecords() {
+        let bytes = PluginBuilder::new(GameId::Fallout4)
+            .with_header_subrecord("TNAM", &[0x2A, 0, 0, 0, 0x01, 0x08, 0, 0, 0x02, 0x08, 0, 0])
+            .with_header_subrecord("TNAM", &[0x3F, 0, 0, 0])
+            .build()
+            .unwrap();
+
+        let mut plugin = Plugin::new(GameId::Fallout4, Path::new("Test.esp"));
+        assert!(plugin.parse(&bytes, true).is_ok());
+
+        assert_eq!(
+            vec![(0x2A, vec![0x801, 0x802]), (0x3F, vec![])],
+            plugin.transient_types()
+        );
+
+        let mut plugin = Plugin::new(GameId::Skyrim, Path::new("Test.esp"));
+        assert!(plugin.parse(&bytes, true).is_ok());
+
+        assert!(plugin.transient_types().is_empty());
+    }
+
+    #[test]
+    fn master_sizes_should_be_empty_for_games_other_than_morrowind() {
+        let bytes = PluginBuilder::new(GameId::Oblivion)
+            .with_master_and_size("Blank.esm", 10)
+            .build()
+            .unwrap();
+
+        let mut plugin = Plugin::new(GameId::Oblivion, Path::new("Test.esp"));
+        assert!(plugin.parse(&bytes, true).is_ok());
+
+        assert!(plugin.master_sizes().unwrap().is_empty());
+    }
+
+    #[test]
+    fn validate_master_sizes_should_find_morrowind_masters_whose_size_has_changed() {
+        let data_path = Path::new("testing-plugins/Morrowind/Data Files");
+        let size = fs::metadata(data_path.join("Blank.esm")).unwrap().len();
+
+        let bytes = PluginBuilder::new(GameId::Morrowind)
+            .with_master_and_size("Blank.esm", size)
+            .with_master_and_size("Blank.esp", size + 1)
+            .with_master_and_size("Missing.esm", 5)
+            .build()
+            .unwrap();
+
+        let mut plugin = Plugin::new(GameId::Morrowind, Path::new("Test.esp"));
+        assert!(plugin.parse(&bytes, true).is_ok());
+
+        assert_eq!(
+            vec![
+                ("Blank.esm".to_string(), size),
+                ("Blank.esp".to_string(), size + 1),
+                ("Missing.esm".to_string(), 5),
+            ],
+            plugin.master_sizes().unwrap()
+        );
+
+        let mismatches = plugin.validate_master_sizes(data_path).unwrap();
+        assert_eq!(1, mismatches.len());
+        assert_eq!("Blank.esp", mismatches[0].master());
+        assert_eq!(size + 1, mismatches[0].expected_size());
+        assert_eq!(size, mismatches[0].actual_size());
+    }
+
     #[test]
     fn header_version_should_be_none_for_a_plugin_hedr_subrecord_that_is_too_small() {
         let mut plugin = Plugin::new(
@@ -985,6 +2566,503 @@ mod tests {
         assert_eq!(10, plugin.record_and_group_count().unwrap());
     }
 
+    fn parse_headers(paths: &[&str]) -> Vec<Plugin> {
+        paths
+            .iter()
+            .map(|p| {
+                let mut plugin = Plugin::new(GameId::Skyrim, Path::new(p));
+                assert!(plugin.parse_file(true).is_ok());
+                plugin
+            })
+            .collect()
+    }
+
+    #[test]
+    fn validate_masters_should_report_masters_that_are_missing_or_load_after_the_plugin() {
+        let load_order = parse_headers(&[
+            "testing-plugins/Skyrim/Data/Blank - Master Dependent.esm",
+            "testing-plugins/Skyrim/Data/Blank.esm",
+        ]);
+
+        let diagnostics = Plugin::validate_masters(&load_order).unwrap();
+        assert_eq!(
+            vec![
+                vec![MasterDiagnostic::LoadsAfter("Blank.esm".to_string())],
+                Vec::new(),
+            ],
+            diagnostics
+        );
+
+        let diagnostics = Plugin::validate_masters(&load_order[..1]).unwrap();
+        assert_eq!(
+            vec![vec![MasterDiagnostic::Missing("Blank.esm".to_string())]],
+            diagnostics
+        );
+    }
+
+    #[test]
+    fn validate_masters_should_match_ghosted_plugins_case_insensitively() {
+        let load_order = parse_headers(&[
+            "testing-plugins/Skyrim/Data/Blank.EsM.GHOST",
+            "testing-plugins/Skyrim/Data/Blank - Master Dependent.esm",
+        ]);
+
+        let diagnostics = Plugin::validate_masters(&load_order).unwrap();
+        assert!(diagnostics.iter().all(|d| d.is_empty()));
+    }
+
+    fn master_file_load_order(game_id: GameId) -> Vec<Plugin> {
+        let master_bytes = PluginBuilder::new(game_id).build().unwrap();
+        let plugin_bytes = PluginBuilder::new(game_id)
+            .with_flags(0x1)
+            .with_master("Blank.esp")
+            .build()
+            .unwrap();
+
+        let mut master = Plugin::new(game_id, Path::new("Blank.esp"));
+        assert!(master.parse(&master_bytes, true).is_ok());
+        let mut plugin = Plugin::new(game_id, Path::new("Blank - Master Dependent.esm"));
+        assert!(plugin.parse(&plugin_bytes, true).is_ok());
+
+        vec![master, plugin]
+    }
+
+    #[test]
+    fn validate_masters_should_report_non_master_masters_of_master_files() {
+        for &game_id in &[
+            GameId::Oblivion,
+            GameId::Fallout3,
+            GameId::FalloutNV,
+            GameId::Skyrim,
+            GameId::SkyrimSE,
+            GameId::Fallout4,
+        ] {
+            let load_order = master_file_load_order(game_id);
+
+            let diagnostics = Plugin::validate_masters(&load_order).unwrap();
+            assert_eq!(
+                vec![
+                    Vec::new(),
+                    vec![MasterDiagnostic::NotAMasterFile("Blank.esp".to_string())],
+                ],
+                diagnostics
+            );
+        }
+    }
+
+    #[test]
+    fn validate_masters_should_not_report_non_master_masters_for_morrowind() {
+        let load_order = master_file_load_order(GameId::Morrowind);
+
+        let diagnostics = Plugin::validate_masters(&load_order).unwrap();
+        assert!(diagnostics.iter().all(|d| d.is_empty()));
+    }
+
+    fn light_master_load_order(game_id: GameId) -> Vec<Plugin> {
+        let master_bytes = PluginBuilder::new(game_id).build().unwrap();
+        let plugin_bytes = PluginBuilder::new(game_id)
+            .with_master("Blank.esl")
+            .build()
+            .unwrap();
+
+        let mut plugin = Plugin::new(game_id, Path::new("Blank - Master Dependent.esp"));
+        assert!(plugin.parse(&plugin_bytes, true).is_ok());
+        let mut master = Plugin::new(game_id, Path::new("Blank.esl"));
+        assert!(master.parse(&master_bytes, true).is_ok());
+
+        vec![plugin, master]
+    }
+
+    #[test]
+    fn validate_masters_should_report_light_masters_that_load_after_the_plugin() {
+        for &game_id in &[GameId::SkyrimSE, GameId::Fallout4] {
+            let load_order = light_master_load_order(game_id);
+
+            let diagnostics = Plugin::validate_masters(&load_order).unwrap();
+            assert_eq!(
+                vec![
+                    vec![MasterDiagnostic::LightMasterLoadsAfter(
+                        "Blank.esl".to_string()
+                    )],
+                    Vec::new(),
+                ],
+                diagnostics
+            );
+        }
+    }
+
+    #[test]
+    fn validate_masters_should_only_report_light_masters_for_games_with_light_plugins() {
+        let load_order = light_master_load_order(GameId::Skyrim);
+
+        let diagnostics = Plugin::validate_masters(&load_order).unwrap();
+        assert_eq!(
+            vec![
+                vec![MasterDiagnostic::LoadsAfter("Blank.esl".to_string())],
+                Vec::new(),
+            ],
+            diagnostics
+        );
+    }
+
+    #[test]
+    fn count_records_and_groups_should_only_be_available_if_no_groups_were_skipped() {
+        let mut plugin = Plugin::new(
+            GameId::Skyrim,
+            Path::new("testing-plugins/Skyrim/Data/Blank.esp"),
+        );
+
+        assert!(plugin.parse_file(true).is_ok());
+        assert!(plugin.count_records_and_groups().is_none());
+
+        let options = ParseOptions::whole_plugin().with_record_types(&["NPC_"]);
+        assert!(plugin.parse_file_with_options(&options).is_ok());
+        assert!(plugin.count_records_and_groups().is_none());
+
+        assert!(plugin.parse_file(false).is_ok());
+        assert_eq!(
+            plugin.record_and_group_count(),
+            plugin.count_records_and_groups()
+        );
+    }
+
+    #[test]
+    fn validate_record_and_group_count_should_report_a_mismatch_until_the_count_is_set() {
+        let mut plugin = Plugin::new(
+            GameId::Skyrim,
+            Path::new("testing-plugins/Skyrim/Data/Blank.esp"),
+        );
+
+        assert!(plugin.parse_file(false).is_ok());
+        assert!(plugin.validate_record_and_group_count().is_none());
+
+        let actual_count = plugin.count_records_and_groups().unwrap();
+        assert!(plugin.set_record_and_group_count(actual_count + 1).is_ok());
+        assert_eq!(Some(actual_count + 1), plugin.record_and_group_count());
+
+        let mismatch = plugin.validate_record_and_group_count().unwrap();
+        assert_eq!(actual_count + 1, mismatch.header_count());
+        assert_eq!(actual_count, mismatch.actual_count());
+
+        assert!(plugin.set_record_and_group_count(actual_count).is_ok());
+        assert!(plugin.validate_record_and_group_count().is_none());
+    }
+
+    #[test]
+    fn overridden_forms_should_be_empty_for_games_without_an_onam_subrecord() {
+        let mut plugin = Plugin::new(
+            GameId::Oblivion,
+            Path::new("testing-plugins/Oblivion/Data/Blank - Master Dependent.esm"),
+        );
+
+        assert!(plugin.parse_file(false).is_ok());
+        assert!(plugin.overridden_forms().unwrap().is_empty());
+        assert!(plugin.validate_overridden_forms().unwrap().is_empty());
+    }
+
+    #[test]
+    fn validate_overridden_forms_should_be_empty_if_no_temporary_records_are_overridden() {
+        let mut plugin = Plugin::new(
+            GameId::SkyrimSE,
+            Path::new("testing-plugins/SkyrimSE/Data/Blank - Master Dependent.esm"),
+        );
+
+        assert!(plugin.parse_file(false).is_ok());
+        assert!(plugin.overridden_forms().unwrap().is_empty());
+        assert!(plugin.validate_overridden_forms().unwrap().is_empty());
+    }
+
+    #[test]
+    fn unlisted_temporary_overrides_should_be_empty_if_no_temporary_records_are_overridden() {
+        let mut plugin = Plugin::new(
+            GameId::SkyrimSE,
+            Path::new("testing-plugins/SkyrimSE/Data/Blank - Master Dependent.esm"),
+        );
+
+        assert!(plugin.parse_file(false).is_ok());
+        assert!(plugin.unlisted_temporary_overrides().unwrap().is_empty());
+    }
+
+    #[test]
+    fn unlisted_temporary_overrides_should_find_temporary_overrides_missing_from_onam() {
+        let mut onam = Vec::new();
+        onam.write_u32::<LittleEndian>(0xABD).unwrap();
+
+        let bytes = PluginBuilder::new(GameId::SkyrimSE)
+            .with_master("Blank.esm")
+            .with_header_subrecord("ONAM", &onam)
+            .with_group(
+                GroupBuilder::top_level("CELL")
+                    .with_record(RecordBuilder::new("CELL", 0xABC))
+                    .with_group(
+                        GroupBuilder::new(0xABC, 9)
+                            .with_record(RecordBuilder::new("REFR", 0xABD))
+                            .with_record(RecordBuilder::new("REFR", 0xABE))
+                            .with_record(RecordBuilder::new("REFR", 0x1000800)),
+                    ),
+            )
+            .build()
+            .unwrap();
+
+        let mut plugin = Plugin::new(GameId::SkyrimSE, Path::new("Test.esp"));
+        assert!(plugin.parse(&bytes, false).is_ok());
+
+        assert_eq!(
+            vec![plugin.resolve_form_id(0xABE).unwrap()],
+            plugin.unlisted_temporary_overrides().unwrap()
+        );
+    }
+
+    #[test]
+    fn validate_overridden_forms_should_find_unlisted_and_unneeded_onam_entries() {
+        let mut onam = Vec::new();
+        onam.write_u32::<LittleEndian>(0xAAA).unwrap();
+        onam.write_u32::<LittleEndian>(0xABD).unwrap();
+
+        let bytes = PluginBuilder::new(GameId::SkyrimSE)
+            .with_flags(0x1)
+            .with_master("Blank.esm")
+            .with_header_subrecord("ONAM", &onam)
+            .with_group(
+                GroupBuilder::top_level("CELL")
+                    .with_record(RecordBuilder::new("CELL", 0x1000800))
+                    .with_group(
+                        GroupBuilder::new(0x1000800, 9)
+                            .with_record(RecordBuilder::new("REFR", 0xABD))
+                            .with_record(RecordBuilder::new("REFR", 0xABE))
+                            .with_record(RecordBuilder::new("REFR", 0x1000801)),
+                    ),
+            )
+            .build()
+            .unwrap();
+
+        let mut plugin = Plugin::new(GameId::SkyrimSE, Path::new("Test.esm"));
+        assert!(plugin.parse(&bytes, false).is_ok());
+
+        let form_id = |f| ResolvedFormId::new("Test.esm", &plugin.masters().unwrap(), f);
+        let diagnostics = plugin.validate_overridden_forms().unwrap();
+        assert_eq!(2, diagnostics.len());
+        assert_eq!(
+            OverriddenFormDiagnostic::NotListed(form_id(0xABE)),
+            diagnostics[0]
+        );
+        assert_eq!(
+            OverriddenFormDiagnostic::NotOverridden(form_id(0xAAA)),
+            diagnostics[1]
+        );
+    }
+
+    fn form_id_test_plugin_bytes() -> Vec<u8> {
+        PluginBuilder::new(GameId::Skyrim)
+            .with_master("Blank.esm")
+            .with_next_object_id(0x900)
+            .with_record(RecordBuilder::new("WEAP", 0x100))
+            .with_record(RecordBuilder::new("WEAP", 0x100_0100))
+            .with_record(RecordBuilder::new("WEAP", 0x100_0800))
+            .with_record(RecordBuilder::new("ARMO", 0x100_0800))
+            .with_record(RecordBuilder::new("ARMO", 0x100_0801))
+            .with_record(RecordBuilder::new("ARMO", 0x100_0900))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn validate_form_ids_should_find_duplicate_reserved_and_out_of_range_form_ids() {
+        let bytes = form_id_test_plugin_bytes();
+
+        let mut plugin = Plugin::new(GameId::Skyrim, Path::new("Test.esp"));
+        assert!(plugin.parse(&bytes, false).is_ok());
+
+        let form_id = |f| plugin.resolve_form_id(f).unwrap();
+        assert_eq!(
+            vec![
+                FormIdDiagnostic::InReservedRange(form_id(0x100_0100)),
+                FormIdDiagnostic::Duplicate(form_id(0x100_0800)),
+                FormIdDiagnostic::NotBelowNextObjectId(form_id(0x100_0900)),
+            ],
+            plugin.validate_form_ids().unwrap()
+        );
+    }
+
+    #[test]
+    fn validate_form_ids_should_find_form_ids_with_a_mod_index_greater_than_the_masters_count() {
+        let bytes = PluginBuilder::new(GameId::Skyrim)
+            .with_master("Blank.esm")
+            .with_next_object_id(0x900)
+            .with_record(RecordBuilder::new("WEAP", 0x100_0800))
+            .with_record(RecordBuilder::new("WEAP", 0x200_0800))
+            .with_record(RecordBuilder::new("WEAP", 0x300_0810))
+            .build()
+            .unwrap();
+
+        let mut plugin = Plugin::new(GameId::Skyrim, Path::new("Test.esp"));
+        assert!(plugin.parse(&bytes, false).is_ok());
+
+        let form_id = |f| plugin.resolve_form_id(f).unwrap();
+        assert_eq!(
+            vec![
+                FormIdDiagnostic::InvalidModIndex(form_id(0x100_0800), 2),
+                FormIdDiagnostic::InvalidModIndex(form_id(0x100_0810), 3),
+            ],
+            plugin.validate_form_ids().unwrap()
+        );
+    }
+
+    #[test]
+    fn is_valid_should_be_false_if_a_plugin_has_duplicate_form_ids() {
+        let path = env::temp_dir().join("esplugin-duplicate-form-ids.esp");
+        File::create(&path)
+            .unwrap()
+            .write_all(&form_id_test_plugin_bytes())
+            .unwrap();
+
+        assert!(Plugin::is_valid(GameId::Skyrim, &path, true));
+        assert!(!Plugin::is_valid(GameId::Skyrim, &path, false));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn validate_form_ids_and_is_valid_should_not_treat_morrowind_records_as_duplicates() {
+        let weapon = |id| RecordBuilder::new("WEAP", 0).with_editor_id(id, GameId::Morrowind);
+        let bytes = PluginBuilder::new(GameId::Morrowind)
+            .with_record(weapon("iron_sword"))
+            .with_record(weapon("iron_dagger"))
+            .build()
+            .unwrap();
+
+        let mut plugin = Plugin::new(GameId::Morrowind, Path::new("Test.esp"));
+        assert!(plugin.parse(&bytes, false).is_ok());
+        assert!(plugin.validate_form_ids().unwrap().is_empty());
+
+        let path = env::temp_dir().join("esplugin-morrowind-form-ids.esp");
+        File::create(&path).unwrap().write_all(&bytes).unwrap();
+
+        assert!(Plugin::is_valid(GameId::Morrowind, &path, false));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn find_injected_records_should_find_records_that_a_master_does_not_add() {
+        let master_bytes = PluginBuilder::new(GameId::Skyrim)
+            .with_flags(0x1)
+            .with_record(RecordBuilder::new("WEAP", 0x800))
+            .with_record(RecordBuilder::new("WEAP", 0x801))
+            .build()
+            .unwrap();
+        let plugin_bytes = PluginBuilder::new(GameId::Skyrim)
+            .with_master("master.esm")
+            .with_master("Missing.esm")
+            .with_record(RecordBuilder::new("WEAP", 0x800))
+            .with_record(RecordBuilder::new("WEAP", 0x802))
+            .with_record(RecordBuilder::new("WEAP", 0x100_0900))
+            .with_record(RecordBuilder::new("WEAP", 0x200_0803))
+            .build()
+            .unwrap();
+
+        let mut master = Plugin::new(GameId::Skyrim, Path::new("Master.esm.ghost"));
+        assert!(master.parse(&master_bytes, false).is_ok());
+        let mut plugin = Plugin::new(GameId::Skyrim, Path::new("Plugin.esp"));
+        assert!(plugin.parse(&plugin_bytes, false).is_ok());
+
+        let injected_records = Plugin::find_injected_records(&[master, plugin]).unwrap();
+
+        assert_eq!(2, injected_records.len());
+        assert!(injected_records[0].is_empty());
+        assert_eq!(
+            vec![ResolvedFormId::new(
+                "Plugin.esp",
+                &["master.esm".to_string(), "Missing.esm".to_string()],
+                0x802
+            )],
+            injected_records[1]
+        );
+    }
+
+    #[test]
+    fn find_injected_records_should_skip_masters_that_do_not_have_all_their_form_ids_loaded() {
+        let master_bytes = PluginBuilder::new(GameId::Skyrim)
+            .with_flags(0x1)
+            .with_record(RecordBuilder::new("WEAP", 0x800))
+            .with_record(RecordBuilder::new("ARMO", 0x801))
+            .build()
+            .unwrap();
+        let plugin_bytes = PluginBuilder::new(GameId::Skyrim)
+            .with_master("Master.esm")
+            .with_record(RecordBuilder::new("WEAP", 0x800))
+            .with_record(RecordBuilder::new("ARMO", 0x801))
+            .build()
+            .unwrap();
+
+        let mut plugin = Plugin::new(GameId::Skyrim, Path::new("Plugin.esp"));
+        assert!(plugin.parse(&plugin_bytes, false).is_ok());
+
+        for options in &[
+            ParseOptions::header_only(),
+            ParseOptions::whole_plugin().with_record_types(&["WEAP"]),
+            ParseOptions::whole_plugin().with_group_labels(&["WEAP"]),
+        ] {
+            let mut master = Plugin::new(GameId::Skyrim, Path::new("Master.esm"));
+            assert!(master.parse_with_options(&master_bytes, options).is_ok());
+
+            let injected_records =
+                Plugin::find_injected_records(&[master, plugin.clone()]).unwrap();
+
+            assert_eq!(vec![Vec::<ResolvedFormId>::new(); 2], injected_records);
+        }
+    }
+
+    #[test]
+    fn diff_should_compare_the_header_and_kept_records_of_two_plugins() {
+        let options = ParseOptions::whole_plugin().with_record_types(&["BOOK", "NPC_"]);
+        let mut plugin = Plugin::new(
+            GameId::Skyrim,
+            Path::new("testing-plugins/Skyrim/Data/Blank.esm"),
+        );
+        assert!(plugin.parse_file_with_options(&options).is_ok());
+
+        let mut other_plugin = Plugin::new(
+            GameId::Skyrim,
+            Path::new("testing-plugins/Skyrim/Data/Blank - Master Dependent.esm"),
+        );
+        assert!(other_plugin.parse_file_with_options(&options).is_ok());
+
+        assert!(plugin.diff(&plugin).unwrap().is_empty());
+
+        let diff = plugin.diff(&other_plugin).unwrap();
+        assert_eq!(
+            &HeaderChange::Masters(Vec::new(), vec!["Blank.esm".to_string()]),
+            &diff.header_changes()[0]
+        );
+        assert!(!diff.record_diffs().is_empty());
+    }
+
+    #[test]
+    fn diff_should_error_if_records_were_not_kept() {
+        let bytes = PluginBuilder::new(GameId::Skyrim)
+            .with_record(RecordBuilder::new("WEAP", 0x800))
+            .build()
+            .unwrap();
+        let mut plugin = Plugin::new(GameId::Skyrim, Path::new("Test.esp"));
+        let mut other_plugin = Plugin::new(GameId::Skyrim, Path::new("Test.esp"));
+
+        assert!(plugin.parse(&bytes, false).is_ok());
+        assert!(other_plugin
+            .parse_with_options(&bytes, &ParseOptions::whole_plugin().keeping_all_records())
+            .is_ok());
+
+        match plugin.diff(&other_plugin) {
+            Err(Error::RecordsNotKept) => {}
+            x => panic!("Unexpected result: {:?}", x),
+        }
+        match other_plugin.diff(&plugin) {
+            Err(Error::RecordsNotKept) => {}
+            x => panic!("Unexpected result: {:?}", x),
+        }
+        assert!(other_plugin.diff(&other_plugin).unwrap().is_empty());
+    }
+
     #[test]
     fn count_override_records_should_count_how_many_records_come_from_masters() {
         let mut plugin = Plugin::new(
@@ -996,6 +3074,153 @@ mod tests {
         assert_eq!(2, plugin.count_override_records());
     }
 
+    #[test]
+    fn unused_masters_should_not_include_masters_with_overridden_records() {
+        let mut plugin = Plugin::new(
+            GameId::Skyrim,
+            Path::new("testing-plugins/Skyrim/Data/Blank - Different Master Dependent.esp"),
+        );
+
+        let options = ParseOptions::whole_plugin().keeping_all_records();
+        assert!(plugin.parse_file_with_options(&options).is_ok());
+        assert!(plugin.unused_masters().unwrap().is_empty());
+    }
+
+    // A.esm is used by an override, B.esm is only used by a reference from a
+    // subrecord, and C.esm is unused.
+    fn unused_masters_test_plugin_bytes() -> Vec<u8> {
+        PluginBuilder::new(GameId::Skyrim)
+            .with_master("A.esm")
+            .with_master("B.esm")
+            .with_master("C.esm")
+            .with_record(RecordBuilder::new("WEAP", 0xD00).with_subrecord("EITM", &[1, 8, 0, 1]))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn unused_masters_should_error_if_not_all_records_were_kept() {
+        let bytes = unused_masters_test_plugin_bytes();
+        let mut plugin = Plugin::new(GameId::Skyrim, Path::new("Test.esp"));
+
+        assert!(plugin.parse(&bytes, false).is_ok());
+        match plugin.unused_masters() {
+            Err(Error::RecordsNotKept) => {}
+            x => panic!("Unexpected result: {:?}", x),
+        }
+
+        let options = ParseOptions::whole_plugin().with_record_types(&["WEAP"]);
+        assert!(plugin.parse_with_options(&bytes, &options).is_ok());
+        match plugin.unused_masters() {
+            Err(Error::RecordsNotKept) => {}
+            x => panic!("Unexpected result: {:?}", x),
+        }
+    }
+
+    #[test]
+    fn unused_masters_should_find_masters_that_no_records_or_references_use() {
+        let bytes = unused_masters_test_plugin_bytes();
+        let mut plugin = Plugin::new(GameId::Skyrim, Path::new("Test.esp"));
+
+        let options = ParseOptions::whole_plugin().keeping_all_records();
+        assert!(plugin.parse_with_options(&bytes, &options).is_ok());
+
+        assert_eq!(vec!["C.esm"], plugin.unused_masters().unwrap());
+    }
+
+    #[test]
+    fn unused_masters_should_treat_data_that_no_schema_covers_as_possible_references() {
+        let bytes = PluginBuilder::new(GameId::Skyrim)
+            .with_master("A.esm")
+            .with_master("B.esm")
+            .with_master("C.esm")
+            .with_record(RecordBuilder::new("WEAP", 0xD00))
+            .with_record(RecordBuilder::new("CONT", 0xD01).with_subrecord("CNTO", &[0, 8, 0, 2]))
+            .with_record(RecordBuilder::new("WEAP", 0xD02).with_subrecord("VMAD", &[0, 8, 0, 1]))
+            .build()
+            .unwrap();
+        let mut plugin = Plugin::new(GameId::Skyrim, Path::new("Test.esp"));
+
+        let options = ParseOptions::whole_plugin().keeping_all_records();
+        assert!(plugin.parse_with_options(&bytes, &options).is_ok());
+
+        assert!(plugin.unused_masters().unwrap().is_empty());
+    }
+
+    #[test]
+    fn unused_masters_should_be_empty_for_a_morrowind_plugin() {
+        let bytes = PluginBuilder::new(GameId::Morrowind)
+            .with_master("Morrowind.esm")
+            .build()
+            .unwrap();
+        let mut plugin = Plugin::new(GameId::Morrowind, Path::new("Test.esp"));
+
+        let options = ParseOptions::whole_plugin().keeping_all_records();
+        assert!(plugin.parse_with_options(&bytes, &options).is_ok());
+
+        assert!(plugin.unused_masters().unwrap().is_empty());
+    }
+
+    #[test]
+    fn remove_masters_and_save_header_should_write_a_plugin_without_the_removed_masters() {
+        let directory = env::temp_dir().join("esplugin-remove-masters-test");
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("Test.esp");
+        File::create(&path)
+            .unwrap()
+            .write_all(&unused_masters_test_plugin_bytes())
+            .unwrap();
+
+        let options = ParseOptions::whole_plugin().keeping_all_records();
+        let mut plugin = Plugin::new(GameId::Skyrim, &path);
+        assert!(plugin.parse_file_with_options(&options).is_ok());
+
+        match plugin.remove_masters(&["b.esm".to_string(), "c.esm".to_string()]) {
+            Err(Error::MasterInUse(ref x)) if x == "B.esm" => {}
+            x => panic!("Unexpected result: {:?}", x),
+        }
+        assert_eq!(3, plugin.masters().unwrap().len());
+
+        assert!(plugin.remove_masters(&["c.esm".to_string()]).is_ok());
+        assert_eq!(vec!["A.esm", "B.esm"], plugin.masters().unwrap());
+        assert!(plugin.validate_form_ids().unwrap().is_empty());
+        assert!(plugin.save_header(&path).is_ok());
+
+        let mut saved_plugin = Plugin::new(GameId::Skyrim, &path);
+        assert!(saved_plugin.parse_file_with_options(&options).is_ok());
+
+        assert_eq!(vec!["A.esm", "B.esm"], saved_plugin.masters().unwrap());
+        assert_eq!(plugin.data.form_ids, saved_plugin.data.form_ids);
+        assert!(saved_plugin.unused_masters().unwrap().is_empty());
+        assert!(saved_plugin.validate_form_ids().unwrap().is_empty());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn remove_masters_should_error_if_the_plugin_adds_records() {
+        let bytes = PluginBuilder::new(GameId::Skyrim)
+            .with_master("A.esm")
+            .with_master("B.esm")
+            .with_next_object_id(0x801)
+            .with_record(RecordBuilder::new("WEAP", 0xD00))
+            .with_record(RecordBuilder::new("WEAP", 0x200_0800))
+            .build()
+            .unwrap();
+        let mut plugin = Plugin::new(GameId::Skyrim, Path::new("Test.esp"));
+
+        let options = ParseOptions::whole_plugin().keeping_all_records();
+        assert!(plugin.parse_with_options(&bytes, &options).is_ok());
+        assert_eq!(vec!["B.esm"], plugin.unused_masters().unwrap());
+
+        match plugin.remove_masters(&["B.esm".to_string()]) {
+            Err(Error::PluginAddsRecords) => {}
+            x => panic!("Unexpected result: {:?}", x),
+        }
+        assert_eq!(vec!["A.esm", "B.esm"], plugin.masters().unwrap());
+        assert!(plugin.validate_form_ids().unwrap().is_empty());
+    }
+
     #[test]
     fn hashed_form_ids_should_use_plugin_names_case_insensitively() {
         let raw_form_ids = vec![0x0000_0001, 0x0100_0002];
@@ -17,42 +17,154 @@
  * along with esplugin. If not, see <http://www.gnu.org/licenses/>.
  */
 use std::io;
+#[cfg(feature = "compressed-fields")]
+use std::io::Read;
+use std::io::SeekFrom;
 
-use byteorder::{ByteOrder, LittleEndian};
-use nom::le_u32;
-use nom::IResult;
+use byteorder::{ByteOrder, LittleEndian, WriteBytesExt};
+use encoding::all::WINDOWS_1252;
+use encoding::{DecoderTrap, Encoding};
+#[cfg(feature = "compressed-fields")]
+use flate2::read::ZlibDecoder;
+use nom::{self, le_u16, le_u32, ErrorKind, IResult};
 
 use error::Error;
 use game_id::GameId;
 use subrecord::{parse_subrecord_data_as_u32, Subrecord};
 
 const RECORD_TYPE_LENGTH: u8 = 4;
+const EDITOR_ID_SUBRECORD_TYPE: &[u8] = b"EDID";
+pub const SUBRECORD_HEADER_LENGTH: usize = 6;
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct RecordHeader {
     record_type: String,
     flags: u32,
     form_id: u32,
     size_of_subrecords: u32,
+    // Morrowind records have an unknown field in place of this.
+    version_control_info: u32,
+    // Only present in games after Oblivion.
+    form_version: u16,
+    unknown: u16,
 }
 
 impl RecordHeader {
-    fn are_subrecords_compressed(&self) -> bool {
+    pub fn parse(input: &[u8], game_id: GameId) -> IResult<&[u8], RecordHeader> {
+        record_header(input, game_id)
+    }
+
+    pub(crate) fn new(
+        record_type: &str,
+        flags: u32,
+        form_id: u32,
+        version_control_info: u32,
+        form_version: u16,
+        unknown: u16,
+    ) -> RecordHeader {
+        RecordHeader {
+            record_type: record_type.to_string(),
+            flags,
+            form_id,
+            size_of_subrecords: 0,
+            version_control_info,
+            form_version,
+            unknown,
+        }
+    }
+
+    // Fields that the game doesn't have are not written.
+    pub(crate) fn write<T: io::Write>(
+        &self,
+        writer: &mut T,
+        game_id: GameId,
+        size_of_subrecords: u32,
+    ) -> Result<(), io::Error> {
+        writer.write_all(self.record_type.as_bytes())?;
+        writer.write_u32::<LittleEndian>(size_of_subrecords)?;
+
+        if game_id == GameId::Morrowind {
+            writer.write_u32::<LittleEndian>(self.version_control_info)?;
+        }
+
+        writer.write_u32::<LittleEndian>(self.flags)?;
+
+        if game_id != GameId::Morrowind {
+            writer.write_u32::<LittleEndian>(self.form_id)?;
+            writer.write_u32::<LittleEndian>(self.version_control_info)?;
+        }
+
+        if game_id != GameId::Morrowind && game_id != GameId::Oblivion {
+            writer.write_u16::<LittleEndian>(self.form_version)?;
+            writer.write_u16::<LittleEndian>(self.unknown)?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn are_subrecords_compressed(&self) -> bool {
         (self.flags & 0x0004_0000) != 0
     }
 
+    pub fn record_type(&self) -> &str {
+        &self.record_type
+    }
+
     pub fn flags(&self) -> u32 {
         self.flags
     }
+
+    pub fn form_id(&self) -> u32 {
+        self.form_id
+    }
+
+    pub fn size_of_subrecords(&self) -> u32 {
+        self.size_of_subrecords
+    }
+
+    pub fn version_control_info(&self) -> u32 {
+        self.version_control_info
+    }
+
+    pub fn form_version(&self) -> u16 {
+        self.form_version
+    }
+
+    pub(crate) fn unknown(&self) -> u16 {
+        self.unknown
+    }
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Record {
     header: RecordHeader,
     subrecords: Vec<Subrecord>,
+    // The subrecord data of a compressed record that was kept as it appears
+    // in the plugin. Empty for other records.
+    compressed_data: Vec<u8>,
 }
 
 impl Record {
+    // The header's size of subrecords is set from the given subrecords.
+    pub(crate) fn new(
+        mut header: RecordHeader,
+        subrecords: Vec<Subrecord>,
+        game_id: GameId,
+    ) -> Record {
+        header.size_of_subrecords = subrecords
+            .iter()
+            .map(|s| s.written_size(game_id) as u32)
+            .sum();
+
+        Record {
+            header,
+            subrecords,
+            compressed_data: Vec::new(),
+        }
+    }
+
     pub fn read_and_validate<T: io::Read>(
         reader: &mut T,
         game_id: GameId,
@@ -76,10 +188,51 @@ impl Record {
         Ok(content)
     }
 
+    pub fn read_form_id<T: io::Read + io::Seek>(
+        reader: &mut T,
+        game_id: GameId,
+    ) -> Result<u32, Error> {
+        let mut header_bytes: Vec<u8> = vec![0; header_length(game_id)];
+        reader.read_exact(&mut header_bytes)?;
+
+        let header = RecordHeader::parse(&header_bytes, game_id)?.1;
+
+        reader.seek(SeekFrom::Current(i64::from(header.size_of_subrecords)))?;
+
+        Ok(header.form_id)
+    }
+
     pub fn parse(input: &[u8], game_id: GameId, skip_subrecords: bool) -> IResult<&[u8], Record> {
         record(input, game_id, skip_subrecords)
     }
 
+    // Compressed records keep their compressed data, as it can't be
+    // recompressed to the same bytes. Their subrecords are parsed from the
+    // decompressed data if compressed fields are supported, and are otherwise
+    // left empty.
+    pub(crate) fn parse_keeping_compressed_data(
+        input: &[u8],
+        game_id: GameId,
+    ) -> IResult<&[u8], Record> {
+        let (input1, header) = try_parse!(input, apply!(record_header, game_id));
+        if !header.are_subrecords_compressed() {
+            return record(input, game_id, false);
+        }
+
+        let (input2, compressed_data) = try_parse!(input1, take!(header.size_of_subrecords));
+        let subrecords = decompressed_subrecords(compressed_data, game_id)
+            .map_err(|_| nom::Err::Failure(nom::Context::Code(input1, ErrorKind::Custom(1))))?;
+
+        Ok((
+            input2,
+            Record {
+                header,
+                subrecords,
+                compressed_data: compressed_data.to_vec(),
+            },
+        ))
+    }
+
     pub fn parse_form_id(input: &[u8], game_id: GameId) -> IResult<&[u8], u32> {
         do_parse!(
             input,
@@ -98,6 +251,26 @@ impl Record {
         )
     }
 
+    // Writes the record using the sizes of its subrecords, so a record that
+    // was parsed without its subrecords is written without them too. A record
+    // that was kept with its compressed data is written with that data.
+    pub fn write<T: io::Write>(&self, writer: &mut T, game_id: GameId) -> Result<(), io::Error> {
+        if !self.compressed_data.is_empty() {
+            self.header
+                .write(writer, game_id, self.compressed_data.len() as u32)?;
+            return writer.write_all(&self.compressed_data);
+        }
+
+        let mut subrecords_data = Vec::new();
+        for subrecord in &self.subrecords {
+            subrecord.write(&mut subrecords_data, game_id)?;
+        }
+
+        self.header
+            .write(writer, game_id, subrecords_data.len() as u32)?;
+        writer.write_all(&subrecords_data)
+    }
+
     pub fn header(&self) -> &RecordHeader {
         &self.header
     }
@@ -105,9 +278,95 @@ impl Record {
     pub fn subrecords(&self) -> &[Subrecord] {
         &self.subrecords
     }
+
+    pub fn compressed_data(&self) -> &[u8] {
+        &self.compressed_data
+    }
+
+    // True if the record was kept with compressed data that could not be
+    // decompressed into its subrecords.
+    pub(crate) fn has_unread_subrecords(&self) -> bool {
+        !self.compressed_data.is_empty() && self.subrecords.is_empty()
+    }
+
+    // Subrecord data can be changed but not resized, so that the record
+    // header's size of subrecords stays correct.
+    pub(crate) fn subrecords_mut(&mut self) -> &mut [Subrecord] {
+        &mut self.subrecords
+    }
+
+    // Keeps only the subrecords for which the predicate returns true,
+    // updating the size of subrecords in the record header to match.
+    pub(crate) fn retain_subrecords<F: FnMut(&Subrecord) -> bool>(
+        &mut self,
+        predicate: F,
+        game_id: GameId,
+    ) {
+        self.subrecords.retain(predicate);
+        self.header.size_of_subrecords = self
+            .subrecords
+            .iter()
+            .map(|s| s.written_size(game_id) as u32)
+            .sum();
+    }
+
+    // Returns None if the record's subrecords are compressed, or if it's a
+    // Morrowind record, as they have NAME IDs instead.
+    pub fn editor_id(&self) -> Option<String> {
+        if self.header.are_subrecords_compressed() {
+            return None;
+        }
+
+        self.subrecords
+            .iter()
+            .find(|s| s.subrecord_type().as_bytes() == EDITOR_ID_SUBRECORD_TYPE)
+            .map(|s| decode_editor_id(s.data()))
+    }
+
+    // Morrowind records are identified by a NAME subrecord instead of an
+    // EDID subrecord. Other games use NAME subrecords for other data.
+    pub(crate) fn name_id(&self) -> Option<String> {
+        self.subrecords
+            .iter()
+            .find(|s| s.subrecord_type() == "NAME")
+            .map(|s| decode_editor_id(s.data()))
+    }
 }
 
-fn header_length(game_id: GameId) -> usize {
+// A record's EDID subrecord is always its first subrecord, so only the start
+// of its subrecord data is needed. Morrowind records have no EDID subrecords.
+pub fn parse_editor_id(
+    header: &RecordHeader,
+    subrecords_data: &[u8],
+    game_id: GameId,
+) -> Option<String> {
+    if game_id == GameId::Morrowind
+        || header.are_subrecords_compressed()
+        || subrecords_data.len() < SUBRECORD_HEADER_LENGTH
+        || &subrecords_data[..4] != EDITOR_ID_SUBRECORD_TYPE
+    {
+        return None;
+    }
+
+    let data_length = LittleEndian::read_u16(&subrecords_data[4..]) as usize;
+
+    subrecords_data
+        .get(SUBRECORD_HEADER_LENGTH..SUBRECORD_HEADER_LENGTH + data_length)
+        .map(decode_editor_id)
+}
+
+fn decode_editor_id(data: &[u8]) -> String {
+    let data = match data.iter().position(|b| *b == 0) {
+        Some(index) => &data[..index],
+        None => data,
+    };
+
+    WINDOWS_1252
+        .decode(data, DecoderTrap::Replace)
+        .unwrap_or_default()
+}
+
+pub fn header_length(game_id: GameId) -> usize {
     match game_id {
         GameId::Morrowind => 16,
         GameId::Oblivion => 20,
@@ -119,17 +378,24 @@ named_args!(record_header(game_id: GameId) <RecordHeader>,
     do_parse!(
         record_type: take_str!(RECORD_TYPE_LENGTH) >>
         size_of_subrecords: le_u32 >>
-        cond!(game_id == GameId::Morrowind, take!(4)) >>
+        morrowind_unknown: cond!(game_id == GameId::Morrowind, le_u32) >>
         flags: le_u32 >>
         form_id: cond!(game_id != GameId::Morrowind, le_u32) >>
-        cond!(game_id != GameId::Morrowind, take!(4)) >>
-        cond!(game_id != GameId::Morrowind && game_id != GameId::Oblivion, take!(4)) >>
+        version_control_info: cond!(game_id != GameId::Morrowind, le_u32) >>
+        form_version: cond!(
+            game_id != GameId::Morrowind && game_id != GameId::Oblivion,
+            le_u16
+        ) >>
+        unknown: cond!(game_id != GameId::Morrowind && game_id != GameId::Oblivion, le_u16) >>
 
         (RecordHeader {
             record_type: record_type.to_string(),
             flags,
             form_id: form_id.unwrap_or(0),
             size_of_subrecords,
+            version_control_info: version_control_info.or(morrowind_unknown).unwrap_or(0),
+            form_version: form_version.unwrap_or(0),
+            unknown: unknown.unwrap_or(0),
         })
     )
 );
@@ -151,7 +417,36 @@ fn record(input: &[u8], game_id: GameId, skip_subrecords: bool) -> IResult<&[u8]
         Vec::new()
     };
 
-    Ok((input2, Record { header, subrecords }))
+    Ok((
+        input2,
+        Record {
+            header,
+            subrecords,
+            compressed_data: Vec::new(),
+        },
+    ))
+}
+
+// Compressed data starts with the size of the decompressed data, followed by
+// the zlib-compressed subrecords.
+#[cfg(feature = "compressed-fields")]
+fn decompressed_subrecords(
+    compressed_data: &[u8],
+    game_id: GameId,
+) -> Result<Vec<Subrecord>, Error> {
+    if compressed_data.len() < 4 {
+        return Err(Error::ParsingIncomplete);
+    }
+
+    let mut data = Vec::new();
+    ZlibDecoder::new(&compressed_data[4..]).read_to_end(&mut data)?;
+
+    Ok(parse_subrecords(&data, game_id, false)?.1)
+}
+
+#[cfg(not(feature = "compressed-fields"))]
+fn decompressed_subrecords(_: &[u8], _: GameId) -> Result<Vec<Subrecord>, Error> {
+    Ok(Vec::new())
 }
 
 fn parse_subrecords(
@@ -327,6 +622,132 @@ mod tests {
         );
     }
 
+    #[test]
+    fn write_should_write_a_record_that_parses_to_an_equal_record() {
+        let data =
+            &include_bytes!("../testing-plugins/Skyrim/Data/Blank - Master Dependent.esm")[..0x56];
+        let record = Record::parse(data, GameId::Skyrim, false).unwrap().1;
+
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, GameId::Skyrim).unwrap();
+
+        assert_eq!(data.len(), bytes.len());
+        assert_eq!(
+            record,
+            Record::parse(&bytes, GameId::Skyrim, false).unwrap().1
+        );
+    }
+
+    #[test]
+    fn write_should_write_a_morrowind_record_that_parses_to_an_equal_record() {
+        let data = &include_bytes!("../testing-plugins/Morrowind/Data Files/Blank.esm")[..0x144];
+        let record = Record::parse(data, GameId::Morrowind, false).unwrap().1;
+
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, GameId::Morrowind).unwrap();
+
+        assert_eq!(data.len(), bytes.len());
+        assert_eq!(
+            record,
+            Record::parse(&bytes, GameId::Morrowind, false).unwrap().1
+        );
+    }
+
+    #[test]
+    fn write_should_write_large_subrecords_correctly() {
+        let data = &include_bytes!("../testing-plugins/Skyrim/Data/Blank.esm")[..0x1004C];
+        let record = Record::parse(data, GameId::Skyrim, false).unwrap().1;
+
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, GameId::Skyrim).unwrap();
+
+        assert_eq!(data.len(), bytes.len());
+        assert_eq!(
+            record,
+            Record::parse(&bytes, GameId::Skyrim, false).unwrap().1
+        );
+    }
+
+    #[test]
+    fn editor_id_should_return_the_edid_subrecord_data_without_its_null_terminator() {
+        let data = b"WEAP\x16\x00\x00\x00\x00\x00\x00\x00\x00\x08\x00\x01\x00\x00\x00\x00\x2C\x00\x00\x00EDID\x0A\x00IronSword\x00DATA\x00\x00";
+        let record = Record::parse(data, GameId::Skyrim, false).unwrap().1;
+
+        assert_eq!(Some("IronSword".to_string()), record.editor_id());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn record_should_serialize_to_json_and_back() {
+        let data = b"WEAP\x16\x00\x00\x00\x00\x00\x00\x00\x00\x08\x00\x01\x00\x00\x00\x00\x2C\x00\x00\x00EDID\x0A\x00IronSword\x00DATA\x00\x00";
+        let record = Record::parse(data, GameId::Skyrim, false).unwrap().1;
+
+        let json = ::serde_json::to_value(&record).unwrap();
+
+        assert_eq!("WEAP", json["header"]["record_type"]);
+        assert_eq!(0x0100_0800, json["header"]["form_id"]);
+        assert_eq!("EDID", json["subrecords"][0]["subrecord_type"]);
+        assert_eq!(record, ::serde_json::from_value(json).unwrap());
+    }
+
+    #[test]
+    fn write_should_keep_the_version_control_info_and_form_version() {
+        let data = b"WEAP\x06\x00\x00\x00\x00\x00\x00\x00\x00\x08\x00\x01\x12\x34\x00\x00\x2C\x00\x01\x00DATA\x00\x00";
+        let record = Record::parse(data, GameId::Skyrim, false).unwrap().1;
+
+        assert_eq!(0x3412, record.header().version_control_info());
+        assert_eq!(44, record.header().form_version());
+
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, GameId::Skyrim).unwrap();
+
+        assert_eq!(&data[..], bytes.as_slice());
+    }
+
+    #[test]
+    fn retain_subrecords_should_update_the_size_of_subrecords() {
+        let data = b"WEAP\x16\x00\x00\x00\x00\x00\x00\x00\x00\x08\x00\x01\x00\x00\x00\x00\x2C\x00\x00\x00EDID\x0A\x00IronSword\x00DATA\x00\x00";
+        let mut record = Record::parse(data, GameId::Skyrim, false).unwrap().1;
+
+        record.retain_subrecords(|s| s.subrecord_type() != "EDID", GameId::Skyrim);
+
+        assert_eq!(1, record.subrecords().len());
+        assert_eq!(6, record.header().size_of_subrecords());
+
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, GameId::Skyrim).unwrap();
+
+        assert_eq!(
+            record,
+            Record::parse(&bytes, GameId::Skyrim, false).unwrap().1
+        );
+    }
+
+    #[test]
+    fn parse_editor_id_should_only_read_a_leading_edid_subrecord() {
+        let data = b"EDID\x0A\x00IronSword\x00DATA\x00\x00";
+        let header = RecordHeader::default();
+
+        assert_eq!(
+            Some("IronSword".to_string()),
+            parse_editor_id(&header, data, GameId::Skyrim)
+        );
+        assert_eq!(None, parse_editor_id(&header, data, GameId::Morrowind));
+        assert_eq!(None, parse_editor_id(&header, &data[16..], GameId::Skyrim));
+        assert_eq!(None, parse_editor_id(&header, &data[..10], GameId::Skyrim));
+    }
+
+    #[test]
+    fn parse_editor_id_should_return_none_for_a_compressed_record() {
+        let data = b"EDID\x0A\x00IronSword\x00";
+        let header = RecordHeader {
+            flags: 0x0004_0000,
+            ..Default::default()
+        };
+
+        assert_eq!(None, parse_editor_id(&header, data, GameId::Skyrim));
+    }
+
     #[test]
     fn parse_form_id_should_return_the_form_id() {
         let data =
@@ -0,0 +1,740 @@
+/*
+ * This file is part of esplugin
+ *
+ * Copyright (C) 2017 Oliver Hamlet
+ *
+ * esplugin is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * esplugin is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with esplugin. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use byteorder::{LittleEndian, ReadBytesExt};
+
+use encoding::all::WINDOWS_1252;
+use encoding::{DecoderTrap, Encoding};
+
+use unicase::eq;
+
+#[cfg(feature = "compressed-saves")]
+use flate2::read::ZlibDecoder;
+
+use diagnostics::SavePluginDiagnostic;
+use error::Error;
+use game_id::GameId;
+use plugin::Plugin;
+
+const OBLIVION_MAGIC: &[u8] = b"TES4SAVEGAME";
+const SKYRIM_MAGIC: &[u8] = b"TESV_SAVEGAME";
+const FALLOUT3_MAGIC: &[u8] = b"FO3SAVEGAME";
+const FALLOUT4_MAGIC: &[u8] = b"FO4_SAVEGAME";
+
+// Skyrim Special Edition saves have a version of 12 or greater.
+const SKYRIM_SE_SAVE_VERSION: u32 = 12;
+const SKYRIM_SE_LIGHT_PLUGINS_FORM_VERSION: u8 = 78;
+const FALLOUT4_LIGHT_PLUGINS_FORM_VERSION: u8 = 68;
+
+const FALLOUT_NV_LANGUAGE_LENGTH: usize = 64;
+
+// The header of a save game, and the plugins that were loaded when it was
+// made. Morrowind saves are not supported.
+#[derive(Clone, PartialEq, Eq, Debug, Hash, Default)]
+pub struct SaveGame {
+    player_name: String,
+    player_level: u32,
+    player_location: String,
+    screenshot_width: u32,
+    screenshot_height: u32,
+    plugins: Vec<String>,
+    light_plugins: Vec<String>,
+}
+
+impl SaveGame {
+    pub fn parse_file(game_id: GameId, path: &Path) -> Result<SaveGame, Error> {
+        let mut content = Vec::new();
+        File::open(path)?.read_to_end(&mut content)?;
+
+        SaveGame::parse(game_id, &content)
+    }
+
+    pub fn parse(game_id: GameId, input: &[u8]) -> Result<SaveGame, Error> {
+        let mut reader = input;
+
+        let result = match game_id {
+            GameId::Morrowind => Err(Error::ParsingError),
+            GameId::Oblivion => parse_oblivion_save(&mut reader),
+            GameId::Skyrim | GameId::SkyrimSE => parse_skyrim_save(&mut reader),
+            GameId::Fallout3 => parse_fallout3_save(&mut reader, false),
+            GameId::FalloutNV => parse_fallout3_save(&mut reader, true),
+            GameId::Fallout4 => parse_fallout4_save(&mut reader),
+        };
+
+        result.map_err(|e| match e {
+            Error::IoError(ref x) if x.kind() == io::ErrorKind::UnexpectedEof => {
+                Error::ParsingIncomplete
+            }
+            e => e,
+        })
+    }
+
+    pub fn player_name(&self) -> &str {
+        &self.player_name
+    }
+
+    pub fn player_level(&self) -> u32 {
+        self.player_level
+    }
+
+    pub fn player_location(&self) -> &str {
+        &self.player_location
+    }
+
+    pub fn screenshot_width(&self) -> u32 {
+        self.screenshot_width
+    }
+
+    pub fn screenshot_height(&self) -> u32 {
+        self.screenshot_height
+    }
+
+    // The plugins in the order they were loaded, not including light plugins.
+    pub fn plugins(&self) -> &[String] {
+        &self.plugins
+    }
+
+    // Only Skyrim Special Edition and Fallout 4 saves list light plugins,
+    // which they do separately from other plugins.
+    pub fn light_plugins(&self) -> &[String] {
+        &self.light_plugins
+    }
+
+    // Light plugins in the load order are compared against the save's light
+    // plugins, and other plugins against the save's other plugins.
+    pub fn compare_load_order(&self, load_order: &[Plugin]) -> Vec<SavePluginDiagnostic> {
+        let mut plugins = Vec::new();
+        let mut light_plugins = Vec::new();
+        for plugin in load_order {
+            if let Some(filename) = plugin.filename() {
+                if plugin.is_light_master_file() {
+                    light_plugins.push(filename);
+                } else {
+                    plugins.push(filename);
+                }
+            }
+        }
+
+        let mut diagnostics = compare_plugins(&self.plugins, &plugins);
+        diagnostics.extend(compare_plugins(&self.light_plugins, &light_plugins));
+
+        diagnostics
+    }
+}
+
+fn compare_plugins(save_plugins: &[String], load_order: &[String]) -> Vec<SavePluginDiagnostic> {
+    let contains =
+        |plugins: &[String], plugin: &str| plugins.iter().any(|p| eq(p.as_str(), plugin));
+
+    let mut diagnostics: Vec<SavePluginDiagnostic> = save_plugins
+        .iter()
+        .filter(|p| !contains(load_order, p))
+        .map(|p| SavePluginDiagnostic::Missing(p.clone()))
+        .collect();
+
+    diagnostics.extend(
+        load_order
+            .iter()
+            .filter(|p| !contains(save_plugins, p))
+            .map(|p| SavePluginDiagnostic::NotInSave(p.clone())),
+    );
+
+    let common_save_plugins = save_plugins.iter().filter(|p| contains(load_order, p));
+    let common_load_order = load_order.iter().filter(|p| contains(save_plugins, p));
+
+    diagnostics.extend(
+        common_save_plugins
+            .zip(common_load_order)
+            .filter(|&(save_plugin, plugin)| !eq(save_plugin.as_str(), plugin.as_str()))
+            .map(|(save_plugin, _)| SavePluginDiagnostic::OutOfOrder(save_plugin.clone())),
+    );
+
+    diagnostics
+}
+
+fn parse_oblivion_save(reader: &mut &[u8]) -> Result<SaveGame, Error> {
+    read_magic(reader, OBLIVION_MAGIC)?;
+
+    // Version numbers and the time the executable was last modified.
+    skip(reader, 18)?;
+    // The header version and size, and the save number.
+    skip(reader, 12)?;
+
+    let player_name = read_bzstring(reader)?;
+    let player_level = u32::from(reader.read_u16::<LittleEndian>()?);
+    let player_location = read_bzstring(reader)?;
+
+    // The days and ticks passed in-game, and the real time of the save.
+    skip(reader, 24)?;
+
+    let screenshot_size = reader.read_u32::<LittleEndian>()? as usize;
+    let screenshot_width = reader.read_u32::<LittleEndian>()?;
+    let screenshot_height = reader.read_u32::<LittleEndian>()?;
+    skip(reader, screenshot_size.saturating_sub(8))?;
+
+    let plugin_count = reader.read_u8()?;
+    let plugins = (0..plugin_count)
+        .map(|_| read_bstring(reader))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(SaveGame {
+        player_name,
+        player_level,
+        player_location,
+        screenshot_width,
+        screenshot_height,
+        plugins,
+        light_plugins: Vec::new(),
+    })
+}
+
+// Skyrim Special Edition saves may compress everything after the screenshot,
+// which can only be read if the compressed-saves feature is enabled.
+fn parse_skyrim_save(reader: &mut &[u8]) -> Result<SaveGame, Error> {
+    read_magic(reader, SKYRIM_MAGIC)?;
+
+    // The header size.
+    skip(reader, 4)?;
+
+    let version = reader.read_u32::<LittleEndian>()?;
+    // The save number.
+    skip(reader, 4)?;
+
+    let player_name = read_wstring(reader)?;
+    let player_level = reader.read_u32::<LittleEndian>()?;
+    let player_location = read_wstring(reader)?;
+
+    // The in-game date and the player's race.
+    read_wstring(reader)?;
+    read_wstring(reader)?;
+    // The player's sex, current and level up experience, and the real time of
+    // the save.
+    skip(reader, 18)?;
+
+    let screenshot_width = reader.read_u32::<LittleEndian>()?;
+    let screenshot_height = reader.read_u32::<LittleEndian>()?;
+
+    let is_special_edition = version >= SKYRIM_SE_SAVE_VERSION;
+    let (compression_type, bytes_per_pixel) = if is_special_edition {
+        (reader.read_u16::<LittleEndian>()?, 4)
+    } else {
+        (0, 3)
+    };
+    skip(
+        reader,
+        bytes_per_pixel * screenshot_width as usize * screenshot_height as usize,
+    )?;
+
+    let decompressed;
+    let mut body: &[u8] = if compression_type == 0 {
+        reader
+    } else {
+        let uncompressed_size = reader.read_u32::<LittleEndian>()? as usize;
+        let compressed_size = reader.read_u32::<LittleEndian>()? as usize;
+        let compressed = reader
+            .get(..compressed_size)
+            .ok_or(Error::ParsingIncomplete)?;
+
+        decompressed = decompress(compression_type, compressed, uncompressed_size)?;
+        &decompressed
+    };
+
+    let form_version = body.read_u8()?;
+    // The size of the plugin info.
+    skip(&mut body, 4)?;
+
+    let plugin_count = body.read_u8()?;
+    let plugins = (0..plugin_count)
+        .map(|_| read_wstring(&mut body))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let light_plugins =
+        if is_special_edition && form_version >= SKYRIM_SE_LIGHT_PLUGINS_FORM_VERSION {
+            read_light_plugins(&mut body)?
+        } else {
+            Vec::new()
+        };
+
+    Ok(SaveGame {
+        player_name,
+        player_level,
+        player_location,
+        screenshot_width,
+        screenshot_height,
+        plugins,
+        light_plugins,
+    })
+}
+
+// Fallout 3 and New Vegas saves separate most of their fields with a '|'
+// character, and New Vegas saves also store the game's language.
+fn parse_fallout3_save(reader: &mut &[u8], is_new_vegas: bool) -> Result<SaveGame, Error> {
+    read_magic(reader, FALLOUT3_MAGIC)?;
+
+    // The header size and version.
+    skip(reader, 8)?;
+    read_delimiter(reader)?;
+
+    if is_new_vegas {
+        skip(reader, FALLOUT_NV_LANGUAGE_LENGTH)?;
+        read_delimiter(reader)?;
+    }
+
+    let screenshot_width = read_delimited_u32(reader)?;
+    let screenshot_height = read_delimited_u32(reader)?;
+    // The save number.
+    read_delimited_u32(reader)?;
+
+    let player_name = read_delimited_string(reader)?;
+    // The player's karma title.
+    read_delimited_string(reader)?;
+    let player_level = read_delimited_u32(reader)?;
+    let player_location = read_delimited_string(reader)?;
+    // The time played.
+    read_delimited_string(reader)?;
+
+    skip(
+        reader,
+        3 * screenshot_width as usize * screenshot_height as usize,
+    )?;
+
+    // The form version and the size of the plugin info.
+    skip(reader, 5)?;
+
+    let plugin_count = reader.read_u8()?;
+    read_delimiter(reader)?;
+    let plugins = (0..plugin_count)
+        .map(|_| read_delimited_string(reader))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(SaveGame {
+        player_name,
+        player_level,
+        player_location,
+        screenshot_width,
+        screenshot_height,
+        plugins,
+        light_plugins: Vec::new(),
+    })
+}
+
+fn parse_fallout4_save(reader: &mut &[u8]) -> Result<SaveGame, Error> {
+    read_magic(reader, FALLOUT4_MAGIC)?;
+
+    // The header size, version and save number.
+    skip(reader, 12)?;
+
+    let player_name = read_wstring(reader)?;
+    let player_level = reader.read_u32::<LittleEndian>()?;
+    let player_location = read_wstring(reader)?;
+
+    // The time played and the player's race.
+    read_wstring(reader)?;
+    read_wstring(reader)?;
+    // The player's sex, current and level up experience, and the real time of
+    // the save.
+    skip(reader, 18)?;
+
+    let screenshot_width = reader.read_u32::<LittleEndian>()?;
+    let screenshot_height = reader.read_u32::<LittleEndian>()?;
+    skip(
+        reader,
+        4 * screenshot_width as usize * screenshot_height as usize,
+    )?;
+
+    let form_version = reader.read_u8()?;
+    // The game version.
+    read_wstring(reader)?;
+    // The size of the plugin info.
+    skip(reader, 4)?;
+
+    let plugin_count = reader.read_u8()?;
+    let plugins = (0..plugin_count)
+        .map(|_| read_wstring(reader))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let light_plugins = if form_version >= FALLOUT4_LIGHT_PLUGINS_FORM_VERSION {
+        read_light_plugins(reader)?
+    } else {
+        Vec::new()
+    };
+
+    Ok(SaveGame {
+        player_name,
+        player_level,
+        player_location,
+        screenshot_width,
+        screenshot_height,
+        plugins,
+        light_plugins,
+    })
+}
+
+#[cfg(feature = "compressed-saves")]
+fn decompress(
+    compression_type: u16,
+    compressed: &[u8],
+    uncompressed_size: usize,
+) -> Result<Vec<u8>, Error> {
+    match compression_type {
+        1 => {
+            let mut decompressed = Vec::with_capacity(uncompressed_size);
+            ZlibDecoder::new(compressed).read_to_end(&mut decompressed)?;
+            Ok(decompressed)
+        }
+        2 => lz4_flex::block::decompress(compressed, uncompressed_size)
+            .map_err(|_| Error::ParsingError),
+        _ => Err(Error::UnsupportedSaveCompression(compression_type)),
+    }
+}
+
+#[cfg(not(feature = "compressed-saves"))]
+fn decompress(compression_type: u16, _: &[u8], _: usize) -> Result<Vec<u8>, Error> {
+    Err(Error::UnsupportedSaveCompression(compression_type))
+}
+
+fn read_light_plugins(reader: &mut &[u8]) -> Result<Vec<String>, Error> {
+    let count = reader.read_u16::<LittleEndian>()?;
+
+    (0..count).map(|_| read_wstring(reader)).collect()
+}
+
+fn read_magic(reader: &mut &[u8], magic: &[u8]) -> Result<(), Error> {
+    if reader.starts_with(magic) {
+        *reader = &reader[magic.len()..];
+        Ok(())
+    } else {
+        Err(Error::ParsingError)
+    }
+}
+
+fn skip(reader: &mut &[u8], count: usize) -> Result<(), Error> {
+    if reader.len() < count {
+        return Err(Error::ParsingIncomplete);
+    }
+
+    *reader = &reader[count..];
+    Ok(())
+}
+
+fn read_string(reader: &mut &[u8], length: usize) -> Result<String, Error> {
+    if reader.len() < length {
+        return Err(Error::ParsingIncomplete);
+    }
+
+    let (data, rest) = reader.split_at(length);
+    *reader = rest;
+
+    WINDOWS_1252
+        .decode(data, DecoderTrap::Strict)
+        .map_err(Error::DecodeError)
+}
+
+// A string prefixed by a u8 length.
+fn read_bstring(reader: &mut &[u8]) -> Result<String, Error> {
+    let length = reader.read_u8()?;
+    read_string(reader, usize::from(length))
+}
+
+// A null-terminated string prefixed by a u8 length that includes the null.
+fn read_bzstring(reader: &mut &[u8]) -> Result<String, Error> {
+    let mut string = read_bstring(reader)?;
+    if string.ends_with('\0') {
+        string.pop();
+    }
+
+    Ok(string)
+}
+
+// A string prefixed by a u16 length.
+fn read_wstring(reader: &mut &[u8]) -> Result<String, Error> {
+    let length = reader.read_u16::<LittleEndian>()?;
+    read_string(reader, usize::from(length))
+}
+
+fn read_delimiter(reader: &mut &[u8]) -> Result<(), Error> {
+    match reader.read_u8()? {
+        b'|' => Ok(()),
+        _ => Err(Error::ParsingError),
+    }
+}
+
+fn read_delimited_u32(reader: &mut &[u8]) -> Result<u32, Error> {
+    let value = reader.read_u32::<LittleEndian>()?;
+    read_delimiter(reader)?;
+
+    Ok(value)
+}
+
+// A string prefixed by a u16 length, with a delimiter after the length and
+// after the string.
+fn read_delimited_string(reader: &mut &[u8]) -> Result<String, Error> {
+    let length = reader.read_u16::<LittleEndian>()?;
+    read_delimiter(reader)?;
+    let string = read_string(reader, usize::from(length))?;
+    read_delimiter(reader)?;
+
+    Ok(string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use byteorder::WriteBytesExt;
+
+    fn wstring(bytes: &mut Vec<u8>, string: &str) {
+        bytes
+            .write_u16::<LittleEndian>(string.len() as u16)
+            .unwrap();
+        bytes.extend_from_slice(string.as_bytes());
+    }
+
+    fn delimited_string(bytes: &mut Vec<u8>, string: &str) {
+        bytes
+            .write_u16::<LittleEndian>(string.len() as u16)
+            .unwrap();
+        bytes.push(b'|');
+        bytes.extend_from_slice(string.as_bytes());
+        bytes.push(b'|');
+    }
+
+    fn delimited_u32(bytes: &mut Vec<u8>, value: u32) {
+        bytes.write_u32::<LittleEndian>(value).unwrap();
+        bytes.push(b'|');
+    }
+
+    // Everything up to and including the screenshot dimensions.
+    fn skyrim_header(magic: &[u8], version: u32) -> Vec<u8> {
+        let mut bytes = magic.to_vec();
+        bytes.write_u32::<LittleEndian>(0).unwrap();
+        bytes.write_u32::<LittleEndian>(version).unwrap();
+        bytes.write_u32::<LittleEndian>(3).unwrap();
+        wstring(&mut bytes, "Prisoner");
+        bytes.write_u32::<LittleEndian>(5).unwrap();
+        wstring(&mut bytes, "Helgen");
+        wstring(&mut bytes, "Day 1");
+        wstring(&mut bytes, "NordRace");
+        bytes.extend_from_slice(&[0; 18]);
+        bytes.write_u32::<LittleEndian>(2).unwrap();
+        bytes.write_u32::<LittleEndian>(1).unwrap();
+        bytes
+    }
+
+    fn skyrim_se_body() -> Vec<u8> {
+        let mut bytes = vec![SKYRIM_SE_LIGHT_PLUGINS_FORM_VERSION];
+        bytes.write_u32::<LittleEndian>(0).unwrap();
+        bytes.push(2);
+        wstring(&mut bytes, "Skyrim.esm");
+        wstring(&mut bytes, "Blank.esp");
+        bytes.write_u16::<LittleEndian>(1).unwrap();
+        wstring(&mut bytes, "Blank.esl");
+        bytes
+    }
+
+    fn plugins(filenames: &[&str]) -> Vec<Plugin> {
+        filenames
+            .iter()
+            .map(|f| Plugin::new(GameId::SkyrimSE, Path::new(f)))
+            .collect()
+    }
+
+    #[test]
+    fn parse_should_read_an_oblivion_save() {
+        let mut bytes = OBLIVION_MAGIC.to_vec();
+        bytes.extend_from_slice(&[0; 30]);
+        bytes.extend_from_slice(b"\x05Name\0");
+        bytes.write_u16::<LittleEndian>(12).unwrap();
+        bytes.extend_from_slice(b"\x08Chorrol\0");
+        bytes.extend_from_slice(&[0; 24]);
+        bytes.write_u32::<LittleEndian>(14).unwrap();
+        bytes.write_u32::<LittleEndian>(2).unwrap();
+        bytes.write_u32::<LittleEndian>(1).unwrap();
+        bytes.extend_from_slice(&[0; 6]);
+        bytes.extend_from_slice(b"\x02\x0cOblivion.esm\x09Blank.esp");
+
+        let save = SaveGame::parse(GameId::Oblivion, &bytes).unwrap();
+
+        assert_eq!("Name", save.player_name());
+        assert_eq!(12, save.player_level());
+        assert_eq!("Chorrol", save.player_location());
+        assert_eq!(2, save.screenshot_width());
+        assert_eq!(1, save.screenshot_height());
+        assert_eq!(&["Oblivion.esm", "Blank.esp"], save.plugins());
+        assert!(save.light_plugins().is_empty());
+    }
+
+    #[test]
+    fn parse_should_read_a_skyrim_save() {
+        let mut bytes = skyrim_header(SKYRIM_MAGIC, 9);
+        bytes.extend_from_slice(&[0; 6]);
+        bytes.push(74);
+        bytes.write_u32::<LittleEndian>(0).unwrap();
+        bytes.push(1);
+        wstring(&mut bytes, "Skyrim.esm");
+
+        let save = SaveGame::parse(GameId::Skyrim, &bytes).unwrap();
+
+        assert_eq!("Prisoner", save.player_name());
+        assert_eq!(5, save.player_level());
+        assert_eq!("Helgen", save.player_location());
+        assert_eq!(2, save.screenshot_width());
+        assert_eq!(1, save.screenshot_height());
+        assert_eq!(&["Skyrim.esm"], save.plugins());
+        assert!(save.light_plugins().is_empty());
+    }
+
+    #[test]
+    fn parse_should_read_the_light_plugins_in_a_skyrim_se_save() {
+        let mut bytes = skyrim_header(SKYRIM_MAGIC, SKYRIM_SE_SAVE_VERSION);
+        bytes.write_u16::<LittleEndian>(0).unwrap();
+        bytes.extend_from_slice(&[0; 8]);
+        bytes.extend(skyrim_se_body());
+
+        let save = SaveGame::parse(GameId::SkyrimSE, &bytes).unwrap();
+
+        assert_eq!(&["Skyrim.esm", "Blank.esp"], save.plugins());
+        assert_eq!(&["Blank.esl"], save.light_plugins());
+    }
+
+    #[cfg(not(feature = "compressed-saves"))]
+    #[test]
+    fn parse_should_error_if_a_skyrim_se_save_is_compressed() {
+        let mut bytes = skyrim_header(SKYRIM_MAGIC, SKYRIM_SE_SAVE_VERSION);
+        bytes.write_u16::<LittleEndian>(2).unwrap();
+        bytes.extend_from_slice(&[0; 16]);
+
+        match SaveGame::parse(GameId::SkyrimSE, &bytes) {
+            Err(Error::UnsupportedSaveCompression(2)) => {}
+            _ => panic!("Expected an unsupported compression error"),
+        }
+    }
+
+    #[cfg(feature = "compressed-saves")]
+    #[test]
+    fn parse_should_decompress_a_skyrim_se_save_compressed_with_lz4() {
+        let body = skyrim_se_body();
+        let compressed = lz4_flex::block::compress(&body);
+
+        let mut bytes = skyrim_header(SKYRIM_MAGIC, SKYRIM_SE_SAVE_VERSION);
+        bytes.write_u16::<LittleEndian>(2).unwrap();
+        bytes.extend_from_slice(&[0; 8]);
+        bytes.write_u32::<LittleEndian>(body.len() as u32).unwrap();
+        bytes
+            .write_u32::<LittleEndian>(compressed.len() as u32)
+            .unwrap();
+        bytes.extend(compressed);
+
+        let save = SaveGame::parse(GameId::SkyrimSE, &bytes).unwrap();
+
+        assert_eq!(&["Skyrim.esm", "Blank.esp"], save.plugins());
+        assert_eq!(&["Blank.esl"], save.light_plugins());
+    }
+
+    #[test]
+    fn parse_should_read_a_fallout_new_vegas_save() {
+        let mut bytes = FALLOUT3_MAGIC.to_vec();
+        bytes.extend_from_slice(&[0; 8]);
+        bytes.push(b'|');
+        bytes.extend_from_slice(&[0; FALLOUT_NV_LANGUAGE_LENGTH]);
+        bytes.push(b'|');
+        delimited_u32(&mut bytes, 1);
+        delimited_u32(&mut bytes, 1);
+        delimited_u32(&mut bytes, 7);
+        delimited_string(&mut bytes, "Courier");
+        delimited_string(&mut bytes, "Wanderer");
+        delimited_u32(&mut bytes, 3);
+        delimited_string(&mut bytes, "Goodsprings");
+        delimited_string(&mut bytes, "001.00.00");
+        bytes.extend_from_slice(&[0; 3 + 5]);
+        bytes.push(1);
+        bytes.push(b'|');
+        delimited_string(&mut bytes, "FalloutNV.esm");
+
+        let save = SaveGame::parse(GameId::FalloutNV, &bytes).unwrap();
+
+        assert_eq!("Courier", save.player_name());
+        assert_eq!(3, save.player_level());
+        assert_eq!("Goodsprings", save.player_location());
+        assert_eq!(&["FalloutNV.esm"], save.plugins());
+
+        assert!(SaveGame::parse(GameId::Fallout3, &bytes).is_err());
+    }
+
+    #[test]
+    fn parse_should_read_the_light_plugins_in_a_fallout4_save() {
+        let mut bytes = skyrim_header(FALLOUT4_MAGIC, 11);
+        bytes.extend_from_slice(&[0; 8]);
+        bytes.push(FALLOUT4_LIGHT_PLUGINS_FORM_VERSION);
+        wstring(&mut bytes, "1.10.163.0");
+        bytes.write_u32::<LittleEndian>(0).unwrap();
+        bytes.push(1);
+        wstring(&mut bytes, "Fallout4.esm");
+        bytes.write_u16::<LittleEndian>(1).unwrap();
+        wstring(&mut bytes, "Blank.esl");
+
+        let save = SaveGame::parse(GameId::Fallout4, &bytes).unwrap();
+
+        assert_eq!("Prisoner", save.player_name());
+        assert_eq!(&["Fallout4.esm"], save.plugins());
+        assert_eq!(&["Blank.esl"], save.light_plugins());
+    }
+
+    #[test]
+    fn parse_should_error_if_the_save_is_for_a_different_game_or_truncated() {
+        let bytes = skyrim_header(SKYRIM_MAGIC, 9);
+
+        assert!(SaveGame::parse(GameId::Oblivion, &bytes).is_err());
+        match SaveGame::parse(GameId::Skyrim, &bytes) {
+            Err(Error::ParsingIncomplete) => {}
+            _ => panic!("Expected a parsing incomplete error"),
+        }
+    }
+
+    #[test]
+    fn compare_load_order_should_find_missing_added_and_moved_plugins() {
+        let save = SaveGame {
+            plugins: vec![
+                "Skyrim.esm".to_string(),
+                "A.esp".to_string(),
+                "B.esp".to_string(),
+                "C.esp".to_string(),
+            ],
+            light_plugins: vec!["A.esl".to_string()],
+            ..Default::default()
+        };
+
+        let load_order = plugins(&["skyrim.esm", "B.esp", "A.esp", "D.esp", "A.esl", "B.esl"]);
+
+        assert_eq!(
+            vec![
+                SavePluginDiagnostic::Missing("C.esp".to_string()),
+                SavePluginDiagnostic::NotInSave("D.esp".to_string()),
+                SavePluginDiagnostic::OutOfOrder("A.esp".to_string()),
+                SavePluginDiagnostic::OutOfOrder("B.esp".to_string()),
+                SavePluginDiagnostic::NotInSave("B.esl".to_string()),
+            ],
+            save.compare_load_order(&load_order)
+        );
+    }
+}
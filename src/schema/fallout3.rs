@@ -0,0 +1,259 @@
+/*
+ * This file is part of esplugin
+ *
+ * Copyright (C) 2017 Oliver Hamlet
+ *
+ * esplugin is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * esplugin is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with esplugin. If not, see <http://www.gnu.org/licenses/>.
+ */
+use super::{
+    FieldSchema, FieldType, RecordSchema, SubrecordSchema, EDID, FLTV, FNAM_GLOBAL_TYPE, FULL,
+    MODL, OBND, REFERENCE_DATA, XESP, XOWN, XRNK, XSCL,
+};
+
+// Fallout: New Vegas uses the same layouts as Fallout 3, though some of its
+// subrecords are longer and so decode extra fields.
+
+const SCRI: SubrecordSchema = subrecord!("SCRI", "Script", [script: FormId]);
+const EITM: SubrecordSchema = subrecord!("EITM", "Object Effect", [enchantment: FormId]);
+const REPL: SubrecordSchema = subrecord!("REPL", "Repair List", [repair_list: FormId]);
+const BIPL: SubrecordSchema = subrecord!("BIPL", "Biped Model List", [model_list: FormId]);
+const YNAM: SubrecordSchema = subrecord!("YNAM", "Pickup Sound", [sound: FormId]);
+const ZNAM: SubrecordSchema = subrecord!("ZNAM", "Drop Sound", [sound: FormId]);
+
+const GLOB: RecordSchema = RecordSchema {
+    record_type: "GLOB",
+    subrecords: &[EDID, FNAM_GLOBAL_TYPE, FLTV],
+};
+
+const WEAP: RecordSchema = RecordSchema {
+    record_type: "WEAP",
+    subrecords: &[
+        EDID,
+        OBND,
+        FULL,
+        MODL,
+        SCRI,
+        EITM,
+        subrecord!("NAM0", "Ammo", [ammo: FormId]),
+        REPL,
+        subrecord!("ETYP", "Equipment Type", [equipment_type: I32]),
+        BIPL,
+        YNAM,
+        ZNAM,
+        subrecord!(
+            "DATA",
+            "Data",
+            [
+                value: I32,
+                health: I32,
+                weight: F32,
+                base_damage: I16,
+                clip_size: U8,
+            ]
+        ),
+    ],
+};
+
+const ARMO: RecordSchema = RecordSchema {
+    record_type: "ARMO",
+    subrecords: &[
+        EDID,
+        OBND,
+        FULL,
+        SCRI,
+        EITM,
+        subrecord!(
+            "BMDT",
+            "Biped Data",
+            [biped_flags: Flags32, general_flags: Flags8, unused: Padding(3)]
+        ),
+        subrecord!("ETYP", "Equipment Type", [equipment_type: I32]),
+        REPL,
+        BIPL,
+        YNAM,
+        ZNAM,
+        subrecord!("DATA", "Data", [value: I32, health: I32, weight: F32]),
+        subrecord!(
+            "DNAM",
+            "Armor Data",
+            [damage_resistance: I16, flags: Flags16, damage_threshold: F32]
+        ),
+    ],
+};
+
+const NPC_: RecordSchema = RecordSchema {
+    record_type: "NPC_",
+    subrecords: &[
+        EDID,
+        OBND,
+        FULL,
+        MODL,
+        subrecord!(
+            "ACBS",
+            "Configuration",
+            [
+                flags: Flags32,
+                fatigue: U16,
+                barter_gold: U16,
+                level: I16,
+                calc_min_level: U16,
+                calc_max_level: U16,
+                speed_multiplier: U16,
+                karma: F32,
+                disposition_base: I16,
+                template_flags: Flags16,
+            ]
+        ),
+        subrecord!(
+            "SNAM",
+            "Faction",
+            [faction: FormId, rank: U8, unused: Padding(3)]
+        ),
+        subrecord!("INAM", "Death Item", [death_item: FormId]),
+        subrecord!("VTCK", "Voice", [voice_type: FormId]),
+        subrecord!("TPLT", "Template", [template: FormId]),
+        subrecord!("RNAM", "Race", [race: FormId]),
+        subrecord!("SPLO", "Actor Effect", [effect: FormId]),
+        subrecord!("EITM", "Unarmed Attack Effect", [effect: FormId]),
+        SCRI,
+        subrecord!("CNTO", "Item", [item: FormId, count: I32]),
+        subrecord!("PKID", "Package", [package: FormId]),
+        subrecord!("CNAM", "Class", [class: FormId]),
+        subrecord!("HNAM", "Hair", [hair: FormId]),
+        subrecord!("LNAM", "Hair Length", [length: F32]),
+        subrecord!("ENAM", "Eyes", [eyes: FormId]),
+        subrecord!(
+            "HCLR",
+            "Hair Color",
+            [red: U8, green: U8, blue: U8, unused: Padding(1)]
+        ),
+        subrecord!("ZNAM", "Combat Style", [combat_style: FormId]),
+        subrecord!("NAM4", "Impact Material Type", [material_type: U32]),
+        subrecord!("NAM6", "Height", [height: F32]),
+        subrecord!("NAM7", "Weight", [weight: F32]),
+    ],
+};
+
+const CELL: RecordSchema = RecordSchema {
+    record_type: "CELL",
+    subrecords: &[
+        EDID,
+        FULL,
+        subrecord!("DATA", "Flags", [flags: Flags8]),
+        subrecord!("XCLC", "Grid", [x: I32, y: I32, flags: Flags32]),
+        subrecord!("XCLW", "Water Height", [height: F32]),
+        subrecord!("XCLR", "Regions", [regions: FormIdArray]),
+        subrecord!("XCIM", "Image Space", [image_space: FormId]),
+        subrecord!("XEZN", "Encounter Zone", [encounter_zone: FormId]),
+        subrecord!("XCCM", "Climate", [climate: FormId]),
+        subrecord!("XCWT", "Water", [water: FormId]),
+        subrecord!("XCAS", "Acoustic Space", [acoustic_space: FormId]),
+        subrecord!("XCMO", "Music Type", [music_type: FormId]),
+        XOWN,
+        XRNK,
+    ],
+};
+
+const REFR: RecordSchema = RecordSchema {
+    record_type: "REFR",
+    subrecords: &[
+        EDID,
+        subrecord!("NAME", "Base", [base: FormId]),
+        subrecord!("XEZN", "Encounter Zone", [encounter_zone: FormId]),
+        XOWN,
+        XRNK,
+        XESP,
+        subrecord!(
+            "XTEL",
+            "Teleport Destination",
+            [
+                door: FormId,
+                position_x: F32,
+                position_y: F32,
+                position_z: F32,
+                rotation_x: F32,
+                rotation_y: F32,
+                rotation_z: F32,
+            ]
+        ),
+        subrecord!(
+            "XLOC",
+            "Lock Data",
+            [level: U8, unused: Padding(3), key: FormId]
+        ),
+        XSCL,
+        subrecord!("XCNT", "Count", [count: I32]),
+        subrecord!("XRDS", "Radius", [radius: F32]),
+        subrecord!("XHLP", "Health", [health: F32]),
+        REFERENCE_DATA,
+    ],
+};
+
+const LVLI: RecordSchema = RecordSchema {
+    record_type: "LVLI",
+    subrecords: &[
+        EDID,
+        OBND,
+        subrecord!("LVLD", "Chance None", [chance_none: U8]),
+        subrecord!("LVLF", "Flags", [flags: Flags8]),
+        subrecord!("LVLG", "Global", [global: FormId]),
+        subrecord!(
+            "LVLO",
+            "Entry",
+            [
+                level: I16,
+                unused: Padding(2),
+                reference: FormId,
+                count: I16,
+                unused: Padding(2),
+            ]
+        ),
+        subrecord!(
+            "COED",
+            "Extra Data",
+            [owner: FormId, global_or_rank: U32, item_condition: F32]
+        ),
+    ],
+};
+
+const QUST: RecordSchema = RecordSchema {
+    record_type: "QUST",
+    subrecords: &[
+        EDID,
+        SCRI,
+        FULL,
+        subrecord!(
+            "DATA",
+            "General",
+            [
+                flags: Flags8,
+                priority: U8,
+                unused: Padding(2),
+                quest_delay: F32,
+            ]
+        ),
+        subrecord!("INDX", "Stage Index", [journal_index: I16]),
+        subrecord!("QSDT", "Stage Flags", [flags: Flags8]),
+        subrecord!("CNAM", "Log Entry", [text: ZString]),
+        subrecord!("QOBJ", "Objective Index", [index: I32]),
+        subrecord!("NNAM", "Objective Description", [text: ZString]),
+        subrecord!(
+            "QSTA",
+            "Target",
+            [target: FormId, flags: Flags8, unused: Padding(3)]
+        ),
+    ],
+};
+
+pub const RECORDS: &[RecordSchema] = &[GLOB, WEAP, ARMO, NPC_, CELL, REFR, LVLI, QUST];
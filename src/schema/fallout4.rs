@@ -0,0 +1,281 @@
+/*
+ * This file is part of esplugin
+ *
+ * Copyright (C) 2017 Oliver Hamlet
+ *
+ * esplugin is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * esplugin is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with esplugin. If not, see <http://www.gnu.org/licenses/>.
+ */
+use super::{
+    FieldSchema, FieldType, RecordSchema, SubrecordSchema, EDID, FLTV, FNAM_GLOBAL_TYPE, FULL,
+    KSIZ, KWDA, MODL, OBND, REFERENCE_DATA, XESP, XOWN, XRNK, XSCL,
+};
+
+const DESC: SubrecordSchema = subrecord!("DESC", "Description", [description: LString]);
+const EITM: SubrecordSchema = subrecord!("EITM", "Object Effect", [enchantment: FormId]);
+const ETYP: SubrecordSchema = subrecord!("ETYP", "Equipment Type", [equipment_type: FormId]);
+const YNAM: SubrecordSchema = subrecord!("YNAM", "Pickup Sound", [sound: FormId]);
+const ZNAM: SubrecordSchema = subrecord!("ZNAM", "Putdown Sound", [sound: FormId]);
+
+const GLOB: RecordSchema = RecordSchema {
+    record_type: "GLOB",
+    subrecords: &[EDID, FNAM_GLOBAL_TYPE, FLTV],
+};
+
+const KYWD: RecordSchema = RecordSchema {
+    record_type: "KYWD",
+    subrecords: &[
+        EDID,
+        subrecord!(
+            "CNAM",
+            "Color",
+            [red: U8, green: U8, blue: U8, unused: Padding(1)]
+        ),
+        FULL,
+        subrecord!("DNAM", "Notes", [notes: ZString]),
+        subrecord!("TNAM", "Type", [keyword_type: U32]),
+        subrecord!("DATA", "Attraction Rule", [attraction_rule: FormId]),
+    ],
+};
+
+const WEAP: RecordSchema = RecordSchema {
+    record_type: "WEAP",
+    subrecords: &[
+        EDID,
+        OBND,
+        FULL,
+        MODL,
+        EITM,
+        subrecord!("EAMT", "Enchantment Amount", [amount: U16]),
+        ETYP,
+        subrecord!("BIDS", "Block Bash Impact", [impact_data_set: FormId]),
+        subrecord!("BAMT", "Alternate Block Material", [material: FormId]),
+        YNAM,
+        ZNAM,
+        KSIZ,
+        KWDA,
+        DESC,
+        subrecord!("INAM", "Impact Data Set", [impact_data_set: FormId]),
+        subrecord!(
+            "DNAM",
+            "Data",
+            [
+                ammo: FormId,
+                speed: F32,
+                reload_speed: F32,
+                reach: F32,
+                minimum_range: F32,
+                maximum_range: F32,
+                attack_delay: F32,
+            ]
+        ),
+    ],
+};
+
+const ARMO: RecordSchema = RecordSchema {
+    record_type: "ARMO",
+    subrecords: &[
+        EDID,
+        OBND,
+        FULL,
+        EITM,
+        subrecord!("BOD2", "Biped Body Template", [body_slots: Flags32]),
+        ETYP,
+        subrecord!("BIDS", "Block Bash Impact", [impact_data_set: FormId]),
+        subrecord!("BAMT", "Alternate Block Material", [material: FormId]),
+        YNAM,
+        ZNAM,
+        subrecord!("RNAM", "Race", [race: FormId]),
+        KSIZ,
+        KWDA,
+        DESC,
+        subrecord!("DATA", "Data", [value: I32, weight: F32, health: U32]),
+        subrecord!(
+            "FNAM",
+            "Armor Data",
+            [armor_rating: U16, base_addon_index: U16, stagger_rating: U8]
+        ),
+        subrecord!("TNAM", "Template", [template: FormId]),
+    ],
+};
+
+const NPC_: RecordSchema = RecordSchema {
+    record_type: "NPC_",
+    subrecords: &[
+        EDID,
+        OBND,
+        subrecord!(
+            "ACBS",
+            "Configuration",
+            [
+                flags: Flags32,
+                xp_value_offset: I16,
+                level: I16,
+                calc_min_level: U16,
+                calc_max_level: U16,
+                disposition_base: I16,
+                template_flags: Flags16,
+                bleedout_override: I16,
+            ]
+        ),
+        subrecord!(
+            "SNAM",
+            "Faction",
+            [faction: FormId, rank: I8, unused: Padding(3)]
+        ),
+        subrecord!("INAM", "Death Item", [death_item: FormId]),
+        subrecord!("VTCK", "Voice", [voice_type: FormId]),
+        subrecord!("TPLT", "Template", [template: FormId]),
+        subrecord!("RNAM", "Race", [race: FormId]),
+        subrecord!("SPCT", "Spell Count", [count: U32]),
+        subrecord!("SPLO", "Spell", [spell: FormId]),
+        subrecord!("PRKZ", "Perk Count", [count: U32]),
+        subrecord!("PRKR", "Perk", [perk: FormId, rank: U8]),
+        KSIZ,
+        KWDA,
+        subrecord!("CNAM", "Class", [class: FormId]),
+        FULL,
+        subrecord!("SHRT", "Short Name", [name: LString]),
+        subrecord!("DOFT", "Default Outfit", [outfit: FormId]),
+        subrecord!("SOFT", "Sleeping Outfit", [outfit: FormId]),
+        subrecord!("CNTO", "Item", [item: FormId, count: I32]),
+        subrecord!("PKID", "Package", [package: FormId]),
+        subrecord!("WNAM", "Worn Armor", [armor: FormId]),
+        subrecord!("ATKR", "Attack Race", [race: FormId]),
+        subrecord!("FTST", "Head Texture", [texture_set: FormId]),
+        subrecord!("ZNAM", "Combat Style", [combat_style: FormId]),
+    ],
+};
+
+const CELL: RecordSchema = RecordSchema {
+    record_type: "CELL",
+    subrecords: &[
+        EDID,
+        FULL,
+        subrecord!("DATA", "Flags", [flags: Flags16]),
+        subrecord!("XCLC", "Grid", [x: I32, y: I32, flags: Flags32]),
+        subrecord!("LTMP", "Lighting Template", [template: FormId]),
+        subrecord!("XCLW", "Water Height", [height: F32]),
+        subrecord!("XCLR", "Regions", [regions: FormIdArray]),
+        subrecord!("XLCN", "Location", [location: FormId]),
+        subrecord!("XCWT", "Water", [water: FormId]),
+        subrecord!("XCCM", "Sky/Weather from Region", [region: FormId]),
+        subrecord!("XCAS", "Acoustic Space", [acoustic_space: FormId]),
+        subrecord!("XEZN", "Encounter Zone", [encounter_zone: FormId]),
+        subrecord!("XCMO", "Music Type", [music_type: FormId]),
+        subrecord!("XCIM", "Image Space", [image_space: FormId]),
+        XOWN,
+        XRNK,
+    ],
+};
+
+const REFR: RecordSchema = RecordSchema {
+    record_type: "REFR",
+    subrecords: &[
+        EDID,
+        subrecord!("NAME", "Base", [base: FormId]),
+        XSCL,
+        XOWN,
+        XRNK,
+        subrecord!(
+            "XLKR",
+            "Linked Reference",
+            [keyword: FormId, reference: FormId]
+        ),
+        XESP,
+        subrecord!(
+            "XTEL",
+            "Teleport Destination",
+            [
+                door: FormId,
+                position_x: F32,
+                position_y: F32,
+                position_z: F32,
+                rotation_x: F32,
+                rotation_y: F32,
+                rotation_z: F32,
+                flags: Flags32,
+                transition_interior: FormId,
+            ]
+        ),
+        subrecord!(
+            "XLOC",
+            "Lock Data",
+            [level: U8, unused: Padding(3), key: FormId, flags: Flags8]
+        ),
+        subrecord!("XEZN", "Encounter Zone", [encounter_zone: FormId]),
+        subrecord!(
+            "XLRT",
+            "Location Reference Type",
+            [location_ref_types: FormIdArray]
+        ),
+        subrecord!("XCNT", "Item Count", [count: I32]),
+        subrecord!(
+            "XNDP",
+            "Navigation Door Link",
+            [navmesh: FormId, triangle: U16, unused: Padding(2)]
+        ),
+        REFERENCE_DATA,
+    ],
+};
+
+const LVLI: RecordSchema = RecordSchema {
+    record_type: "LVLI",
+    subrecords: &[
+        EDID,
+        OBND,
+        subrecord!("LVLD", "Chance None", [chance_none: U8]),
+        subrecord!("LVLM", "Max Count", [max_count: U8]),
+        subrecord!("LVLF", "Flags", [flags: Flags8]),
+        subrecord!("LVLG", "Global", [global: FormId]),
+        subrecord!("LLCT", "Count", [count: U8]),
+        subrecord!(
+            "LVLO",
+            "Entry",
+            [
+                level: U16,
+                unused: Padding(2),
+                reference: FormId,
+                count: U16,
+            ]
+        ),
+        subrecord!(
+            "COED",
+            "Extra Data",
+            [owner: FormId, global_or_rank: U32, item_condition: F32]
+        ),
+    ],
+};
+
+const QUST: RecordSchema = RecordSchema {
+    record_type: "QUST",
+    subrecords: &[
+        EDID,
+        FULL,
+        subrecord!("DNAM", "General", [flags: Flags16, priority: U8]),
+        subrecord!("FLTR", "Object Window Filter", [filter: ZString]),
+        subrecord!(
+            "INDX",
+            "Stage",
+            [journal_index: U16, flags: Flags8, unused: Padding(1)]
+        ),
+        subrecord!("QOBJ", "Objective Index", [index: U16]),
+        subrecord!("NNAM", "Objective Display Text", [text: LString]),
+        subrecord!("ANAM", "Next Alias ID", [alias_id: U32]),
+        subrecord!("ALST", "Reference Alias ID", [alias_id: U32]),
+        subrecord!("ALLS", "Location Alias ID", [alias_id: U32]),
+        subrecord!("ALID", "Alias Name", [name: ZString]),
+    ],
+};
+
+pub const RECORDS: &[RecordSchema] = &[GLOB, KYWD, WEAP, ARMO, NPC_, CELL, REFR, LVLI, QUST];
@@ -0,0 +1,677 @@
+/*
+ * This file is part of esplugin
+ *
+ * Copyright (C) 2017 Oliver Hamlet
+ *
+ * esplugin is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * esplugin is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with esplugin. If not, see <http://www.gnu.org/licenses/>.
+ */
+use std::cmp;
+
+use byteorder::{ByteOrder, LittleEndian};
+use encoding::all::WINDOWS_1252;
+use encoding::{DecoderTrap, Encoding};
+
+use error::Error;
+use game_id::GameId;
+use record::Record;
+
+// Builds a SubrecordSchema from its type, a descriptive name and a list of
+// field names and types, e.g.
+// subrecord!("DATA", "Data", [value: U32, weight: F32, damage: U16])
+macro_rules! subrecord {
+    ($subrecord_type:expr, $name:expr, [$($field:ident : $field_type:ident $(($arg:expr))*),* $(,)*]) => {
+        SubrecordSchema {
+            subrecord_type: $subrecord_type,
+            name: $name,
+            fields: &[$(FieldSchema {
+                name: stringify!($field),
+                field_type: FieldType::$field_type $(($arg))*,
+            }),*],
+        }
+    };
+}
+
+const EDID: SubrecordSchema = subrecord!("EDID", "Editor ID", [editor_id: ZString]);
+const FULL: SubrecordSchema = subrecord!("FULL", "Name", [name: LString]);
+const MODL: SubrecordSchema = subrecord!("MODL", "Model", [model: ZString]);
+const OBND: SubrecordSchema = subrecord!(
+    "OBND",
+    "Object Bounds",
+    [x1: I16, y1: I16, z1: I16, x2: I16, y2: I16, z2: I16]
+);
+const KSIZ: SubrecordSchema = subrecord!("KSIZ", "Keyword Count", [count: U32]);
+const KWDA: SubrecordSchema = subrecord!("KWDA", "Keywords", [keywords: FormIdArray]);
+const FNAM_GLOBAL_TYPE: SubrecordSchema = subrecord!("FNAM", "Type", [value_type: U8]);
+const FLTV: SubrecordSchema = subrecord!("FLTV", "Value", [value: F32]);
+const XSCL: SubrecordSchema = subrecord!("XSCL", "Scale", [scale: F32]);
+const XOWN: SubrecordSchema = subrecord!("XOWN", "Owner", [owner: FormId]);
+const XRNK: SubrecordSchema = subrecord!("XRNK", "Faction Rank", [rank: I32]);
+const XESP: SubrecordSchema = subrecord!(
+    "XESP",
+    "Enable Parent",
+    [parent: FormId, flags: Flags8, unused: Padding(3)]
+);
+const REFERENCE_DATA: SubrecordSchema = subrecord!(
+    "DATA",
+    "Position/Rotation",
+    [
+        position_x: F32,
+        position_y: F32,
+        position_z: F32,
+        rotation_x: F32,
+        rotation_y: F32,
+        rotation_z: F32,
+    ]
+);
+
+mod fallout3;
+mod fallout4;
+mod morrowind;
+mod oblivion;
+mod skyrim;
+mod views;
+
+pub use self::views::{
+    Armor, Cell, FactionRank, Global, ItemCount, Keyword, LeveledEntry, LeveledItem, Npc, Quest,
+    Reference, Text, Weapon,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum FieldType {
+    U8,
+    U16,
+    U32,
+    I8,
+    I16,
+    I32,
+    F32,
+    FormId,
+    Flags8,
+    Flags16,
+    Flags32,
+    // A null-terminated Windows-1252 string.
+    ZString,
+    // A string table ID in localised plugins, and a ZString otherwise.
+    LString,
+    // Bytes that are skipped without being decoded.
+    Padding(usize),
+    // All the remaining data, as a list of FormIDs.
+    FormIdArray,
+    // All the remaining data, undecoded.
+    Bytes,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct FieldSchema {
+    pub name: &'static str,
+    pub field_type: FieldType,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct SubrecordSchema {
+    pub subrecord_type: &'static str,
+    pub name: &'static str,
+    pub fields: &'static [FieldSchema],
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct RecordSchema {
+    pub record_type: &'static str,
+    pub subrecords: &'static [SubrecordSchema],
+}
+
+// Tables are listed from most to least specific, so a game can override the
+// layout of individual subrecords that it shares with another game.
+fn schema_tables(game_id: GameId) -> &'static [&'static [RecordSchema]] {
+    match game_id {
+        GameId::Morrowind => &[morrowind::RECORDS],
+        GameId::Oblivion => &[oblivion::RECORDS],
+        GameId::Fallout3 | GameId::FalloutNV => &[fallout3::RECORDS],
+        GameId::Skyrim => &[skyrim::RECORDS],
+        GameId::SkyrimSE => &[skyrim::SE_RECORDS, skyrim::RECORDS],
+        GameId::Fallout4 => &[fallout4::RECORDS],
+    }
+}
+
+pub fn has_schema(game_id: GameId, record_type: &str) -> bool {
+    schema_tables(game_id)
+        .iter()
+        .any(|t| t.iter().any(|r| r.record_type == record_type))
+}
+
+pub fn subrecord_schema(
+    game_id: GameId,
+    record_type: &str,
+    subrecord_type: &str,
+) -> Option<&'static SubrecordSchema> {
+    schema_tables(game_id)
+        .iter()
+        .flat_map(|t| t.iter())
+        .filter(|r| r.record_type == record_type)
+        .flat_map(|r| r.subrecords.iter())
+        .find(|s| s.subrecord_type == subrecord_type)
+}
+
+// Returns the parts of the record's subrecord data that its game's schema
+// doesn't decode: all of each subrecord that has no schema, and whatever is
+// left after the fields of each subrecord that has one.
+pub fn undecoded_data(record: &Record, game_id: GameId, is_localized: bool) -> Vec<&[u8]> {
+    let record_type = record.header().record_type();
+
+    record
+        .subrecords()
+        .iter()
+        .filter_map(|subrecord| {
+            let data = subrecord.data();
+            let decoded_size = subrecord_schema(game_id, record_type, subrecord.subrecord_type())
+                .map(|s| decode_fields(s.fields, data, is_localized).1)
+                .unwrap_or(0);
+
+            if decoded_size < data.len() {
+                Some(&data[decoded_size..])
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum FieldValue {
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    F32(f32),
+    FormId(u32),
+    Flags(u32),
+    String(String),
+    LocalizedString(u32),
+    FormIds(Vec<u32>),
+    Bytes(Vec<u8>),
+}
+
+impl FieldValue {
+    pub fn as_u32(&self) -> Option<u32> {
+        match *self {
+            FieldValue::U8(x) => Some(u32::from(x)),
+            FieldValue::U16(x) => Some(u32::from(x)),
+            FieldValue::U32(x) | FieldValue::Flags(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_i32(&self) -> Option<i32> {
+        match *self {
+            FieldValue::U8(x) => Some(i32::from(x)),
+            FieldValue::U16(x) => Some(i32::from(x)),
+            FieldValue::I8(x) => Some(i32::from(x)),
+            FieldValue::I16(x) => Some(i32::from(x)),
+            FieldValue::I32(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            FieldValue::U8(x) => Some(i64::from(x)),
+            FieldValue::U16(x) => Some(i64::from(x)),
+            FieldValue::U32(x) => Some(i64::from(x)),
+            FieldValue::I8(x) => Some(i64::from(x)),
+            FieldValue::I16(x) => Some(i64::from(x)),
+            FieldValue::I32(x) => Some(i64::from(x)),
+            _ => None,
+        }
+    }
+
+    pub fn as_f32(&self) -> Option<f32> {
+        match *self {
+            FieldValue::F32(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_form_id(&self) -> Option<u32> {
+        match *self {
+            FieldValue::FormId(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            FieldValue::String(ref x) => Some(x),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Field {
+    name: &'static str,
+    value: FieldValue,
+}
+
+impl Field {
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn value(&self) -> &FieldValue {
+        &self.value
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct TypedSubrecord {
+    subrecord_type: &'static str,
+    name: &'static str,
+    fields: Vec<Field>,
+}
+
+impl TypedSubrecord {
+    pub fn subrecord_type(&self) -> &'static str {
+        self.subrecord_type
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    pub fn field(&self, name: &str) -> Option<&FieldValue> {
+        self.fields
+            .iter()
+            .find(|f| f.name == name)
+            .map(|f| &f.value)
+    }
+}
+
+// A view of a record that holds the decoded values of the subrecords that its
+// game's schema describes. Other subrecords are left out.
+#[derive(Clone, PartialEq, Debug)]
+pub struct TypedRecord {
+    record_type: String,
+    form_id: u32,
+    subrecords: Vec<TypedSubrecord>,
+}
+
+impl TypedRecord {
+    // Returns None if there is no schema for the record's type. Fields that
+    // are missing from the end of a subrecord's data are left out, as older
+    // versions of some subrecords are shorter.
+    pub fn decode(
+        record: &Record,
+        game_id: GameId,
+        is_localized: bool,
+    ) -> Result<Option<TypedRecord>, Error> {
+        let record_type = record.header().record_type();
+        if !has_schema(game_id, record_type) {
+            return Ok(None);
+        }
+
+        let mut subrecords = Vec::new();
+        for subrecord in record.subrecords() {
+            if let Some(schema) = subrecord_schema(game_id, record_type, subrecord.subrecord_type())
+            {
+                subrecords.push(TypedSubrecord {
+                    subrecord_type: schema.subrecord_type,
+                    name: schema.name,
+                    fields: decode_fields(schema.fields, subrecord.data(), is_localized).0,
+                });
+            }
+        }
+
+        Ok(Some(TypedRecord {
+            record_type: record_type.to_string(),
+            form_id: record.header().form_id(),
+            subrecords,
+        }))
+    }
+
+    pub fn record_type(&self) -> &str {
+        &self.record_type
+    }
+
+    pub fn form_id(&self) -> u32 {
+        self.form_id
+    }
+
+    pub fn subrecords(&self) -> &[TypedSubrecord] {
+        &self.subrecords
+    }
+
+    // Returns the first subrecord of the given type.
+    pub fn subrecord(&self, subrecord_type: &str) -> Option<&TypedSubrecord> {
+        self.subrecords
+            .iter()
+            .find(|s| s.subrecord_type == subrecord_type)
+    }
+
+    pub fn field(&self, subrecord_type: &str, field_name: &str) -> Option<&FieldValue> {
+        self.subrecord(subrecord_type)
+            .and_then(|s| s.field(field_name))
+    }
+
+    pub fn editor_id(&self) -> Option<&str> {
+        self.field("EDID", "editor_id").and_then(|v| v.as_str())
+    }
+
+    // Returns the FormIDs held by the record's FormID fields, sorted and
+    // without duplicates. Null (zero) FormIDs are left out. The FormIDs are
+    // as they appear in the record's plugin.
+    pub fn referenced_form_ids(&self) -> Vec<u32> {
+        let mut form_ids: Vec<u32> = self
+            .subrecords
+            .iter()
+            .flat_map(|s| s.fields.iter())
+            .flat_map(|f| match f.value {
+                FieldValue::FormId(form_id) => vec![form_id],
+                FieldValue::FormIds(ref form_ids) => form_ids.clone(),
+                _ => Vec::new(),
+            })
+            .filter(|f| *f != 0)
+            .collect();
+
+        form_ids.sort();
+        form_ids.dedup();
+
+        form_ids
+    }
+}
+
+// Returns the decoded fields and the number of bytes they took up.
+fn decode_fields(schema: &[FieldSchema], data: &[u8], is_localized: bool) -> (Vec<Field>, usize) {
+    let mut fields = Vec::new();
+    let mut remaining = data;
+
+    for field in schema {
+        let (value, size) = match decode_field(field.field_type, remaining, is_localized) {
+            Some(x) => x,
+            None => break,
+        };
+
+        if let Some(value) = value {
+            fields.push(Field {
+                name: field.name,
+                value,
+            });
+        }
+
+        remaining = &remaining[size..];
+    }
+
+    (fields, data.len() - remaining.len())
+}
+
+// Returns the decoded value (if the field isn't padding) and the number of
+// bytes it took up, or None if there isn't enough data left for the field.
+// Strings are decoded leniently, so that one bad string doesn't stop the rest
+// of the plugin's records being decoded.
+fn decode_field(
+    field_type: FieldType,
+    data: &[u8],
+    is_localized: bool,
+) -> Option<(Option<FieldValue>, usize)> {
+    let size = match field_type {
+        FieldType::U8 | FieldType::I8 | FieldType::Flags8 => 1,
+        FieldType::U16 | FieldType::I16 | FieldType::Flags16 => 2,
+        FieldType::U32
+        | FieldType::I32
+        | FieldType::F32
+        | FieldType::FormId
+        | FieldType::Flags32 => 4,
+        FieldType::LString if is_localized => 4,
+        FieldType::Padding(size) => size,
+        FieldType::ZString | FieldType::LString | FieldType::FormIdArray | FieldType::Bytes => {
+            data.len()
+        }
+    };
+
+    if data.is_empty() || data.len() < size {
+        return None;
+    }
+
+    let value = match field_type {
+        FieldType::U8 => FieldValue::U8(data[0]),
+        FieldType::U16 => FieldValue::U16(LittleEndian::read_u16(data)),
+        FieldType::U32 => FieldValue::U32(LittleEndian::read_u32(data)),
+        FieldType::I8 => FieldValue::I8(data[0] as i8),
+        FieldType::I16 => FieldValue::I16(LittleEndian::read_i16(data)),
+        FieldType::I32 => FieldValue::I32(LittleEndian::read_i32(data)),
+        FieldType::F32 => FieldValue::F32(LittleEndian::read_f32(data)),
+        FieldType::FormId => FieldValue::FormId(LittleEndian::read_u32(data)),
+        FieldType::Flags8 => FieldValue::Flags(u32::from(data[0])),
+        FieldType::Flags16 => FieldValue::Flags(u32::from(LittleEndian::read_u16(data))),
+        FieldType::Flags32 => FieldValue::Flags(LittleEndian::read_u32(data)),
+        FieldType::LString if is_localized => {
+            FieldValue::LocalizedString(LittleEndian::read_u32(data))
+        }
+        FieldType::ZString | FieldType::LString => {
+            let end = data.iter().position(|b| *b == 0).unwrap_or(data.len());
+            let string = WINDOWS_1252
+                .decode(&data[..end], DecoderTrap::Replace)
+                .unwrap_or_default();
+
+            return Some((Some(FieldValue::String(string)), cmp::min(end + 1, size)));
+        }
+        FieldType::Padding(_) => return Some((None, size)),
+        FieldType::FormIdArray => FieldValue::FormIds(
+            data.chunks(4)
+                .filter(|c| c.len() == 4)
+                .map(LittleEndian::read_u32)
+                .collect(),
+        ),
+        FieldType::Bytes => FieldValue::Bytes(data.to_vec()),
+    };
+
+    Some((Some(value), size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WEAP_RECORD: &[u8] =
+        b"WEAP\x2E\x00\x00\x00\x00\x00\x00\x00\x00\x08\x00\x01\x00\x00\x00\x00\x2C\x00\x00\x00\
+        EDID\x0A\x00IronSword\x00\
+        DATA\x0A\x00\x19\x00\x00\x00\x00\x00\x10\x41\x07\x00\
+        KWDA\x08\x00\x01\x00\x00\x00\x02\x00\x00\x00";
+
+    fn weapon(game_id: GameId) -> TypedRecord {
+        let record = Record::parse(WEAP_RECORD, GameId::Skyrim, false).unwrap().1;
+        TypedRecord::decode(&record, game_id, false)
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn decode_should_return_none_for_a_record_type_with_no_schema() {
+        let data =
+            b"ABCD\x00\x00\x00\x00\x00\x00\x00\x00\x00\x08\x00\x01\x00\x00\x00\x00\x2C\x00\x00\x00";
+        let record = Record::parse(data, GameId::Skyrim, false).unwrap().1;
+
+        assert!(TypedRecord::decode(&record, GameId::Skyrim, false)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn decode_should_decode_the_fields_of_subrecords_in_the_schema() {
+        let weapon = weapon(GameId::Skyrim);
+
+        assert_eq!("WEAP", weapon.record_type());
+        assert_eq!(0x0100_0800, weapon.form_id());
+        assert_eq!(Some("IronSword"), weapon.editor_id());
+        assert_eq!(
+            Some(25),
+            weapon.field("DATA", "value").and_then(|v| v.as_u32())
+        );
+        assert_eq!(
+            Some(9.0),
+            weapon.field("DATA", "weight").and_then(|v| v.as_f32())
+        );
+        assert_eq!(
+            Some(7),
+            weapon.field("DATA", "damage").and_then(|v| v.as_u32())
+        );
+        assert_eq!(
+            Some(&FieldValue::FormIds(vec![1, 2])),
+            weapon.field("KWDA", "keywords")
+        );
+    }
+
+    #[test]
+    fn decode_should_leave_out_fields_that_are_past_the_end_of_the_data() {
+        let record = Record::parse(WEAP_RECORD, GameId::Skyrim, false).unwrap().1;
+        let weapon = TypedRecord::decode(&record, GameId::Oblivion, false)
+            .unwrap()
+            .unwrap();
+
+        let data = weapon.subrecord("DATA").unwrap();
+        assert_eq!(2, data.fields().len());
+        assert_eq!("weapon_type", data.fields()[0].name());
+        assert_eq!(Some(&FieldValue::U32(25)), data.field("weapon_type"));
+        assert!(weapon.field("DATA", "damage").is_none());
+    }
+
+    #[test]
+    fn decode_field_should_skip_padding() {
+        let schema = &[
+            FieldSchema {
+                name: "a",
+                field_type: FieldType::U8,
+            },
+            FieldSchema {
+                name: "unused",
+                field_type: FieldType::Padding(3),
+            },
+            FieldSchema {
+                name: "b",
+                field_type: FieldType::FormId,
+            },
+        ];
+        let (fields, size) = decode_fields(schema, b"\x01\x00\x00\x00\x02\x00\x00\x00", false);
+
+        assert_eq!(8, size);
+        assert_eq!(2, fields.len());
+        assert_eq!(FieldValue::U8(1), fields[0].value);
+        assert_eq!(FieldValue::FormId(2), fields[1].value);
+    }
+
+    #[test]
+    fn decode_field_should_decode_lstrings_as_string_ids_in_localized_plugins() {
+        let data = b"\x01\x02\x00\x00";
+
+        assert_eq!(
+            FieldValue::LocalizedString(0x201),
+            decode_field(FieldType::LString, data, true)
+                .unwrap()
+                .0
+                .unwrap()
+        );
+        assert_eq!(
+            FieldValue::String("\u{1}\u{2}".to_string()),
+            decode_field(FieldType::LString, data, false)
+                .unwrap()
+                .0
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn decode_field_should_decode_bytes_that_windows_1252_leaves_undefined() {
+        let value = decode_field(FieldType::ZString, b"a\x81b\x00", false)
+            .unwrap()
+            .0
+            .unwrap();
+
+        assert_eq!(FieldValue::String("a\u{81}b".to_string()), value);
+    }
+
+    #[test]
+    fn referenced_form_ids_should_return_the_sorted_non_null_form_id_field_values() {
+        let data =
+            b"REFR\x22\x00\x00\x00\x00\x00\x00\x00\x03\x08\x00\x01\x00\x00\x00\x00\x2C\x00\x00\x00\
+            NAME\x04\x00\x00\x08\x00\x01\
+            XOWN\x04\x00\x00\x00\x00\x00\
+            XESP\x08\x00\x14\x00\x00\x00\x01\x00\x00\x00";
+        let record = Record::parse(data, GameId::Skyrim, false).unwrap().1;
+        let reference = TypedRecord::decode(&record, GameId::Skyrim, false)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(vec![0x14, 0x0100_0800], reference.referenced_form_ids());
+
+        assert_eq!(vec![1, 2], weapon(GameId::Skyrim).referenced_form_ids());
+    }
+
+    #[test]
+    fn undecoded_data_should_return_data_that_the_schema_does_not_cover() {
+        let record = Record::parse(WEAP_RECORD, GameId::Skyrim, false).unwrap().1;
+
+        assert!(undecoded_data(&record, GameId::Skyrim, false).is_empty());
+
+        let oblivion_data: &[u8] = b"\x07\x00";
+        assert_eq!(
+            vec![oblivion_data, b"\x01\x00\x00\x00\x02\x00\x00\x00"],
+            undecoded_data(&record, GameId::Oblivion, false)
+        );
+
+        let data =
+            b"ABCD\x0A\x00\x00\x00\x00\x00\x00\x00\x00\x08\x00\x01\x00\x00\x00\x00\x2C\x00\x00\x00\
+            DATA\x04\x00\x00\x08\x00\x01";
+        let record = Record::parse(data, GameId::Skyrim, false).unwrap().1;
+        let record_data: &[u8] = b"\x00\x08\x00\x01";
+
+        assert_eq!(
+            vec![record_data],
+            undecoded_data(&record, GameId::Skyrim, false)
+        );
+    }
+
+    #[test]
+    fn subrecord_schema_should_prefer_more_specific_tables() {
+        let skyrim = subrecord_schema(GameId::Skyrim, "WEAP", "CRDT").unwrap();
+        let skyrim_se = subrecord_schema(GameId::SkyrimSE, "WEAP", "CRDT").unwrap();
+
+        assert_ne!(skyrim, skyrim_se);
+        assert_eq!(
+            subrecord_schema(GameId::Skyrim, "WEAP", "DATA"),
+            subrecord_schema(GameId::SkyrimSE, "WEAP", "DATA")
+        );
+    }
+
+    #[test]
+    fn every_game_should_have_a_schema_for_global_variables() {
+        let games = &[
+            GameId::Morrowind,
+            GameId::Oblivion,
+            GameId::Fallout3,
+            GameId::FalloutNV,
+            GameId::Skyrim,
+            GameId::SkyrimSE,
+            GameId::Fallout4,
+        ];
+
+        for game_id in games {
+            assert!(has_schema(*game_id, "GLOB"));
+        }
+    }
+}
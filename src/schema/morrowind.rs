@@ -0,0 +1,129 @@
+/*
+ * This file is part of esplugin
+ *
+ * Copyright (C) 2017 Oliver Hamlet
+ *
+ * esplugin is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * esplugin is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with esplugin. If not, see <http://www.gnu.org/licenses/>.
+ */
+use super::{FieldSchema, FieldType, RecordSchema, SubrecordSchema, FLTV, FNAM_GLOBAL_TYPE, MODL};
+
+// Morrowind records have no FormIDs or EditorIDs, and instead refer to each
+// other by the IDs in their NAME subrecords.
+
+const NAME: SubrecordSchema = subrecord!("NAME", "ID", [id: ZString]);
+const FNAM_NAME: SubrecordSchema = subrecord!("FNAM", "Name", [name: ZString]);
+const SCRI: SubrecordSchema = subrecord!("SCRI", "Script", [script: ZString]);
+const ENAM: SubrecordSchema = subrecord!("ENAM", "Enchantment", [enchantment: ZString]);
+const ITEX: SubrecordSchema = subrecord!("ITEX", "Icon", [icon: ZString]);
+
+const GLOB: RecordSchema = RecordSchema {
+    record_type: "GLOB",
+    subrecords: &[NAME, FNAM_GLOBAL_TYPE, FLTV],
+};
+
+const WEAP: RecordSchema = RecordSchema {
+    record_type: "WEAP",
+    subrecords: &[
+        NAME,
+        MODL,
+        FNAM_NAME,
+        subrecord!(
+            "WPDT",
+            "Weapon Data",
+            [
+                weight: F32,
+                value: U32,
+                weapon_type: U16,
+                health: U16,
+                speed: F32,
+                reach: F32,
+                enchantment_points: U16,
+                chop_min: U8,
+                chop_max: U8,
+                slash_min: U8,
+                slash_max: U8,
+                thrust_min: U8,
+                thrust_max: U8,
+                flags: Flags32,
+            ]
+        ),
+        ITEX,
+        ENAM,
+        SCRI,
+    ],
+};
+
+const ARMO: RecordSchema = RecordSchema {
+    record_type: "ARMO",
+    subrecords: &[
+        NAME,
+        MODL,
+        FNAM_NAME,
+        subrecord!(
+            "AODT",
+            "Armor Data",
+            [
+                armor_type: U32,
+                weight: F32,
+                value: U32,
+                health: U32,
+                enchantment_points: U32,
+                armor_rating: U32,
+            ]
+        ),
+        ITEX,
+        ENAM,
+        SCRI,
+    ],
+};
+
+const NPC_: RecordSchema = RecordSchema {
+    record_type: "NPC_",
+    subrecords: &[
+        NAME,
+        MODL,
+        FNAM_NAME,
+        subrecord!("RNAM", "Race", [race: ZString]),
+        subrecord!("CNAM", "Class", [class: ZString]),
+        subrecord!("ANAM", "Faction", [faction: ZString]),
+        subrecord!("BNAM", "Head Model", [head: ZString]),
+        subrecord!("KNAM", "Hair Model", [hair: ZString]),
+        SCRI,
+        subrecord!("FLAG", "Flags", [flags: Flags32]),
+    ],
+};
+
+const CELL: RecordSchema = RecordSchema {
+    record_type: "CELL",
+    subrecords: &[
+        NAME,
+        subrecord!("DATA", "Data", [flags: Flags32, grid_x: I32, grid_y: I32]),
+        subrecord!("RGNN", "Region", [region: ZString]),
+        subrecord!("WHGT", "Water Height", [height: F32]),
+    ],
+};
+
+const LEVI: RecordSchema = RecordSchema {
+    record_type: "LEVI",
+    subrecords: &[
+        NAME,
+        subrecord!("DATA", "Flags", [flags: Flags32]),
+        subrecord!("NNAM", "Chance None", [chance_none: U8]),
+        subrecord!("INDX", "Count", [count: U32]),
+        subrecord!("INAM", "Item", [item: ZString]),
+        subrecord!("INTV", "Level", [level: U16]),
+    ],
+};
+
+pub const RECORDS: &[RecordSchema] = &[GLOB, WEAP, ARMO, NPC_, CELL, LEVI];
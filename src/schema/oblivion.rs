@@ -0,0 +1,222 @@
+/*
+ * This file is part of esplugin
+ *
+ * Copyright (C) 2017 Oliver Hamlet
+ *
+ * esplugin is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * esplugin is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with esplugin. If not, see <http://www.gnu.org/licenses/>.
+ */
+use super::{
+    FieldSchema, FieldType, RecordSchema, SubrecordSchema, EDID, FLTV, FNAM_GLOBAL_TYPE, FULL,
+    MODL, REFERENCE_DATA, XESP, XOWN, XRNK, XSCL,
+};
+
+const SCRI: SubrecordSchema = subrecord!("SCRI", "Script", [script: FormId]);
+const ICON: SubrecordSchema = subrecord!("ICON", "Icon", [icon: ZString]);
+const ENAM: SubrecordSchema = subrecord!("ENAM", "Enchantment", [enchantment: FormId]);
+const ANAM: SubrecordSchema = subrecord!("ANAM", "Enchantment Points", [points: U16]);
+const XGLB: SubrecordSchema = subrecord!("XGLB", "Global", [global: FormId]);
+
+const GLOB: RecordSchema = RecordSchema {
+    record_type: "GLOB",
+    subrecords: &[EDID, FNAM_GLOBAL_TYPE, FLTV],
+};
+
+const WEAP: RecordSchema = RecordSchema {
+    record_type: "WEAP",
+    subrecords: &[
+        EDID,
+        FULL,
+        MODL,
+        subrecord!("MODB", "Bound Radius", [radius: F32]),
+        ICON,
+        SCRI,
+        ENAM,
+        ANAM,
+        subrecord!(
+            "DATA",
+            "Data",
+            [
+                weapon_type: U32,
+                speed: F32,
+                reach: F32,
+                flags: Flags32,
+                value: U32,
+                health: U32,
+                weight: F32,
+                damage: U16,
+            ]
+        ),
+    ],
+};
+
+const ARMO: RecordSchema = RecordSchema {
+    record_type: "ARMO",
+    subrecords: &[
+        EDID,
+        FULL,
+        SCRI,
+        ENAM,
+        ANAM,
+        subrecord!(
+            "BMDT",
+            "Biped Data",
+            [biped_flags: Flags16, general_flags: Flags8, unused: Padding(1)]
+        ),
+        subrecord!("MODL", "Male Biped Model", [model: ZString]),
+        subrecord!("MOD2", "Male World Model", [model: ZString]),
+        ICON,
+        subrecord!("MOD3", "Female Biped Model", [model: ZString]),
+        subrecord!("MOD4", "Female World Model", [model: ZString]),
+        subrecord!("ICO2", "Female Icon", [icon: ZString]),
+        subrecord!(
+            "DATA",
+            "Data",
+            [armor: U16, value: U32, health: U32, weight: F32]
+        ),
+    ],
+};
+
+const NPC_: RecordSchema = RecordSchema {
+    record_type: "NPC_",
+    subrecords: &[
+        EDID,
+        FULL,
+        MODL,
+        subrecord!(
+            "ACBS",
+            "Configuration",
+            [
+                flags: Flags32,
+                base_spell_points: U16,
+                fatigue: U16,
+                barter_gold: U16,
+                level: I16,
+                calc_min_level: U16,
+                calc_max_level: U16,
+            ]
+        ),
+        subrecord!(
+            "SNAM",
+            "Faction",
+            [faction: FormId, rank: U8, unused: Padding(3)]
+        ),
+        subrecord!("INAM", "Death Item", [death_item: FormId]),
+        subrecord!("RNAM", "Race", [race: FormId]),
+        subrecord!("SPLO", "Spell", [spell: FormId]),
+        SCRI,
+        subrecord!("CNTO", "Item", [item: FormId, count: I32]),
+        subrecord!("PKID", "Package", [package: FormId]),
+        subrecord!("CNAM", "Class", [class: FormId]),
+        subrecord!("HNAM", "Hair", [hair: FormId]),
+        subrecord!("LNAM", "Hair Length", [length: F32]),
+        subrecord!("ENAM", "Eyes", [eyes: FormId]),
+        subrecord!(
+            "HCLR",
+            "Hair Color",
+            [red: U8, green: U8, blue: U8, unused: Padding(1)]
+        ),
+        subrecord!("ZNAM", "Combat Style", [combat_style: FormId]),
+    ],
+};
+
+const CELL: RecordSchema = RecordSchema {
+    record_type: "CELL",
+    subrecords: &[
+        EDID,
+        FULL,
+        subrecord!("DATA", "Flags", [flags: Flags8]),
+        subrecord!("XCLC", "Grid", [x: I32, y: I32]),
+        subrecord!("XCLW", "Water Height", [height: F32]),
+        subrecord!("XCLR", "Regions", [regions: FormIdArray]),
+        subrecord!("XCMT", "Music", [music_type: U8]),
+        subrecord!("XCCM", "Climate", [climate: FormId]),
+        subrecord!("XCWT", "Water", [water: FormId]),
+        XOWN,
+        XRNK,
+        XGLB,
+    ],
+};
+
+const REFR: RecordSchema = RecordSchema {
+    record_type: "REFR",
+    subrecords: &[
+        EDID,
+        subrecord!("NAME", "Base", [base: FormId]),
+        XOWN,
+        XRNK,
+        XGLB,
+        XESP,
+        subrecord!(
+            "XTEL",
+            "Teleport Destination",
+            [
+                door: FormId,
+                position_x: F32,
+                position_y: F32,
+                position_z: F32,
+                rotation_x: F32,
+                rotation_y: F32,
+                rotation_z: F32,
+            ]
+        ),
+        subrecord!(
+            "XLOC",
+            "Lock Data",
+            [level: U8, unused: Padding(3), key: FormId]
+        ),
+        XSCL,
+        REFERENCE_DATA,
+    ],
+};
+
+const LVLI: RecordSchema = RecordSchema {
+    record_type: "LVLI",
+    subrecords: &[
+        EDID,
+        subrecord!("LVLD", "Chance None", [chance_none: U8]),
+        subrecord!("LVLF", "Flags", [flags: Flags8]),
+        subrecord!(
+            "LVLO",
+            "Entry",
+            [
+                level: I16,
+                unused: Padding(2),
+                reference: FormId,
+                count: I16,
+                unused: Padding(2),
+            ]
+        ),
+    ],
+};
+
+const QUST: RecordSchema = RecordSchema {
+    record_type: "QUST",
+    subrecords: &[
+        EDID,
+        SCRI,
+        FULL,
+        ICON,
+        subrecord!("DATA", "General", [flags: Flags8, priority: U8]),
+        subrecord!("INDX", "Stage Index", [journal_index: U16]),
+        subrecord!("QSDT", "Stage Flags", [flags: Flags8]),
+        subrecord!("CNAM", "Log Entry", [text: ZString]),
+        subrecord!(
+            "QSTA",
+            "Target",
+            [target: FormId, flags: Flags8, unused: Padding(3)]
+        ),
+    ],
+};
+
+pub const RECORDS: &[RecordSchema] = &[GLOB, WEAP, ARMO, NPC_, CELL, REFR, LVLI, QUST];
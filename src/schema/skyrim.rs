@@ -0,0 +1,361 @@
+/*
+ * This file is part of esplugin
+ *
+ * Copyright (C) 2017 Oliver Hamlet
+ *
+ * esplugin is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * esplugin is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with esplugin. If not, see <http://www.gnu.org/licenses/>.
+ */
+use super::{
+    FieldSchema, FieldType, RecordSchema, SubrecordSchema, EDID, FLTV, FNAM_GLOBAL_TYPE, FULL,
+    KSIZ, KWDA, MODL, OBND, REFERENCE_DATA, XESP, XOWN, XRNK, XSCL,
+};
+
+const DESC: SubrecordSchema = subrecord!("DESC", "Description", [description: LString]);
+const EITM: SubrecordSchema = subrecord!("EITM", "Object Effect", [enchantment: FormId]);
+const EAMT: SubrecordSchema = subrecord!("EAMT", "Enchantment Amount", [amount: U16]);
+const ETYP: SubrecordSchema = subrecord!("ETYP", "Equipment Type", [equipment_type: FormId]);
+const BIDS: SubrecordSchema = subrecord!("BIDS", "Block Bash Impact", [impact_data_set: FormId]);
+const BAMT: SubrecordSchema = subrecord!("BAMT", "Alternate Block Material", [material: FormId]);
+const YNAM: SubrecordSchema = subrecord!("YNAM", "Pickup Sound", [sound: FormId]);
+const ZNAM: SubrecordSchema = subrecord!("ZNAM", "Putdown Sound", [sound: FormId]);
+
+const GLOB: RecordSchema = RecordSchema {
+    record_type: "GLOB",
+    subrecords: &[EDID, FNAM_GLOBAL_TYPE, FLTV],
+};
+
+const KYWD: RecordSchema = RecordSchema {
+    record_type: "KYWD",
+    subrecords: &[
+        EDID,
+        subrecord!(
+            "CNAM",
+            "Color",
+            [red: U8, green: U8, blue: U8, unused: Padding(1)]
+        ),
+    ],
+};
+
+const WEAP: RecordSchema = RecordSchema {
+    record_type: "WEAP",
+    subrecords: &[
+        EDID,
+        OBND,
+        FULL,
+        MODL,
+        EITM,
+        EAMT,
+        ETYP,
+        BIDS,
+        BAMT,
+        YNAM,
+        ZNAM,
+        KSIZ,
+        KWDA,
+        DESC,
+        subrecord!("INAM", "Impact Data Set", [impact_data_set: FormId]),
+        subrecord!("WNAM", "First Person Model", [model: FormId]),
+        subrecord!("SNAM", "Attack Sound", [sound: FormId]),
+        subrecord!("XNAM", "Attack Sound 2D", [sound: FormId]),
+        subrecord!("NAM7", "Attack Loop Sound", [sound: FormId]),
+        subrecord!("TNAM", "Attack Fail Sound", [sound: FormId]),
+        subrecord!("UNAM", "Idle Sound", [sound: FormId]),
+        subrecord!("NAM9", "Equip Sound", [sound: FormId]),
+        subrecord!("NAM8", "Unequip Sound", [sound: FormId]),
+        subrecord!("DATA", "Game Data", [value: U32, weight: F32, damage: U16]),
+        subrecord!(
+            "DNAM",
+            "Data",
+            [
+                animation_type: U8,
+                unused: Padding(3),
+                speed: F32,
+                reach: F32,
+                flags: Flags16,
+                unused: Padding(2),
+                sight_fov: F32,
+                unknown: Padding(4),
+                base_vats_to_hit_chance: U8,
+                attack_animation: U8,
+                projectile_count: U8,
+                embedded_weapon_actor_value: U8,
+                minimum_range: F32,
+                maximum_range: F32,
+                on_hit: U32,
+                flags2: Flags32,
+                animation_attack_multiplier: F32,
+            ]
+        ),
+        subrecord!(
+            "CRDT",
+            "Critical Data",
+            [
+                damage: U16,
+                unused: Padding(2),
+                multiplier: F32,
+                flags: Flags8,
+                unused: Padding(3),
+                spell_effect: FormId,
+            ]
+        ),
+        subrecord!("VNAM", "Detection Sound Level", [sound_level: U32]),
+        subrecord!("CNAM", "Template", [template: FormId]),
+    ],
+};
+
+// Skyrim SE pads the critical data struct to 8-byte alignment.
+const WEAP_SE: RecordSchema = RecordSchema {
+    record_type: "WEAP",
+    subrecords: &[subrecord!(
+        "CRDT",
+        "Critical Data",
+        [
+            damage: U16,
+            unused: Padding(2),
+            multiplier: F32,
+            flags: Flags8,
+            unused: Padding(7),
+            spell_effect: FormId,
+            unused: Padding(4),
+        ]
+    )],
+};
+
+const ARMO: RecordSchema = RecordSchema {
+    record_type: "ARMO",
+    subrecords: &[
+        EDID,
+        OBND,
+        FULL,
+        EITM,
+        EAMT,
+        subrecord!("MOD2", "Male World Model", [model: ZString]),
+        subrecord!("MOD4", "Female World Model", [model: ZString]),
+        subrecord!(
+            "BODT",
+            "Body Template",
+            [
+                body_slots: Flags32,
+                flags: Flags8,
+                unused: Padding(3),
+                armor_type: U32,
+            ]
+        ),
+        subrecord!("BOD2", "Body Template", [body_slots: Flags32, armor_type: U32]),
+        ETYP,
+        BIDS,
+        BAMT,
+        YNAM,
+        ZNAM,
+        subrecord!("RNAM", "Race", [race: FormId]),
+        KSIZ,
+        KWDA,
+        DESC,
+        subrecord!("MODL", "Armature", [armature: FormId]),
+        subrecord!("DATA", "Data", [value: I32, weight: F32]),
+        subrecord!("DNAM", "Armor Rating", [armor_rating: U32]),
+        subrecord!("TNAM", "Template", [template: FormId]),
+    ],
+};
+
+const NPC_: RecordSchema = RecordSchema {
+    record_type: "NPC_",
+    subrecords: &[
+        EDID,
+        OBND,
+        subrecord!(
+            "ACBS",
+            "Configuration",
+            [
+                flags: Flags32,
+                magicka_offset: I16,
+                stamina_offset: I16,
+                level: U16,
+                calc_min_level: U16,
+                calc_max_level: U16,
+                speed_multiplier: U16,
+                disposition_base: I16,
+                template_flags: Flags16,
+                health_offset: I16,
+                bleedout_override: U16,
+            ]
+        ),
+        subrecord!(
+            "SNAM",
+            "Faction",
+            [faction: FormId, rank: I8, unused: Padding(3)]
+        ),
+        subrecord!("INAM", "Death Item", [death_item: FormId]),
+        subrecord!("VTCK", "Voice", [voice_type: FormId]),
+        subrecord!("TPLT", "Template", [template: FormId]),
+        subrecord!("RNAM", "Race", [race: FormId]),
+        subrecord!("SPCT", "Spell Count", [count: U32]),
+        subrecord!("SPLO", "Spell", [spell: FormId]),
+        subrecord!("DOFT", "Default Outfit", [outfit: FormId]),
+        subrecord!("SOFT", "Sleeping Outfit", [outfit: FormId]),
+        subrecord!("CRIF", "Crime Faction", [faction: FormId]),
+        KSIZ,
+        KWDA,
+        subrecord!("CNAM", "Class", [class: FormId]),
+        FULL,
+        subrecord!("SHRT", "Short Name", [name: LString]),
+        subrecord!("PNAM", "Head Part", [head_part: FormId]),
+        subrecord!("HCLF", "Hair Color", [color: FormId]),
+        subrecord!("ZNAM", "Combat Style", [combat_style: FormId]),
+        subrecord!("GNAM", "Gift Filter", [filter: FormId]),
+        subrecord!("NAM6", "Height", [height: F32]),
+        subrecord!("NAM7", "Weight", [weight: F32]),
+        subrecord!("NAM8", "Sound Level", [sound_level: U32]),
+        subrecord!("WNAM", "Worn Armor", [armor: FormId]),
+        subrecord!("ATKR", "Attack Race", [race: FormId]),
+        subrecord!("CNTO", "Item", [item: FormId, count: I32]),
+        subrecord!("PKID", "Package", [package: FormId]),
+        subrecord!("FTST", "Head Texture", [texture_set: FormId]),
+    ],
+};
+
+const CELL: RecordSchema = RecordSchema {
+    record_type: "CELL",
+    subrecords: &[
+        EDID,
+        FULL,
+        subrecord!("DATA", "Flags", [flags: Flags16]),
+        subrecord!("XCLC", "Grid", [x: I32, y: I32, flags: Flags32]),
+        subrecord!("LTMP", "Lighting Template", [template: FormId]),
+        subrecord!("XCLW", "Water Height", [height: F32]),
+        subrecord!("XCLR", "Regions", [regions: FormIdArray]),
+        subrecord!("XLCN", "Location", [location: FormId]),
+        subrecord!("XCWT", "Water", [water: FormId]),
+        subrecord!("XCCM", "Sky/Weather from Region", [region: FormId]),
+        subrecord!("XCAS", "Acoustic Space", [acoustic_space: FormId]),
+        subrecord!("XEZN", "Encounter Zone", [encounter_zone: FormId]),
+        subrecord!("XCMO", "Music Type", [music_type: FormId]),
+        subrecord!("XCIM", "Image Space", [image_space: FormId]),
+        XOWN,
+        XRNK,
+    ],
+};
+
+const REFR: RecordSchema = RecordSchema {
+    record_type: "REFR",
+    subrecords: &[
+        EDID,
+        subrecord!("NAME", "Base", [base: FormId]),
+        XSCL,
+        XOWN,
+        XRNK,
+        subrecord!(
+            "XLKR",
+            "Linked Reference",
+            [keyword: FormId, reference: FormId]
+        ),
+        XESP,
+        subrecord!(
+            "XTEL",
+            "Teleport Destination",
+            [
+                door: FormId,
+                position_x: F32,
+                position_y: F32,
+                position_z: F32,
+                rotation_x: F32,
+                rotation_y: F32,
+                rotation_z: F32,
+                flags: Flags32,
+            ]
+        ),
+        subrecord!(
+            "XLOC",
+            "Lock Data",
+            [level: U8, unused: Padding(3), key: FormId, flags: Flags8]
+        ),
+        subrecord!("XEZN", "Encounter Zone", [encounter_zone: FormId]),
+        subrecord!(
+            "XLRT",
+            "Location Reference Type",
+            [location_ref_types: FormIdArray]
+        ),
+        subrecord!("XCNT", "Item Count", [count: I32]),
+        subrecord!(
+            "XNDP",
+            "Navigation Door Link",
+            [navmesh: FormId, triangle: U16, unused: Padding(2)]
+        ),
+        REFERENCE_DATA,
+    ],
+};
+
+const LVLI: RecordSchema = RecordSchema {
+    record_type: "LVLI",
+    subrecords: &[
+        EDID,
+        OBND,
+        subrecord!("LVLD", "Chance None", [chance_none: U8]),
+        subrecord!("LVLF", "Flags", [flags: Flags8]),
+        subrecord!("LVLG", "Global", [global: FormId]),
+        subrecord!("LLCT", "Count", [count: U8]),
+        subrecord!(
+            "LVLO",
+            "Entry",
+            [
+                level: U16,
+                unused: Padding(2),
+                reference: FormId,
+                count: U16,
+                unused: Padding(2),
+            ]
+        ),
+        subrecord!(
+            "COED",
+            "Extra Data",
+            [owner: FormId, global_or_rank: U32, item_condition: F32]
+        ),
+    ],
+};
+
+const QUST: RecordSchema = RecordSchema {
+    record_type: "QUST",
+    subrecords: &[
+        EDID,
+        FULL,
+        subrecord!(
+            "DNAM",
+            "General",
+            [
+                flags: Flags16,
+                priority: U8,
+                form_version: U8,
+                unknown: Padding(4),
+                quest_type: U32,
+            ]
+        ),
+        subrecord!("QTGL", "Text Display Global", [global: FormId]),
+        subrecord!("FLTR", "Object Window Filter", [filter: ZString]),
+        subrecord!(
+            "INDX",
+            "Stage",
+            [journal_index: U16, flags: Flags8, unused: Padding(1)]
+        ),
+        subrecord!("QOBJ", "Objective Index", [index: U16]),
+        subrecord!("NNAM", "Objective Display Text", [text: LString]),
+        subrecord!("ANAM", "Next Alias ID", [alias_id: U32]),
+        subrecord!("ALST", "Reference Alias ID", [alias_id: U32]),
+        subrecord!("ALLS", "Location Alias ID", [alias_id: U32]),
+        subrecord!("ALID", "Alias Name", [name: ZString]),
+    ],
+};
+
+pub const RECORDS: &[RecordSchema] = &[GLOB, KYWD, WEAP, ARMO, NPC_, CELL, REFR, LVLI, QUST];
+
+pub const SE_RECORDS: &[RecordSchema] = &[WEAP_SE];
@@ -0,0 +1,472 @@
+/*
+ * This file is part of esplugin
+ *
+ * Copyright (C) 2017 Oliver Hamlet
+ *
+ * esplugin is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * esplugin is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with esplugin. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+// Typed structs for the record types that the schemas cover. Each field
+// lists the subrecord fields it can be read from, as the layouts differ
+// between games, and is None (or empty) if none of them are present. The
+// tests check that every listed subrecord field exists in a schema.
+
+use super::{FieldValue, TypedRecord, TypedSubrecord};
+
+// Builds a struct from a record, e.g.
+// record_view!(Global: ["GLOB"] { value: Option<f32> = float[("FLTV", "value")], });
+// Repeated subrecords can be collected into a list of subrecord views by
+// giving them in an entries block after the fields.
+macro_rules! record_view {
+    (
+        $name:ident: [$($record_type:expr),+] {
+            $($field:ident: $field_type:ty = $decode:ident[$(($subrecord_type:expr, $field_name:expr)),+],)*
+        }
+        $(entries {
+            $($entries_field:ident: $entry_type:ident = $entries_subrecord_type:expr,)*
+        })*
+    ) => {
+        #[derive(Clone, PartialEq, Debug)]
+        pub struct $name {
+            pub form_id: u32,
+            pub editor_id: Option<String>,
+            $(pub $field: $field_type,)*
+            $($(pub $entries_field: Vec<$entry_type>,)*)*
+        }
+
+        impl $name {
+            const RECORD_TYPES: &'static [&'static str] = &[$($record_type),+];
+
+            #[cfg(test)]
+            const FIELDS: &'static [&'static [(&'static str, &'static str)]] =
+                &[$(&[$(($subrecord_type, $field_name)),+]),*];
+
+            #[cfg(test)]
+            const ENTRIES: &'static [(&'static str, &'static [&'static str])] =
+                &[$($(($entries_subrecord_type, $entry_type::FIELD_NAMES)),*)*];
+
+            // Returns None if the record isn't of a type that this struct
+            // covers.
+            pub fn from_typed_record(record: &TypedRecord) -> Option<$name> {
+                if !$name::RECORD_TYPES.contains(&record.record_type()) {
+                    return None;
+                }
+
+                Some($name {
+                    form_id: record.form_id(),
+                    editor_id: editor_id(record),
+                    $($field: $decode(record_values(
+                        record,
+                        &[$(($subrecord_type, $field_name)),+],
+                    )),)*
+                    $($($entries_field: record
+                        .subrecords()
+                        .iter()
+                        .filter(|s| s.subrecord_type() == $entries_subrecord_type)
+                        .map($entry_type::from_typed_subrecord)
+                        .collect(),)*)*
+                })
+            }
+        }
+    };
+}
+
+// Builds a struct from one of a record's subrecords.
+macro_rules! subrecord_view {
+    (
+        $name:ident {
+            $($field:ident: $field_type:ty = $decode:ident($field_name:expr),)*
+        }
+    ) => {
+        #[derive(Clone, PartialEq, Debug)]
+        pub struct $name {
+            $(pub $field: $field_type,)*
+        }
+
+        impl $name {
+            #[cfg(test)]
+            const FIELD_NAMES: &'static [&'static str] = &[$($field_name),*];
+
+            fn from_typed_subrecord(subrecord: &TypedSubrecord) -> $name {
+                $name {
+                    $($field: $decode(subrecord.field($field_name).into_iter()),)*
+                }
+            }
+        }
+    };
+}
+
+// A string that is stored in a string table in localized plugins.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum Text {
+    String(String),
+    StringId(u32),
+}
+
+record_view!(Global: ["GLOB"] {
+    value_type: Option<i64> = integer[("FNAM", "value_type")],
+    value: Option<f32> = float[("FLTV", "value")],
+});
+
+record_view!(Keyword: ["KYWD"] {
+    name: Option<Text> = text[("FULL", "name")],
+    red: Option<i64> = integer[("CNAM", "red")],
+    green: Option<i64> = integer[("CNAM", "green")],
+    blue: Option<i64> = integer[("CNAM", "blue")],
+    notes: Option<String> = string[("DNAM", "notes")],
+});
+
+record_view!(Weapon: ["WEAP"] {
+    name: Option<Text> = text[("FULL", "name"), ("FNAM", "name")],
+    value: Option<i64> = integer[("DATA", "value"), ("WPDT", "value")],
+    weight: Option<f32> = float[("DATA", "weight"), ("WPDT", "weight")],
+    damage: Option<i64> = integer[("DATA", "damage"), ("DATA", "base_damage")],
+    speed: Option<f32> = float[("DNAM", "speed"), ("DATA", "speed"), ("WPDT", "speed")],
+    reach: Option<f32> = float[("DNAM", "reach"), ("DATA", "reach"), ("WPDT", "reach")],
+    enchantment: Option<u32> = form_id[("EITM", "enchantment"), ("ENAM", "enchantment")],
+    keywords: Vec<u32> = form_ids[("KWDA", "keywords")],
+});
+
+record_view!(Armor: ["ARMO"] {
+    name: Option<Text> = text[("FULL", "name"), ("FNAM", "name")],
+    value: Option<i64> = integer[("DATA", "value"), ("AODT", "value")],
+    weight: Option<f32> = float[("DATA", "weight"), ("AODT", "weight")],
+    armor_rating: Option<i64> = integer[
+        ("DNAM", "armor_rating"),
+        ("FNAM", "armor_rating"),
+        ("AODT", "armor_rating"),
+        ("DATA", "armor")
+    ],
+    enchantment: Option<u32> = form_id[("EITM", "enchantment"), ("ENAM", "enchantment")],
+    race: Option<u32> = form_id[("RNAM", "race")],
+    keywords: Vec<u32> = form_ids[("KWDA", "keywords")],
+});
+
+subrecord_view!(FactionRank {
+    faction: Option<u32> = form_id("faction"),
+    rank: Option<i64> = integer("rank"),
+});
+
+subrecord_view!(ItemCount {
+    item: Option<u32> = form_id("item"),
+    count: Option<i64> = integer("count"),
+});
+
+record_view!(Npc: ["NPC_"] {
+    name: Option<Text> = text[("FULL", "name"), ("FNAM", "name")],
+    flags: Option<u32> = flags[("ACBS", "flags"), ("FLAG", "flags")],
+    level: Option<i64> = integer[("ACBS", "level")],
+    race: Option<u32> = form_id[("RNAM", "race")],
+    class: Option<u32> = form_id[("CNAM", "class")],
+    spells: Vec<u32> = form_ids[("SPLO", "spell"), ("SPLO", "effect")],
+    packages: Vec<u32> = form_ids[("PKID", "package")],
+    keywords: Vec<u32> = form_ids[("KWDA", "keywords")],
+}
+entries {
+    factions: FactionRank = "SNAM",
+    items: ItemCount = "CNTO",
+});
+
+record_view!(Cell: ["CELL"] {
+    name: Option<Text> = text[("FULL", "name")],
+    flags: Option<u32> = flags[("DATA", "flags")],
+    grid_x: Option<i64> = integer[("XCLC", "x"), ("DATA", "grid_x")],
+    grid_y: Option<i64> = integer[("XCLC", "y"), ("DATA", "grid_y")],
+    water_height: Option<f32> = float[("XCLW", "height"), ("WHGT", "height")],
+    owner: Option<u32> = form_id[("XOWN", "owner")],
+    regions: Vec<u32> = form_ids[("XCLR", "regions")],
+});
+
+record_view!(Reference: ["REFR"] {
+    base: Option<u32> = form_id[("NAME", "base")],
+    scale: Option<f32> = float[("XSCL", "scale")],
+    owner: Option<u32> = form_id[("XOWN", "owner")],
+    enable_parent: Option<u32> = form_id[("XESP", "parent")],
+    position_x: Option<f32> = float[("DATA", "position_x")],
+    position_y: Option<f32> = float[("DATA", "position_y")],
+    position_z: Option<f32> = float[("DATA", "position_z")],
+    rotation_x: Option<f32> = float[("DATA", "rotation_x")],
+    rotation_y: Option<f32> = float[("DATA", "rotation_y")],
+    rotation_z: Option<f32> = float[("DATA", "rotation_z")],
+    count: Option<i64> = integer[("XCNT", "count")],
+    teleport_door: Option<u32> = form_id[("XTEL", "door")],
+    lock_level: Option<i64> = integer[("XLOC", "level")],
+    key: Option<u32> = form_id[("XLOC", "key")],
+});
+
+subrecord_view!(LeveledEntry {
+    level: Option<i64> = integer("level"),
+    reference: Option<u32> = form_id("reference"),
+    count: Option<i64> = integer("count"),
+});
+
+// Morrowind stores each leveled list entry as a pair of INAM and INTV
+// subrecords, so its entries are left out.
+record_view!(LeveledItem: ["LVLI", "LEVI"] {
+    chance_none: Option<i64> = integer[("LVLD", "chance_none"), ("NNAM", "chance_none")],
+    flags: Option<u32> = flags[("LVLF", "flags"), ("DATA", "flags")],
+    global: Option<u32> = form_id[("LVLG", "global")],
+}
+entries {
+    entries: LeveledEntry = "LVLO",
+});
+
+record_view!(Quest: ["QUST"] {
+    name: Option<Text> = text[("FULL", "name")],
+    flags: Option<u32> = flags[("DNAM", "flags"), ("DATA", "flags")],
+    priority: Option<i64> = integer[("DNAM", "priority"), ("DATA", "priority")],
+    script: Option<u32> = form_id[("SCRI", "script")],
+});
+
+// Morrowind records have no EditorIDs, so use their NAME IDs instead.
+fn editor_id(record: &TypedRecord) -> Option<String> {
+    record
+        .editor_id()
+        .or_else(|| record.field("NAME", "id").and_then(|v| v.as_str()))
+        .map(|s| s.to_string())
+}
+
+// Returns the values of the given fields, in the order given, from every
+// subrecord of each field's subrecord type.
+fn record_values<'a>(
+    record: &'a TypedRecord,
+    keys: &'a [(&'static str, &'static str)],
+) -> impl Iterator<Item = &'a FieldValue> {
+    keys.iter().flat_map(move |&(subrecord_type, field_name)| {
+        record
+            .subrecords()
+            .iter()
+            .filter(move |s| s.subrecord_type() == subrecord_type)
+            .filter_map(move |s| s.field(field_name))
+    })
+}
+
+fn integer<'a, I: Iterator<Item = &'a FieldValue>>(mut values: I) -> Option<i64> {
+    values.find_map(|v| v.as_i64())
+}
+
+fn flags<'a, I: Iterator<Item = &'a FieldValue>>(mut values: I) -> Option<u32> {
+    values.find_map(|v| v.as_u32())
+}
+
+fn float<'a, I: Iterator<Item = &'a FieldValue>>(mut values: I) -> Option<f32> {
+    values.find_map(|v| v.as_f32())
+}
+
+fn form_id<'a, I: Iterator<Item = &'a FieldValue>>(mut values: I) -> Option<u32> {
+    values.find_map(|v| v.as_form_id())
+}
+
+fn string<'a, I: Iterator<Item = &'a FieldValue>>(mut values: I) -> Option<String> {
+    values.find_map(|v| v.as_str()).map(|s| s.to_string())
+}
+
+fn text<'a, I: Iterator<Item = &'a FieldValue>>(mut values: I) -> Option<Text> {
+    values.find_map(|v| match *v {
+        FieldValue::String(ref x) => Some(Text::String(x.clone())),
+        FieldValue::LocalizedString(x) => Some(Text::StringId(x)),
+        _ => None,
+    })
+}
+
+fn form_ids<'a, I: Iterator<Item = &'a FieldValue>>(values: I) -> Vec<u32> {
+    values
+        .flat_map(|v| match *v {
+            FieldValue::FormId(x) => vec![x],
+            FieldValue::FormIds(ref x) => x.clone(),
+            _ => Vec::new(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::subrecord_schema;
+    use super::*;
+
+    use builder::RecordBuilder;
+    use game_id::GameId;
+
+    const GAMES: &[GameId] = &[
+        GameId::Morrowind,
+        GameId::Oblivion,
+        GameId::Fallout3,
+        GameId::FalloutNV,
+        GameId::Skyrim,
+        GameId::SkyrimSE,
+        GameId::Fallout4,
+    ];
+
+    fn is_in_a_schema(record_types: &[&str], subrecord_type: &str, field_name: &str) -> bool {
+        GAMES.iter().any(|g| {
+            record_types.iter().any(|r| {
+                subrecord_schema(*g, r, subrecord_type)
+                    .map(|s| s.fields.iter().any(|f| f.name == field_name))
+                    .unwrap_or(false)
+            })
+        })
+    }
+
+    fn assert_fields_are_in_a_schema(
+        record_types: &[&str],
+        fields: &[&[(&str, &str)]],
+        entries: &[(&str, &[&str])],
+    ) {
+        for &(subrecord_type, field_name) in fields.iter().flat_map(|f| f.iter()) {
+            assert!(
+                is_in_a_schema(record_types, subrecord_type, field_name),
+                "{:?} {} {}",
+                record_types,
+                subrecord_type,
+                field_name
+            );
+        }
+
+        for &(subrecord_type, field_names) in entries {
+            for field_name in field_names {
+                assert!(
+                    is_in_a_schema(record_types, subrecord_type, field_name),
+                    "{:?} {} {}",
+                    record_types,
+                    subrecord_type,
+                    field_name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn every_field_should_be_read_from_subrecord_fields_that_are_in_a_schema() {
+        macro_rules! check {
+            ($($name:ident),*) => {
+                $(assert_fields_are_in_a_schema(
+                    $name::RECORD_TYPES,
+                    $name::FIELDS,
+                    $name::ENTRIES,
+                );)*
+            };
+        }
+
+        check!(
+            Global,
+            Keyword,
+            Weapon,
+            Armor,
+            Npc,
+            Cell,
+            Reference,
+            LeveledItem,
+            Quest
+        );
+    }
+
+    fn decode(record: RecordBuilder, game_id: GameId) -> TypedRecord {
+        TypedRecord::decode(&record.build(game_id), game_id, false)
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn from_typed_record_should_return_none_for_a_record_of_another_type() {
+        let record = decode(RecordBuilder::new("GLOB", 0x800), GameId::Skyrim);
+
+        assert!(Weapon::from_typed_record(&record).is_none());
+        assert!(Global::from_typed_record(&record).is_some());
+    }
+
+    #[test]
+    fn from_typed_record_should_decode_fields_from_the_subrecords_that_are_present() {
+        let record = decode(
+            RecordBuilder::new("WEAP", 0x800)
+                .with_editor_id("IronSword", GameId::Skyrim)
+                .with_subrecord("FULL", b"Iron Sword\0")
+                .with_subrecord("KWDA", b"\x01\x00\x00\x00\x02\x00\x00\x00")
+                .with_subrecord("DATA", b"\x19\x00\x00\x00\x00\x00\x10\x41\x07\x00"),
+            GameId::Skyrim,
+        );
+        let weapon = Weapon::from_typed_record(&record).unwrap();
+
+        assert_eq!(0x800, weapon.form_id);
+        assert_eq!(Some("IronSword".to_string()), weapon.editor_id);
+        assert_eq!(Some(Text::String("Iron Sword".to_string())), weapon.name);
+        assert_eq!(Some(25), weapon.value);
+        assert_eq!(Some(9.0), weapon.weight);
+        assert_eq!(Some(7), weapon.damage);
+        assert_eq!(None, weapon.speed);
+        assert_eq!(vec![1, 2], weapon.keywords);
+    }
+
+    #[test]
+    fn from_typed_record_should_use_the_field_layout_of_the_records_game() {
+        let record = decode(
+            RecordBuilder::new("WEAP", 0)
+                .with_subrecord("NAME", b"iron sword\0")
+                .with_subrecord(
+                    "WPDT",
+                    b"\x00\x00\x10\x41\x19\x00\x00\x00\x01\x00\x02\x00\x00\x00\x80\x3F",
+                ),
+            GameId::Morrowind,
+        );
+        let weapon = Weapon::from_typed_record(&record).unwrap();
+
+        assert_eq!(Some("iron sword".to_string()), weapon.editor_id);
+        assert_eq!(Some(25), weapon.value);
+        assert_eq!(Some(9.0), weapon.weight);
+        assert_eq!(Some(1.0), weapon.speed);
+    }
+
+    #[test]
+    fn from_typed_record_should_collect_repeated_subrecords() {
+        let record = decode(
+            RecordBuilder::new("LVLI", 0x800)
+                .with_subrecord("LVLD", b"\x0A")
+                .with_subrecord("LVLO", b"\x01\x00\x00\x00\x14\x00\x00\x00\x02\x00\x00\x00")
+                .with_subrecord("LVLO", b"\x05\x00\x00\x00\x15\x00\x00\x00\x01\x00\x00\x00"),
+            GameId::Skyrim,
+        );
+        let leveled_item = LeveledItem::from_typed_record(&record).unwrap();
+
+        assert_eq!(Some(10), leveled_item.chance_none);
+        assert_eq!(
+            vec![
+                LeveledEntry {
+                    level: Some(1),
+                    reference: Some(0x14),
+                    count: Some(2),
+                },
+                LeveledEntry {
+                    level: Some(5),
+                    reference: Some(0x15),
+                    count: Some(1),
+                },
+            ],
+            leveled_item.entries
+        );
+    }
+
+    #[test]
+    fn from_typed_record_should_decode_lstrings_as_string_ids_in_localized_plugins() {
+        let record = RecordBuilder::new("KYWD", 0x800)
+            .with_subrecord("FULL", b"\x01\x02\x00\x00")
+            .build(GameId::Fallout4);
+        let record = TypedRecord::decode(&record, GameId::Fallout4, true)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            Some(Text::StringId(0x201)),
+            Keyword::from_typed_record(&record).unwrap().name
+        );
+    }
+}
@@ -19,12 +19,13 @@
 
 extern crate nom;
 
-#[cfg(feature = "compressed-fields")]
 use std::io;
 #[cfg(feature = "compressed-fields")]
 use std::io::Read;
 use std::str;
 
+use byteorder::{LittleEndian, WriteBytesExt};
+
 #[cfg(feature = "compressed-fields")]
 use flate2::read::DeflateDecoder;
 
@@ -37,6 +38,7 @@ use game_id::GameId;
 const SUBRECORD_TYPE_LENGTH: u8 = 4;
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Subrecord {
     subrecord_type: String,
     data: Vec<u8>,
@@ -59,6 +61,14 @@ impl Subrecord {
         }
     }
 
+    pub(crate) fn from_data(subrecord_type: &str, data: Vec<u8>) -> Subrecord {
+        Subrecord {
+            subrecord_type: subrecord_type.to_string(),
+            data,
+            is_compressed: false,
+        }
+    }
+
     #[cfg(feature = "compressed-fields")]
     pub fn decompress_data(&self) -> Result<Vec<u8>, io::Error> {
         if !self.is_compressed {
@@ -79,6 +89,37 @@ impl Subrecord {
         Ok(decompressed_data)
     }
 
+    // Data that is too large for its size to fit in a u16 is preceded by an
+    // XXXX subrecord that holds its size instead.
+    pub fn write<T: io::Write>(&self, writer: &mut T, game_id: GameId) -> Result<(), io::Error> {
+        if game_id == GameId::Morrowind {
+            writer.write_all(self.subrecord_type.as_bytes())?;
+            writer.write_u32::<LittleEndian>(self.data.len() as u32)?;
+        } else if self.data.len() > usize::from(u16::MAX) {
+            writer.write_all(b"XXXX")?;
+            writer.write_u16::<LittleEndian>(4)?;
+            writer.write_u32::<LittleEndian>(self.data.len() as u32)?;
+            writer.write_all(self.subrecord_type.as_bytes())?;
+            writer.write_u16::<LittleEndian>(0)?;
+        } else {
+            writer.write_all(self.subrecord_type.as_bytes())?;
+            writer.write_u16::<LittleEndian>(self.data.len() as u16)?;
+        }
+
+        writer.write_all(&self.data)
+    }
+
+    // The number of bytes that write() writes.
+    pub(crate) fn written_size(&self, game_id: GameId) -> usize {
+        if game_id == GameId::Morrowind {
+            8 + self.data.len()
+        } else if self.data.len() > usize::from(u16::MAX) {
+            16 + self.data.len()
+        } else {
+            6 + self.data.len()
+        }
+    }
+
     pub fn subrecord_type(&self) -> &str {
         &self.subrecord_type
     }
@@ -86,6 +127,10 @@ impl Subrecord {
     pub fn data(&self) -> &[u8] {
         &self.data
     }
+
+    pub(crate) fn data_mut(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
 }
 
 named!(subrecord_type<&str>, take_str!(SUBRECORD_TYPE_LENGTH));
@@ -223,6 +268,75 @@ mod tests {
         assert_eq!(vec![0x6D, 0x63, 0x61, 0x72], subrecord.data);
     }
 
+    #[test]
+    fn write_should_write_a_morrowind_subrecord_correctly() {
+        let subrecord = Subrecord::new(TES3_DATA_SUBRECORD, GameId::Morrowind, 0, false)
+            .unwrap()
+            .1;
+
+        let mut bytes = Vec::new();
+        subrecord.write(&mut bytes, GameId::Morrowind).unwrap();
+
+        assert_eq!(TES3_DATA_SUBRECORD, bytes.as_slice());
+    }
+
+    #[test]
+    fn write_should_write_a_non_morrowind_subrecord_correctly() {
+        let subrecord = Subrecord::new(TES4_CNAM_SUBRECORD, GameId::Skyrim, 0, false)
+            .unwrap()
+            .1;
+
+        let mut bytes = Vec::new();
+        subrecord.write(&mut bytes, GameId::Skyrim).unwrap();
+
+        assert_eq!(TES4_CNAM_SUBRECORD, bytes.as_slice());
+    }
+
+    #[test]
+    fn write_should_precede_a_subrecord_with_more_than_65535_bytes_of_data_with_an_xxxx_subrecord(
+) {
+        let subrecord = Subrecord {
+            subrecord_type: "ONAM".to_string(),
+            data: vec![0; 0x10000],
+            is_compressed: false,
+        };
+
+        let mut bytes = Vec::new();
+        subrecord.write(&mut bytes, GameId::Skyrim).unwrap();
+
+        assert_eq!(0x10010, bytes.len());
+        assert_eq!(b"XXXX\x04\x00\x00\x00\x01\x00ONAM\x00\x00", &bytes[..16]);
+
+        let data_length = parse_subrecord_data_as_u32(&bytes).unwrap().1;
+        let parsed_subrecord = Subrecord::new(&bytes[10..], GameId::Skyrim, data_length, false)
+            .unwrap()
+            .1;
+
+        assert_eq!(subrecord, parsed_subrecord);
+    }
+
+    #[test]
+    fn written_size_should_equal_the_number_of_bytes_written() {
+        let subrecord = Subrecord::new(TES4_CNAM_SUBRECORD, GameId::Skyrim, 0, false)
+            .unwrap()
+            .1;
+
+        for game_id in &[GameId::Morrowind, GameId::Skyrim] {
+            let mut bytes = Vec::new();
+            subrecord.write(&mut bytes, *game_id).unwrap();
+
+            assert_eq!(bytes.len(), subrecord.written_size(*game_id));
+        }
+
+        let subrecord = Subrecord {
+            subrecord_type: "ONAM".to_string(),
+            data: vec![0; 0x10000],
+            is_compressed: false,
+        };
+
+        assert_eq!(0x10010, subrecord.written_size(GameId::Skyrim));
+    }
+
     #[test]
     #[cfg(feature = "compressed-fields")]
     fn decompress_data_should_read_a_compressed_subrecord_correctly() {
@@ -0,0 +1,565 @@
+/*
+ * This file is part of esplugin
+ *
+ * Copyright (C) 2017 Oliver Hamlet
+ *
+ * esplugin is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * esplugin is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with esplugin. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+// A line-based text format for plugins. Each group and record is written on
+// its own line, followed by its contents indented by two spaces:
+//
+//     TES4 flags=00000001 form_id=00000000 vc=00000000 version=44 unknown=0
+//       HEDR 9A99D93F0100000000080000
+//       CNAM "Author"
+//     GRUP type=0 label=WEAP extra=0000000000000000
+//       WEAP flags=00000000 form_id=00000800 vc=00000000 version=44 unknown=0
+//         EDID "IronSword"
+//         DATA 19000000
+//
+// Subrecord data that is a null-terminated ASCII string is written as a
+// quoted string without its null terminator, and other data is written as
+// hex. The data of compressed records is written as hex on a single
+// "compressed" line, as it can't be recompressed to the same bytes.
+
+use std::iter::Peekable;
+
+use byteorder::{ByteOrder, LittleEndian, WriteBytesExt};
+
+use error::Error;
+use game_id::GameId;
+use record::{header_length, Record, RecordHeader};
+use subrecord::Subrecord;
+
+const INDENT: &str = "  ";
+const COMPRESSED_DATA_PREFIX: &str = "compressed ";
+
+pub fn plugin_to_text(game_id: GameId, input: &[u8]) -> Result<String, Error> {
+    let mut text = String::new();
+    let mut remaining_input = input;
+
+    while !remaining_input.is_empty() {
+        remaining_input = write_group_or_record(&mut text, game_id, remaining_input, 0)?;
+    }
+
+    Ok(text)
+}
+
+pub fn plugin_from_text(game_id: GameId, text: &str) -> Result<Vec<u8>, Error> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .filter(|&(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| parse_line(index + 1, line))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .peekable();
+
+    let mut output = Vec::new();
+    read_groups_and_records(&mut lines, game_id, 0, &mut output)?;
+
+    match lines.next() {
+        Some(line) => Err(Error::TextParsingError(line.number)),
+        None => Ok(output),
+    }
+}
+
+fn write_group_or_record<'a>(
+    text: &mut String,
+    game_id: GameId,
+    input: &'a [u8],
+    depth: usize,
+) -> Result<&'a [u8], Error> {
+    if game_id != GameId::Morrowind && input.starts_with(b"GRUP") {
+        write_group(text, game_id, input, depth)
+    } else {
+        write_record(text, game_id, input, depth)
+    }
+}
+
+// Group headers are the same length as record headers.
+fn write_group<'a>(
+    text: &mut String,
+    game_id: GameId,
+    input: &'a [u8],
+    depth: usize,
+) -> Result<&'a [u8], Error> {
+    let header_length = header_length(game_id);
+    if input.len() < header_length {
+        return Err(Error::ParsingIncomplete);
+    }
+
+    let size = LittleEndian::read_u32(&input[4..]) as usize;
+    if size < header_length {
+        return Err(Error::ParsingError);
+    } else if input.len() < size {
+        return Err(Error::ParsingIncomplete);
+    }
+
+    let group_type = LittleEndian::read_u32(&input[12..]);
+    let label = if group_type == 0 {
+        to_token(&input[8..12])?
+    } else {
+        format!("{:08X}", LittleEndian::read_u32(&input[8..]))
+    };
+
+    text.push_str(&INDENT.repeat(depth));
+    text.push_str(&format!(
+        "GRUP type={} label={} extra={}\n",
+        group_type,
+        label,
+        to_hex(&input[16..header_length])
+    ));
+
+    let mut contents = &input[header_length..size];
+    while !contents.is_empty() {
+        contents = write_group_or_record(text, game_id, contents, depth + 1)?;
+    }
+
+    Ok(&input[size..])
+}
+
+fn write_record<'a>(
+    text: &mut String,
+    game_id: GameId,
+    input: &'a [u8],
+    depth: usize,
+) -> Result<&'a [u8], Error> {
+    let header = RecordHeader::parse(input, game_id)?.1;
+    let size = header_length(game_id) + header.size_of_subrecords() as usize;
+    if input.len() < size {
+        return Err(Error::ParsingIncomplete);
+    }
+
+    text.push_str(&INDENT.repeat(depth));
+    text.push_str(&to_token(header.record_type().as_bytes())?);
+    text.push_str(&format!(" flags={:08X}", header.flags()));
+    if game_id != GameId::Morrowind {
+        text.push_str(&format!(" form_id={:08X}", header.form_id()));
+    }
+    text.push_str(&format!(" vc={:08X}", header.version_control_info()));
+    if has_form_version(game_id) {
+        text.push_str(&format!(
+            " version={} unknown={}",
+            header.form_version(),
+            header.unknown()
+        ));
+    }
+    text.push('\n');
+
+    let indent = INDENT.repeat(depth + 1);
+    if header.are_subrecords_compressed() {
+        text.push_str(&indent);
+        text.push_str(COMPRESSED_DATA_PREFIX);
+        text.push_str(&to_hex(&input[header_length(game_id)..size]));
+        text.push('\n');
+    } else {
+        let record = Record::parse(&input[..size], game_id, false)?.1;
+        for subrecord in record.subrecords() {
+            text.push_str(&indent);
+            text.push_str(&to_token(subrecord.subrecord_type().as_bytes())?);
+            if !subrecord.data().is_empty() {
+                text.push(' ');
+                text.push_str(&data_to_text(subrecord.data()));
+            }
+            text.push('\n');
+        }
+    }
+
+    Ok(&input[size..])
+}
+
+struct Line<'a> {
+    number: usize,
+    depth: usize,
+    content: &'a str,
+}
+
+fn parse_line(number: usize, line: &str) -> Result<Line<'_>, Error> {
+    let content = line.trim_start_matches(' ');
+    let indent_length = line.len() - content.len();
+    let depth = indent_length / INDENT.len();
+
+    if depth * INDENT.len() != indent_length || content.starts_with(char::is_whitespace) {
+        return Err(Error::TextParsingError(number));
+    }
+
+    Ok(Line {
+        number,
+        depth,
+        content: content.trim_end(),
+    })
+}
+
+fn read_groups_and_records<'a, I: Iterator<Item = Line<'a>>>(
+    lines: &mut Peekable<I>,
+    game_id: GameId,
+    depth: usize,
+    output: &mut Vec<u8>,
+) -> Result<(), Error> {
+    while let Some(line) = next_line_at_depth(lines, depth)? {
+        if game_id != GameId::Morrowind && line.content.starts_with("GRUP ") {
+            read_group(lines, game_id, &line, output)?;
+        } else {
+            read_record(lines, game_id, &line, output)?;
+        }
+    }
+
+    Ok(())
+}
+
+// Lines that are indented further than expected are an error, while lines
+// that are indented less belong to a parent group or record.
+fn next_line_at_depth<'a, I: Iterator<Item = Line<'a>>>(
+    lines: &mut Peekable<I>,
+    depth: usize,
+) -> Result<Option<Line<'a>>, Error> {
+    match lines.peek() {
+        Some(line) if line.depth > depth => return Err(Error::TextParsingError(line.number)),
+        Some(line) if line.depth < depth => return Ok(None),
+        _ => {}
+    }
+
+    Ok(lines.next())
+}
+
+fn read_group<'a, I: Iterator<Item = Line<'a>>>(
+    lines: &mut Peekable<I>,
+    game_id: GameId,
+    line: &Line,
+    output: &mut Vec<u8>,
+) -> Result<(), Error> {
+    let error = || Error::TextParsingError(line.number);
+    let fields = Fields::parse(&line.content[5..]);
+
+    let group_type = fields.u32("type", 10).ok_or_else(error)?;
+    let label = fields.value("label").ok_or_else(error)?;
+    let label = if group_type == 0 {
+        from_token(label).ok_or_else(error)?
+    } else {
+        let label = u32::from_str_radix(label, 16).map_err(|_| error())?;
+        let mut bytes = vec![0; 4];
+        LittleEndian::write_u32(&mut bytes, label);
+        bytes
+    };
+    let extra = fields.value("extra").and_then(from_hex).ok_or_else(error)?;
+    if extra.len() != header_length(game_id) - 16 {
+        return Err(error());
+    }
+
+    let start = output.len();
+    output.extend_from_slice(b"GRUP\0\0\0\0");
+    output.extend_from_slice(&label);
+    output.write_u32::<LittleEndian>(group_type)?;
+    output.extend_from_slice(&extra);
+
+    read_groups_and_records(lines, game_id, line.depth + 1, output)?;
+
+    let size = (output.len() - start) as u32;
+    LittleEndian::write_u32(&mut output[start + 4..], size);
+
+    Ok(())
+}
+
+fn read_record<'a, I: Iterator<Item = Line<'a>>>(
+    lines: &mut Peekable<I>,
+    game_id: GameId,
+    line: &Line,
+    output: &mut Vec<u8>,
+) -> Result<(), Error> {
+    let error = || Error::TextParsingError(line.number);
+
+    let record_type = line.content.get(..4).ok_or_else(error)?;
+    if from_token(record_type).is_none() {
+        return Err(error());
+    }
+    let fields = Fields::parse(&line.content[4..]);
+
+    let form_id = if game_id == GameId::Morrowind {
+        0
+    } else {
+        fields.u32("form_id", 16).ok_or_else(error)?
+    };
+    let (form_version, unknown) = if has_form_version(game_id) {
+        (
+            fields.u16("version").ok_or_else(error)?,
+            fields.u16("unknown").ok_or_else(error)?,
+        )
+    } else {
+        (0, 0)
+    };
+
+    let header = RecordHeader::new(
+        record_type,
+        fields.u32("flags", 16).ok_or_else(error)?,
+        form_id,
+        fields.u32("vc", 16).ok_or_else(error)?,
+        form_version,
+        unknown,
+    );
+
+    if header.are_subrecords_compressed() {
+        let data_line = next_line_at_depth(lines, line.depth + 1)?.ok_or_else(error)?;
+        let data = if data_line.content.starts_with(COMPRESSED_DATA_PREFIX) {
+            from_hex(&data_line.content[COMPRESSED_DATA_PREFIX.len()..])
+        } else {
+            None
+        };
+        let data = data.ok_or(Error::TextParsingError(data_line.number))?;
+
+        header.write(output, game_id, data.len() as u32)?;
+        output.extend_from_slice(&data);
+    } else {
+        let mut subrecords = Vec::new();
+        while let Some(subrecord_line) = next_line_at_depth(lines, line.depth + 1)? {
+            let subrecord = parse_subrecord(subrecord_line.content)
+                .ok_or(Error::TextParsingError(subrecord_line.number))?;
+            subrecords.push(subrecord);
+        }
+
+        Record::new(header, subrecords, game_id).write(output, game_id)?;
+    }
+
+    Ok(())
+}
+
+fn parse_subrecord(content: &str) -> Option<Subrecord> {
+    let subrecord_type = content.get(..4)?;
+    from_token(subrecord_type)?;
+
+    let data = match &content[4..] {
+        "" => Vec::new(),
+        value if value.starts_with(' ') => data_from_text(&value[1..])?,
+        _ => return None,
+    };
+
+    Some(Subrecord::from_data(subrecord_type, data))
+}
+
+fn has_form_version(game_id: GameId) -> bool {
+    game_id != GameId::Morrowind && game_id != GameId::Oblivion
+}
+
+// The space-separated key=value pairs that follow a group or record's type.
+struct Fields<'a>(Vec<(&'a str, &'a str)>);
+
+impl<'a> Fields<'a> {
+    fn parse(text: &'a str) -> Fields<'a> {
+        Fields(
+            text.split_whitespace()
+                .filter_map(|field| {
+                    let mut parts = field.splitn(2, '=');
+                    match (parts.next(), parts.next()) {
+                        (Some(key), Some(value)) => Some((key, value)),
+                        _ => None,
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    fn value(&self, key: &str) -> Option<&'a str> {
+        self.0.iter().find(|&&(k, _)| k == key).map(|&(_, v)| v)
+    }
+
+    fn u32(&self, key: &str, radix: u32) -> Option<u32> {
+        self.value(key)
+            .and_then(|v| u32::from_str_radix(v, radix).ok())
+    }
+
+    fn u16(&self, key: &str) -> Option<u16> {
+        self.value(key).and_then(|v| v.parse().ok())
+    }
+}
+
+// Record, subrecord and group types must be four printable ASCII characters
+// that aren't spaces to be written as text.
+fn to_token(bytes: &[u8]) -> Result<String, Error> {
+    if bytes.len() == 4 && bytes.iter().all(|b| b.is_ascii_graphic()) {
+        Ok(bytes.iter().map(|b| *b as char).collect())
+    } else {
+        Err(Error::ParsingError)
+    }
+}
+
+fn from_token(token: &str) -> Option<Vec<u8>> {
+    if token.len() == 4 && token.bytes().all(|b| b.is_ascii_graphic()) {
+        Some(token.as_bytes().to_vec())
+    } else {
+        None
+    }
+}
+
+fn data_to_text(data: &[u8]) -> String {
+    match data.split_last() {
+        Some((&0, string)) if string.iter().all(|b| *b == b' ' || b.is_ascii_graphic()) => {
+            let mut text = String::with_capacity(data.len() + 2);
+            text.push('"');
+            for byte in string {
+                if *byte == b'"' || *byte == b'\\' {
+                    text.push('\\');
+                }
+                text.push(*byte as char);
+            }
+            text.push('"');
+            text
+        }
+        _ => to_hex(data),
+    }
+}
+
+fn data_from_text(text: &str) -> Option<Vec<u8>> {
+    if !text.starts_with('"') {
+        return from_hex(text);
+    }
+
+    if text.len() < 2 || !text.ends_with('"') {
+        return None;
+    }
+
+    let mut data = Vec::with_capacity(text.len() - 1);
+    let mut bytes = text[1..text.len() - 1].bytes();
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'\\' => match bytes.next() {
+                Some(b'"') => data.push(b'"'),
+                Some(b'\\') => data.push(b'\\'),
+                _ => return None,
+            },
+            b'"' => return None,
+            _ => data.push(byte),
+        }
+    }
+    data.push(0);
+
+    Some(data)
+}
+
+fn to_hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.is_ascii() {
+        return None;
+    }
+
+    text.as_bytes()
+        .chunks(2)
+        .map(|c| match c {
+            [high, low] => Some(hex_digit(*high)? << 4 | hex_digit(*low)?),
+            _ => None,
+        })
+        .collect()
+}
+
+fn hex_digit(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|d| d as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SKYRIM_PLUGIN: &[u8] = b"TES4\x1D\x00\x00\x00\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x2C\x00\x00\x00HEDR\x0C\x00\x9A\x99\xD9\x3F\x01\x00\x00\x00\x00\x08\x00\x00CNAM\x05\x00A \"B\x00GRUP\x66\x00\x00\x00WEAP\x00\x00\x00\x00\x12\x34\x00\x00\x00\x00\x00\x00WEAP\x16\x00\x00\x00\x00\x00\x00\x00\x00\x08\x00\x00\x01\x00\x00\x00\x2C\x00\x02\x00EDID\x0A\x00IronSword\x00DATA\x00\x00WEAP\x08\x00\x00\x00\x00\x00\x04\x00\x01\x08\x00\x00\x00\x00\x00\x00\x2C\x00\x00\x00\x04\x00\x00\x00\x78\x9C\x03\x00";
+
+    const SKYRIM_TEXT: &str =
+        "TES4 flags=00000001 form_id=00000000 vc=00000000 version=44 unknown=0
+  HEDR 9A99D93F0100000000080000
+  CNAM \"A \\\"B\"
+GRUP type=0 label=WEAP extra=1234000000000000
+  WEAP flags=00000000 form_id=00000800 vc=00000001 version=44 unknown=2
+    EDID \"IronSword\"
+    DATA
+  WEAP flags=00040000 form_id=00000801 vc=00000000 version=44 unknown=0
+    compressed 04000000789C0300
+";
+
+    #[test]
+    fn plugin_to_text_should_write_groups_records_and_subrecords_on_indented_lines() {
+        let text = plugin_to_text(GameId::Skyrim, SKYRIM_PLUGIN).unwrap();
+
+        assert_eq!(SKYRIM_TEXT, text);
+    }
+
+    #[test]
+    fn plugin_from_text_should_give_the_original_plugin_bytes() {
+        let bytes = plugin_from_text(GameId::Skyrim, SKYRIM_TEXT).unwrap();
+
+        assert_eq!(SKYRIM_PLUGIN, bytes.as_slice());
+    }
+
+    #[test]
+    fn plugin_text_should_round_trip_for_morrowind_and_oblivion_plugins() {
+        let morrowind_plugin =
+            b"TES3\x0D\x00\x00\x00\x07\x00\x00\x00\x01\x00\x00\x00CNAM\x05\x00\x00\x00Name\x00";
+        let text = plugin_to_text(GameId::Morrowind, morrowind_plugin).unwrap();
+
+        assert_eq!("TES3 flags=00000001 vc=00000007\n  CNAM \"Name\"\n", text);
+        assert_eq!(
+            &morrowind_plugin[..],
+            plugin_from_text(GameId::Morrowind, &text)
+                .unwrap()
+                .as_slice()
+        );
+
+        let oblivion_plugin = b"TES4\x06\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x03\x00\x00\x00HEDR\x00\x00GRUP\x14\x00\x00\x00\x01\x08\x00\x00\x09\x00\x00\x00\x05\x00\x00\x00";
+        let text = plugin_to_text(GameId::Oblivion, oblivion_plugin).unwrap();
+
+        assert_eq!(
+            "TES4 flags=00000000 form_id=00000000 vc=00000003\n  HEDR\nGRUP type=9 label=00000801 extra=05000000\n",
+            text
+        );
+        assert_eq!(
+            &oblivion_plugin[..],
+            plugin_from_text(GameId::Oblivion, &text)
+                .unwrap()
+                .as_slice()
+        );
+    }
+
+    #[test]
+    fn plugin_from_text_should_error_with_the_number_of_an_invalid_line() {
+        let text = "TES4 flags=00000001 form_id=00000000 vc=00000000 version=44 unknown=0\n\n    HEDR 00\n";
+        match plugin_from_text(GameId::Skyrim, text) {
+            Err(Error::TextParsingError(3)) => {}
+            x => panic!("Unexpected result: {:?}", x),
+        }
+
+        let text =
+            "TES4 flags=00000001 form_id=00000000 vc=00000000 version=44 unknown=0\n  HEDR 0\n";
+        match plugin_from_text(GameId::Skyrim, text) {
+            Err(Error::TextParsingError(2)) => {}
+            x => panic!("Unexpected result: {:?}", x),
+        }
+
+        let text = "TES4 flags=00000001 form_id=00000000 vc=00000000\n";
+        match plugin_from_text(GameId::Skyrim, text) {
+            Err(Error::TextParsingError(1)) => {}
+            x => panic!("Unexpected result: {:?}", x),
+        }
+    }
+
+    #[test]
+    fn plugin_text_should_round_trip_a_real_plugin() {
+        let data = include_bytes!("../testing-plugins/Skyrim/Data/Blank.esm");
+
+        let text = plugin_to_text(GameId::Skyrim, data).unwrap();
+
+        assert_eq!(
+            &data[..],
+            plugin_from_text(GameId::Skyrim, &text).unwrap().as_slice()
+        );
+    }
+}